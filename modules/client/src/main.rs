@@ -1,11 +1,12 @@
 //! main.rs
 /// Main entrypoint of the client
 use anyhow::Result;
-use inquire::Text;
 use inquire::{validator::Validation, CustomType};
 use inquire::{InquireError, Select};
+use inquire::{Password, Text};
 use log::{error, info};
 use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::Client;
 use serde_json::json;
 use std::{env::var, process::exit};
@@ -83,15 +84,45 @@ macro_rules! delete {
     }};
 }
 
+/// Log in as a staff member, the client sends the token with every request.
+async fn login(base_url: &str) -> Result<Client> {
+    let name = Text::new("Staff name:").prompt()?;
+    let secret = Password::new("PIN or password:")
+        .without_confirmation()
+        .prompt()?;
+    let response = Client::new()
+        .post(format!("{}/auth/login", base_url))
+        .json(&json!({"name": name, "secret": secret}))
+        .send()
+        .await?
+        .json::<serde_json::Value>()
+        .await?;
+    let Some(token) = response["data"]["token"].as_str() else {
+        anyhow::bail!("Login failed: {}", response["error"]);
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", token))?,
+    );
+    Ok(Client::builder().default_headers(headers).build()?)
+}
+
 /// Main entrypoint of client.
 #[tokio::main]
 async fn main() {
-    let client = Client::new();
-
     let host = var("HOST_URL").unwrap_or_else(|_| HOST_URL.to_string());
     let port = var("HOST_PORT").unwrap_or_else(|_| HOST_PORT.to_string());
     let base_url = format!("http://{}:{}/api/v1", host, port);
 
+    let client = match login(&base_url).await {
+        Ok(client) => client,
+        Err(err) => {
+            error!("{}", err);
+            exit(1)
+        }
+    };
+
     loop {
         let options: Vec<&str> = vec![
             "Item operations",
@@ -134,7 +165,7 @@ async fn main() {
                                     &url,
                                     json!({
                                         "description": description.to_string(),
-                                        "price": price,
+                                        "price": {"amount": price, "currency": "EUR"},
                                     }),
                                     "Added item"
                                 );
@@ -276,7 +307,7 @@ async fn main() {
                                     "1"
                                 );
                                 let mut items = vec![];
-                                let url = format!("{}/orders", base_url);
+                                let url = format!("{}/tables/{}/tickets", base_url, table);
                                 // The nesting is getting out of control ...
                                 loop {
                                     let item: i32 = iprompt!(
@@ -287,18 +318,8 @@ async fn main() {
                                     );
                                     let quantity: i32 =
                                         iprompt!(i32, "Enter quantity:", "Quantity of items", "1");
-                                    // TODO: Change structure of the order creation to accept a list of items instead.
-                                    // E.g.
-                                    // {
-                                    //  "table_id": 1,
-                                    //  "items": [
-                                    //    { "item_id": 101, "quantity": 2 },
-                                    //    { "item_id": 102, "quantity": 5 }
-                                    //  ]
-                                    //}
 
                                     items.push(json!({
-                                        "table_id": table,
                                         "item_id": item,
                                         "quantity": quantity
                                     }));
@@ -311,7 +332,7 @@ async fn main() {
                                         break;
                                     }
                                 }
-                                post!(client, &url, json!(items), "Created orders");
+                                post!(client, &url, json!({ "items": items }), "Created ticket");
                             }
                             "Get all orders" => {
                                 let url = format!("{}/orders", base_url);
@@ -344,9 +365,9 @@ async fn main() {
                     let tracker = TaskTracker::new();
 
                     let items = vec![
-                        json!({ "description": "Yakitori", "price": 3 }),
-                        json!({ "description": "Takoyaki", "price": 3 }),
-                        json!({ "description": "Highball", "price": 2 }),
+                        json!({ "description": "Yakitori", "price": {"amount": 3, "currency": "EUR"} }),
+                        json!({ "description": "Takoyaki", "price": {"amount": 3, "currency": "EUR"} }),
+                        json!({ "description": "Highball", "price": {"amount": 2, "currency": "EUR"} }),
                     ];
                     let itemurl = format!("{}/items", base_url);
                     for item in items {
@@ -368,6 +389,8 @@ async fn main() {
                         }
                         let checkin_url = format!("{}/tables/check_in", cloned_url);
                         let checkout_url = format!("{}/tables/{}/check_out", base_url, id);
+                        let bill_url = format!("{}/tables/{}/bill", base_url, id);
+                        let payment_url = format!("{}/tables/{}/payments", base_url, id);
                         let order_url = format!("{}/orders", cloned_url);
                         tracker.spawn(async move {
                             post!(
@@ -385,6 +408,28 @@ async fn main() {
                                 );
                                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                             }
+                            // The table can only check out once the bill is paid.
+                            let total = match cloned_client.get(&bill_url).send().await {
+                                Ok(res) => res
+                                    .json::<serde_json::Value>()
+                                    .await
+                                    .map(|bill| {
+                                        bill["data"]["total"]["amount"].as_i64().unwrap_or(0)
+                                    })
+                                    .unwrap_or(0),
+                                Err(_) => 0,
+                            };
+                            if total > 0 {
+                                post!(
+                                    cloned_client,
+                                    &payment_url,
+                                    json!({
+                                        "tender": "card",
+                                        "amount": {"amount": total, "currency": "EUR"},
+                                    }),
+                                    format!("Paid table {:?}", id)
+                                );
+                            }
                             post!(
                                 cloned_client,
                                 &checkout_url,
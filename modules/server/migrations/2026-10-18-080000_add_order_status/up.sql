@@ -0,0 +1,3 @@
+ALTER TABLE orders
+  ADD COLUMN status TEXT NOT NULL DEFAULT 'pending'
+  CHECK (status IN ('pending', 'cooking', 'ready', 'served', 'cancelled'));
@@ -0,0 +1,8 @@
+CREATE TABLE tickets (
+  id SERIAL PRIMARY KEY,
+  published_at TEXT NOT NULL,
+  table_id INTEGER NOT NULL REFERENCES tables(id)
+);
+
+-- Orders placed through the flat order endpoint don't belong to a ticket.
+ALTER TABLE orders ADD COLUMN ticket_id INTEGER REFERENCES tickets(id);
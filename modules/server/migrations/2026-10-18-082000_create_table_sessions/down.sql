@@ -0,0 +1,16 @@
+DROP TRIGGER tickets_need_open_session ON tickets;
+DROP TRIGGER orders_need_open_session ON orders;
+DROP TRIGGER tables_closed_is_final ON tables;
+DROP FUNCTION ensure_table_session_open();
+DROP INDEX tables_one_open_session;
+ALTER TABLE tables DROP CONSTRAINT tables_session_closed;
+
+ALTER TABLE tables ADD COLUMN checked_in_time TEXT;
+UPDATE tables SET checked_in_time = to_char(opened_at, 'YYYY-MM-DD"T"HH24:MI:SS.USOF'),
+  total = COALESCE(total, -1);
+ALTER TABLE tables
+  ALTER COLUMN checked_in_time SET NOT NULL,
+  ALTER COLUMN total SET NOT NULL,
+  DROP COLUMN status,
+  DROP COLUMN closed_at,
+  DROP COLUMN opened_at;
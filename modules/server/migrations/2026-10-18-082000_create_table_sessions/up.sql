@@ -0,0 +1,57 @@
+-- A row in `tables` is a session: a party sitting at a table number from check-in to checkout.
+ALTER TABLE tables
+  ADD COLUMN opened_at TIMESTAMPTZ,
+  ADD COLUMN closed_at TIMESTAMPTZ,
+  ADD COLUMN status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed')),
+  ALTER COLUMN total DROP NOT NULL;
+
+UPDATE tables SET opened_at = checked_in_time::timestamptz;
+
+-- Checked out tables had their total set, we don't know when they left.
+UPDATE tables SET status = 'closed', closed_at = opened_at WHERE total <> -1;
+
+-- Only the latest check-in of a table number stays open.
+UPDATE tables SET status = 'closed', closed_at = opened_at, total = 0
+WHERE status = 'open' AND EXISTS (
+  SELECT 1 FROM tables newer
+  WHERE newer.table_number = tables.table_number
+    AND newer.status = 'open'
+    AND newer.id > tables.id
+);
+
+UPDATE tables SET total = NULL WHERE status = 'open';
+
+ALTER TABLE tables
+  ALTER COLUMN opened_at SET NOT NULL,
+  ALTER COLUMN opened_at SET DEFAULT now(),
+  DROP COLUMN checked_in_time,
+  ADD CONSTRAINT tables_session_closed CHECK (
+    (status = 'open' AND closed_at IS NULL AND total IS NULL)
+    OR (status = 'closed' AND closed_at IS NOT NULL AND total IS NOT NULL)
+  );
+
+CREATE UNIQUE INDEX tables_one_open_session ON tables (table_number) WHERE status = 'open';
+
+-- A closed session is final, nothing may be changed or ordered on it anymore.
+CREATE FUNCTION ensure_table_session_open() RETURNS trigger AS $$
+DECLARE
+  closed BOOLEAN;
+BEGIN
+  IF TG_TABLE_NAME = 'tables' THEN
+    closed := OLD.status = 'closed';
+  ELSE
+    closed := EXISTS (SELECT 1 FROM tables WHERE id = NEW.table_id AND status = 'closed');
+  END IF;
+  IF closed THEN
+    RAISE EXCEPTION 'table session is closed' USING ERRCODE = 'check_violation';
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tables_closed_is_final BEFORE UPDATE ON tables
+  FOR EACH ROW EXECUTE PROCEDURE ensure_table_session_open();
+CREATE TRIGGER orders_need_open_session BEFORE INSERT OR UPDATE OF table_id ON orders
+  FOR EACH ROW EXECUTE PROCEDURE ensure_table_session_open();
+CREATE TRIGGER tickets_need_open_session BEFORE INSERT OR UPDATE OF table_id ON tickets
+  FOR EACH ROW EXECUTE PROCEDURE ensure_table_session_open();
@@ -0,0 +1,18 @@
+CREATE OR REPLACE FUNCTION ensure_table_session_open() RETURNS trigger AS $$
+DECLARE
+  closed BOOLEAN;
+BEGIN
+  IF TG_TABLE_NAME = 'tables' THEN
+    closed := OLD.status = 'closed';
+  ELSE
+    closed := EXISTS (SELECT 1 FROM tables WHERE id = NEW.table_id AND status = 'closed');
+  END IF;
+  IF closed THEN
+    RAISE EXCEPTION 'table session is closed' USING ERRCODE = 'check_violation';
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TABLE bill_lines;
+DROP TABLE bills;
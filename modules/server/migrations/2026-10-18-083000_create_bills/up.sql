@@ -0,0 +1,35 @@
+-- The bill handed to a table at checkout, a session has at most one.
+CREATE TABLE bills (
+  id SERIAL PRIMARY KEY,
+  table_id INTEGER NOT NULL UNIQUE REFERENCES tables(id),
+  subtotal INTEGER NOT NULL,
+  total INTEGER NOT NULL,
+  issued_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE bill_lines (
+  id SERIAL PRIMARY KEY,
+  bill_id INTEGER NOT NULL REFERENCES bills(id),
+  kind TEXT NOT NULL CHECK (kind IN ('item', 'charge')),
+  description TEXT NOT NULL,
+  unit_price INTEGER NOT NULL,
+  quantity INTEGER NOT NULL,
+  amount INTEGER NOT NULL
+);
+
+-- Lock the session while writing to it, so an order can't sneak in while the table checks out.
+CREATE OR REPLACE FUNCTION ensure_table_session_open() RETURNS trigger AS $$
+DECLARE
+  closed BOOLEAN;
+BEGIN
+  IF TG_TABLE_NAME = 'tables' THEN
+    closed := OLD.status = 'closed';
+  ELSE
+    SELECT status = 'closed' INTO closed FROM tables WHERE id = NEW.table_id FOR SHARE;
+  END IF;
+  IF closed THEN
+    RAISE EXCEPTION 'table session is closed' USING ERRCODE = 'check_violation';
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,32 @@
+DROP TABLE bill_parts;
+
+CREATE OR REPLACE FUNCTION ensure_table_session_open() RETURNS trigger AS $$
+DECLARE
+  closed BOOLEAN;
+BEGIN
+  IF TG_TABLE_NAME = 'tables' THEN
+    closed := OLD.status = 'closed';
+  ELSE
+    SELECT status = 'closed' INTO closed FROM tables WHERE id = NEW.table_id FOR SHARE;
+  END IF;
+  IF closed THEN
+    RAISE EXCEPTION 'table session is closed' USING ERRCODE = 'check_violation';
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Sessions being billed in parts are put back to open.
+UPDATE tables SET status = 'open' WHERE status = 'billing';
+
+DROP INDEX tables_one_open_session;
+CREATE UNIQUE INDEX tables_one_open_session ON tables (table_number) WHERE status = 'open';
+
+ALTER TABLE tables
+  DROP CONSTRAINT tables_session_closed,
+  ADD CONSTRAINT tables_session_closed CHECK (
+    (status = 'open' AND closed_at IS NULL AND total IS NULL)
+    OR (status = 'closed' AND closed_at IS NOT NULL AND total IS NOT NULL)
+  ),
+  DROP CONSTRAINT tables_status_check,
+  ADD CONSTRAINT tables_status_check CHECK (status IN ('open', 'closed'));
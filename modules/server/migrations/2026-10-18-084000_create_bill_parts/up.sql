@@ -0,0 +1,39 @@
+-- A table whose bill is being settled in parts takes no more orders, but is still seated.
+ALTER TABLE tables
+  DROP CONSTRAINT tables_status_check,
+  ADD CONSTRAINT tables_status_check CHECK (status IN ('open', 'billing', 'closed')),
+  DROP CONSTRAINT tables_session_closed,
+  ADD CONSTRAINT tables_session_closed CHECK (
+    (status <> 'closed' AND closed_at IS NULL AND total IS NULL)
+    OR (status = 'closed' AND closed_at IS NOT NULL AND total IS NOT NULL)
+  );
+
+DROP INDEX tables_one_open_session;
+CREATE UNIQUE INDEX tables_one_open_session ON tables (table_number) WHERE status <> 'closed';
+
+CREATE OR REPLACE FUNCTION ensure_table_session_open() RETURNS trigger AS $$
+DECLARE
+  closed BOOLEAN;
+BEGIN
+  IF TG_TABLE_NAME = 'tables' THEN
+    closed := OLD.status = 'closed';
+  ELSE
+    SELECT status <> 'open' INTO closed FROM tables WHERE id = NEW.table_id FOR SHARE;
+  END IF;
+  IF closed THEN
+    RAISE EXCEPTION 'table session is closed' USING ERRCODE = 'check_violation';
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Share of a bill to be settled by one guest.
+CREATE TABLE bill_parts (
+  id SERIAL PRIMARY KEY,
+  bill_id INTEGER NOT NULL REFERENCES bills(id),
+  position INTEGER NOT NULL,
+  amount INTEGER NOT NULL,
+  order_ids INTEGER[] NOT NULL DEFAULT '{}',
+  paid_at TIMESTAMPTZ,
+  UNIQUE (bill_id, position)
+);
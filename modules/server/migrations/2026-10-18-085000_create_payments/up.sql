@@ -0,0 +1,14 @@
+-- Money received for a table session, tips are kept apart from what is paid towards the bill.
+CREATE TABLE payments (
+  id SERIAL PRIMARY KEY,
+  table_id INTEGER NOT NULL REFERENCES tables(id),
+  bill_part_id INTEGER REFERENCES bill_parts(id),
+  tender TEXT NOT NULL CHECK (tender IN ('cash', 'card', 'voucher')),
+  amount INTEGER NOT NULL CHECK (amount > 0),
+  tip INTEGER NOT NULL DEFAULT 0 CHECK (tip >= 0),
+  reference TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  voided_at TIMESTAMPTZ
+);
+
+CREATE INDEX payments_table_id ON payments (table_id);
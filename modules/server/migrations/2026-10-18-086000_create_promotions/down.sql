@@ -0,0 +1,10 @@
+DELETE FROM bill_lines WHERE kind = 'discount';
+ALTER TABLE bill_lines
+  DROP COLUMN promotion_id,
+  DROP CONSTRAINT bill_lines_kind_check,
+  ADD CONSTRAINT bill_lines_kind_check CHECK (kind IN ('item', 'charge'));
+
+DROP TABLE table_coupons;
+DROP TABLE promotions;
+ALTER TABLE items DROP COLUMN category_id;
+DROP TABLE categories;
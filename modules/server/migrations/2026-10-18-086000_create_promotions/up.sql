@@ -0,0 +1,42 @@
+CREATE TABLE categories (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE
+);
+
+ALTER TABLE items ADD COLUMN category_id INTEGER REFERENCES categories(id);
+
+-- A discount rule, applied to the order lines of a table whenever its bill is computed.
+CREATE TABLE promotions (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL,
+  kind TEXT NOT NULL CHECK (kind IN ('percentage', 'fixed_amount', 'buy_get_free')),
+  percent INTEGER CHECK (percent BETWEEN 1 AND 100),
+  amount INTEGER CHECK (amount > 0),
+  buy_quantity INTEGER CHECK (buy_quantity > 0),
+  free_quantity INTEGER CHECK (free_quantity > 0),
+  item_id INTEGER REFERENCES items(id),
+  category_id INTEGER REFERENCES categories(id),
+  -- Local time of day the order must be placed in, e.g. a happy hour.
+  starts_at TIME,
+  ends_at TIME,
+  -- Coupons only apply to tables that redeemed their code.
+  code TEXT UNIQUE,
+  active BOOLEAN NOT NULL DEFAULT true,
+  CHECK ((starts_at IS NULL) = (ends_at IS NULL)),
+  CHECK (
+    (kind = 'percentage' AND percent IS NOT NULL)
+    OR (kind = 'fixed_amount' AND amount IS NOT NULL)
+    OR (kind = 'buy_get_free' AND buy_quantity IS NOT NULL AND free_quantity IS NOT NULL)
+  )
+);
+
+CREATE TABLE table_coupons (
+  table_id INTEGER NOT NULL REFERENCES tables(id),
+  promotion_id INTEGER NOT NULL REFERENCES promotions(id),
+  PRIMARY KEY (table_id, promotion_id)
+);
+
+ALTER TABLE bill_lines
+  DROP CONSTRAINT bill_lines_kind_check,
+  ADD CONSTRAINT bill_lines_kind_check CHECK (kind IN ('item', 'discount', 'charge')),
+  ADD COLUMN promotion_id INTEGER REFERENCES promotions(id);
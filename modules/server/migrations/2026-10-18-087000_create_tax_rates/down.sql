@@ -0,0 +1,8 @@
+DELETE FROM bill_lines WHERE kind IN ('tax', 'included_tax');
+ALTER TABLE bill_lines
+  DROP COLUMN tax_rate_id,
+  DROP CONSTRAINT bill_lines_kind_check,
+  ADD CONSTRAINT bill_lines_kind_check CHECK (kind IN ('item', 'discount', 'charge'));
+
+DROP TABLE tax_rates;
+ALTER TABLE tables DROP COLUMN service;
@@ -0,0 +1,24 @@
+ALTER TABLE tables
+  ADD COLUMN service TEXT NOT NULL DEFAULT 'dine_in' CHECK (service IN ('dine_in', 'takeout'));
+
+-- A tax on the items of a category, or on a single item which then ignores its category's taxes.
+CREATE TABLE tax_rates (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL,
+  -- Hundredths of a percent, 1250 is 12.5%.
+  basis_points INTEGER NOT NULL CHECK (basis_points BETWEEN 0 AND 10000),
+  -- Whether prices already include the tax.
+  inclusive BOOLEAN NOT NULL DEFAULT false,
+  -- Only tax this kind of service, both when null.
+  service TEXT CHECK (service IN ('dine_in', 'takeout')),
+  item_id INTEGER REFERENCES items(id),
+  category_id INTEGER REFERENCES categories(id),
+  active BOOLEAN NOT NULL DEFAULT true,
+  CHECK ((item_id IS NULL) <> (category_id IS NULL))
+);
+
+ALTER TABLE bill_lines
+  DROP CONSTRAINT bill_lines_kind_check,
+  ADD CONSTRAINT bill_lines_kind_check
+    CHECK (kind IN ('item', 'discount', 'tax', 'included_tax', 'charge')),
+  ADD COLUMN tax_rate_id INTEGER REFERENCES tax_rates(id);
@@ -0,0 +1,29 @@
+ALTER TABLE promotions
+  DROP COLUMN currency,
+  ALTER COLUMN amount TYPE INTEGER;
+
+ALTER TABLE payments
+  DROP COLUMN currency,
+  ALTER COLUMN tip TYPE INTEGER,
+  ALTER COLUMN amount TYPE INTEGER;
+
+ALTER TABLE bill_parts
+  DROP COLUMN currency,
+  ALTER COLUMN amount TYPE INTEGER;
+
+ALTER TABLE bill_lines
+  ALTER COLUMN amount TYPE INTEGER,
+  ALTER COLUMN unit_price TYPE INTEGER;
+
+ALTER TABLE bills
+  DROP COLUMN currency,
+  ALTER COLUMN total TYPE INTEGER,
+  ALTER COLUMN subtotal TYPE INTEGER;
+
+ALTER TABLE tables
+  DROP COLUMN currency,
+  ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE items
+  DROP COLUMN currency,
+  ALTER COLUMN price TYPE INTEGER;
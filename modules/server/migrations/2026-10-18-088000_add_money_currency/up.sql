@@ -0,0 +1,33 @@
+-- Amounts are in minor units of the currency of their row, cents for EUR.
+ALTER TABLE items
+  ALTER COLUMN price TYPE BIGINT,
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR' CHECK (currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE tables
+  ALTER COLUMN total TYPE BIGINT,
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR' CHECK (currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE bills
+  ALTER COLUMN subtotal TYPE BIGINT,
+  ALTER COLUMN total TYPE BIGINT,
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR' CHECK (currency ~ '^[A-Z]{3}$');
+
+-- Bill lines are in the currency of their bill.
+ALTER TABLE bill_lines
+  ALTER COLUMN unit_price TYPE BIGINT,
+  ALTER COLUMN amount TYPE BIGINT;
+
+ALTER TABLE bill_parts
+  ALTER COLUMN amount TYPE BIGINT,
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR' CHECK (currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE payments
+  ALTER COLUMN amount TYPE BIGINT,
+  ALTER COLUMN tip TYPE BIGINT,
+  ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR' CHECK (currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE promotions
+  ALTER COLUMN amount TYPE BIGINT,
+  ADD COLUMN currency TEXT CHECK (currency ~ '^[A-Z]{3}$');
+UPDATE promotions SET currency = 'EUR' WHERE amount IS NOT NULL;
+ALTER TABLE promotions ADD CHECK ((amount IS NULL) = (currency IS NULL));
@@ -0,0 +1,3 @@
+DROP TABLE order_modifiers;
+DROP TABLE modifiers;
+DROP TABLE modifier_groups;
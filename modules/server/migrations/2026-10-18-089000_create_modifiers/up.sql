@@ -0,0 +1,27 @@
+-- A choice offered with an item, e.g. its toppings.
+-- A group asking for at least one selection is required.
+CREATE TABLE modifier_groups (
+  id SERIAL PRIMARY KEY,
+  item_id INTEGER NOT NULL REFERENCES items(id),
+  name TEXT NOT NULL,
+  min_selections INTEGER NOT NULL DEFAULT 0 CHECK (min_selections >= 0),
+  max_selections INTEGER NOT NULL DEFAULT 1 CHECK (max_selections >= 1),
+  UNIQUE (item_id, name),
+  CHECK (min_selections <= max_selections)
+);
+
+-- An option of a group, changing the price of the item by its delta.
+CREATE TABLE modifiers (
+  id SERIAL PRIMARY KEY,
+  group_id INTEGER NOT NULL REFERENCES modifier_groups(id),
+  name TEXT NOT NULL,
+  price_delta BIGINT NOT NULL DEFAULT 0,
+  currency TEXT NOT NULL DEFAULT 'EUR' CHECK (currency ~ '^[A-Z]{3}$'),
+  UNIQUE (group_id, name)
+);
+
+CREATE TABLE order_modifiers (
+  order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+  modifier_id INTEGER NOT NULL REFERENCES modifiers(id),
+  PRIMARY KEY (order_id, modifier_id)
+);
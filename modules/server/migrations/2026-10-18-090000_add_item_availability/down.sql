@@ -0,0 +1,3 @@
+ALTER TABLE items
+  DROP COLUMN remaining,
+  DROP COLUMN available;
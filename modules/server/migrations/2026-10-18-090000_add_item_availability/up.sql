@@ -0,0 +1,4 @@
+-- Whether an item can be ordered, and how many portions are left when they are counted.
+ALTER TABLE items
+  ADD COLUMN available BOOLEAN NOT NULL DEFAULT true,
+  ADD COLUMN remaining INTEGER CHECK (remaining >= 0);
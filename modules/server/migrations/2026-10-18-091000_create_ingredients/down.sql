@@ -0,0 +1,3 @@
+ALTER TABLE items DROP COLUMN stocked;
+DROP TABLE recipe_ingredients;
+DROP TABLE ingredients;
@@ -0,0 +1,20 @@
+-- Stock of an ingredient, counted in its unit, e.g. grams.
+-- It is reported low once it is at or below its threshold.
+CREATE TABLE ingredients (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  unit TEXT NOT NULL,
+  stock INTEGER NOT NULL DEFAULT 0 CHECK (stock >= 0),
+  low_stock INTEGER NOT NULL DEFAULT 0 CHECK (low_stock >= 0)
+);
+
+-- What goes into a single portion of an item.
+CREATE TABLE recipe_ingredients (
+  item_id INTEGER NOT NULL REFERENCES items(id),
+  ingredient_id INTEGER NOT NULL REFERENCES ingredients(id),
+  quantity INTEGER NOT NULL CHECK (quantity > 0),
+  PRIMARY KEY (item_id, ingredient_id)
+);
+
+-- Whether there is enough of every ingredient for a portion of the item.
+ALTER TABLE items ADD COLUMN stocked BOOLEAN NOT NULL DEFAULT true;
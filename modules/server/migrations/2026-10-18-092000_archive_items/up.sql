@@ -0,0 +1,2 @@
+-- An archived item is no longer on the menu, orders and bills still refer to it.
+ALTER TABLE items ADD COLUMN archived_at TIMESTAMPTZ;
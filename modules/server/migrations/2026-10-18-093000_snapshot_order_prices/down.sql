@@ -0,0 +1,6 @@
+DROP TABLE item_prices;
+
+ALTER TABLE orders
+  DROP COLUMN currency,
+  DROP COLUMN unit_price,
+  DROP COLUMN description;
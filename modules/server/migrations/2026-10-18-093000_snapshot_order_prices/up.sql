@@ -0,0 +1,28 @@
+-- An order keeps the description and unit price its item had when it was ordered.
+ALTER TABLE orders
+  ADD COLUMN description TEXT,
+  ADD COLUMN unit_price BIGINT,
+  ADD COLUMN currency TEXT CHECK (currency ~ '^[A-Z]{3}$');
+
+UPDATE orders
+SET description = items.description, unit_price = items.price, currency = items.currency
+FROM items
+WHERE items.id = orders.item_id;
+
+ALTER TABLE orders
+  ALTER COLUMN description SET NOT NULL,
+  ALTER COLUMN unit_price SET NOT NULL,
+  ALTER COLUMN currency SET NOT NULL;
+
+-- Every price an item had, each from the moment it took effect until the next one did.
+CREATE TABLE item_prices (
+  id SERIAL PRIMARY KEY,
+  item_id INTEGER NOT NULL REFERENCES items(id),
+  price BIGINT NOT NULL,
+  currency TEXT NOT NULL CHECK (currency ~ '^[A-Z]{3}$'),
+  effective_from TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Earlier prices are unknown, the current ones are known from now on.
+INSERT INTO item_prices (item_id, price, currency)
+SELECT id, price, currency FROM items;
@@ -0,0 +1,4 @@
+ALTER TABLE tables
+  DROP COLUMN party_size,
+  DROP CONSTRAINT tables_dining_table;
+DROP TABLE dining_tables;
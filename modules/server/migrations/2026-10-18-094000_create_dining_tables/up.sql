@@ -0,0 +1,22 @@
+-- The physical tables of the floor, a session in `tables` is a party seated at one of them.
+-- An inactive table is kept for its history, but nobody can be seated at it.
+CREATE TABLE dining_tables (
+  id SERIAL PRIMARY KEY,
+  table_number INTEGER NOT NULL UNIQUE,
+  seats INTEGER NOT NULL CHECK (seats > 0),
+  section TEXT NOT NULL DEFAULT 'main',
+  active BOOLEAN NOT NULL DEFAULT true
+);
+
+-- Any table number used to be accepted, the floor starts out with tables 1 to 100
+-- and every table number that was ever checked in.
+INSERT INTO dining_tables (table_number, seats)
+SELECT generate_series(1, 100), 4;
+INSERT INTO dining_tables (table_number, seats)
+SELECT DISTINCT table_number, 4 FROM tables
+ON CONFLICT (table_number) DO NOTHING;
+
+ALTER TABLE tables
+  ADD CONSTRAINT tables_dining_table FOREIGN KEY (table_number)
+    REFERENCES dining_tables(table_number),
+  ADD COLUMN party_size INTEGER CHECK (party_size > 0);
@@ -0,0 +1,2 @@
+ALTER TABLE tables DROP COLUMN reservation_id;
+DROP TABLE reservations;
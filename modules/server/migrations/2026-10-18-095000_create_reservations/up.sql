@@ -0,0 +1,20 @@
+-- A booking of a party for a time window, the table may be assigned later.
+-- Only booked reservations hold their table, overlapping bookings of a table are rejected.
+CREATE TABLE reservations (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL,
+  phone TEXT,
+  party_size INTEGER NOT NULL CHECK (party_size > 0),
+  starts_at TIMESTAMPTZ NOT NULL,
+  ends_at TIMESTAMPTZ NOT NULL,
+  table_number INTEGER REFERENCES dining_tables(table_number),
+  status TEXT NOT NULL DEFAULT 'booked' CHECK (status IN ('booked', 'seated', 'cancelled')),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  CONSTRAINT reservations_window CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX reservations_booked ON reservations (table_number, starts_at)
+  WHERE status = 'booked';
+
+-- The reservation a session was seated for.
+ALTER TABLE tables ADD COLUMN reservation_id INTEGER REFERENCES reservations(id);
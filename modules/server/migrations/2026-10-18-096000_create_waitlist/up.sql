@@ -0,0 +1,20 @@
+-- Walk-in parties waiting for a table, in the order they arrived.
+-- A party leaves the queue when it is seated at a table session, or removed.
+CREATE TABLE waitlist_entries (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL,
+  party_size INTEGER NOT NULL CHECK (party_size > 0),
+  contact TEXT,
+  status TEXT NOT NULL DEFAULT 'waiting' CHECK (status IN ('waiting', 'seated', 'removed')),
+  quoted_minutes INTEGER NOT NULL CHECK (quoted_minutes >= 0),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  left_at TIMESTAMPTZ,
+  table_id INTEGER REFERENCES tables(id),
+  CONSTRAINT waitlist_entries_left CHECK (
+    (status = 'waiting' AND left_at IS NULL AND table_id IS NULL)
+    OR (status = 'seated' AND left_at IS NOT NULL AND table_id IS NOT NULL)
+    OR (status = 'removed' AND left_at IS NOT NULL AND table_id IS NULL)
+  )
+);
+
+CREATE INDEX waitlist_entries_waiting ON waitlist_entries (id) WHERE status = 'waiting';
@@ -0,0 +1,15 @@
+-- History of parties moving tables, kept with the session that goes on.
+-- A transfer moves a session to another table number, a merge closes the session
+-- seated at `from_number` and moves everything it ordered to the one at `to_number`.
+CREATE TABLE table_moves (
+  id SERIAL PRIMARY KEY,
+  kind TEXT NOT NULL CHECK (kind IN ('transfer', 'merge')),
+  table_id INTEGER NOT NULL REFERENCES tables(id),
+  from_number INTEGER NOT NULL,
+  to_number INTEGER NOT NULL,
+  merged_table_id INTEGER REFERENCES tables(id),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  CONSTRAINT table_moves_merged CHECK ((kind = 'merge') = (merged_table_id IS NOT NULL))
+);
+
+CREATE INDEX table_moves_table ON table_moves (table_id);
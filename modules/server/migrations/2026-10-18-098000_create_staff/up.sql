@@ -0,0 +1,10 @@
+-- Staff accounts, members log in with a PIN or password and only its hash is kept.
+-- The role decides which parts of the API a member may use.
+CREATE TABLE staff_members (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  role TEXT NOT NULL CHECK (role IN ('waiter', 'kitchen', 'manager', 'admin')),
+  secret_hash TEXT NOT NULL,
+  active BOOLEAN NOT NULL DEFAULT TRUE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
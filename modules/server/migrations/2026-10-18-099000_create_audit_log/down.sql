@@ -0,0 +1,4 @@
+DROP TRIGGER audit_entries_no_truncate ON audit_entries;
+DROP TRIGGER audit_entries_append_only ON audit_entries;
+DROP FUNCTION audit_entries_append_only();
+DROP TABLE audit_entries;
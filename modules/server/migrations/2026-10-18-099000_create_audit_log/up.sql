@@ -0,0 +1,29 @@
+-- Every write made through the API, who made it from which device and what it changed.
+-- Entries are only ever appended, the log can't be changed afterwards.
+CREATE TABLE audit_entries (
+  id SERIAL PRIMARY KEY,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  actor_id INTEGER REFERENCES staff_members(id),
+  device TEXT,
+  action TEXT NOT NULL,
+  entity TEXT NOT NULL,
+  entity_id INTEGER NOT NULL,
+  before JSONB,
+  after JSONB,
+  trace_id TEXT
+);
+
+CREATE INDEX audit_entries_entity ON audit_entries (entity, entity_id);
+CREATE INDEX audit_entries_actor ON audit_entries (actor_id, created_at);
+CREATE INDEX audit_entries_created_at ON audit_entries (created_at);
+
+CREATE FUNCTION audit_entries_append_only() RETURNS trigger AS $$
+BEGIN
+  RAISE EXCEPTION 'audit log is append-only' USING ERRCODE = 'check_violation';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_entries_append_only BEFORE UPDATE OR DELETE ON audit_entries
+  FOR EACH ROW EXECUTE PROCEDURE audit_entries_append_only();
+CREATE TRIGGER audit_entries_no_truncate BEFORE TRUNCATE ON audit_entries
+  FOR EACH STATEMENT EXECUTE PROCEDURE audit_entries_append_only();
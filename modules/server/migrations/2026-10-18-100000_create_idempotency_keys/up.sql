@@ -0,0 +1,14 @@
+-- Idempotency keys of requests that may be retried, e.g. by tablets on a flaky connection.
+-- A key is stored with a fingerprint of the request it came with, and the response once
+-- there is one, so a retry is answered with the original response instead of being handled again.
+CREATE TABLE idempotency_keys (
+  key TEXT PRIMARY KEY,
+  fingerprint TEXT NOT NULL,
+  -- Both unset while the request is still being handled.
+  status_code SMALLINT,
+  body BYTEA,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idempotency_keys_expires_at ON idempotency_keys (expires_at);
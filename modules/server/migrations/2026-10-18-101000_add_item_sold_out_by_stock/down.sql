@@ -0,0 +1 @@
+ALTER TABLE items DROP COLUMN sold_out_by_stock;
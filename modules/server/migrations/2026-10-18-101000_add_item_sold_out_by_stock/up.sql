@@ -0,0 +1,3 @@
+-- Whether an item was sold out by its counted portions running out, rather than by hand.
+-- Only those come back when portions are given back.
+ALTER TABLE items ADD COLUMN sold_out_by_stock BOOLEAN NOT NULL DEFAULT false;
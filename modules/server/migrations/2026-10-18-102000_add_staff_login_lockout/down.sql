@@ -0,0 +1,3 @@
+ALTER TABLE staff_members
+  DROP COLUMN locked_until,
+  DROP COLUMN failed_logins;
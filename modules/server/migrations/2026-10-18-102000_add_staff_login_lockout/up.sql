@@ -0,0 +1,4 @@
+-- Failed logins since the last successful one, the account is locked for a while after too many.
+ALTER TABLE staff_members
+  ADD COLUMN failed_logins INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN locked_until TIMESTAMPTZ;
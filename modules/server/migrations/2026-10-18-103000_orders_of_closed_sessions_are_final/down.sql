@@ -0,0 +1,2 @@
+DROP TRIGGER orders_of_closed_session_are_final ON orders;
+DROP FUNCTION ensure_order_session_open();
@@ -0,0 +1,15 @@
+-- Orders of a closed session were sold, they can't be cancelled or deleted anymore.
+CREATE FUNCTION ensure_order_session_open() RETURNS trigger AS $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM tables WHERE id = OLD.table_id AND status = 'closed') THEN
+    RAISE EXCEPTION 'table session is closed' USING ERRCODE = 'check_violation';
+  END IF;
+  IF TG_OP = 'DELETE' THEN
+    RETURN OLD;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER orders_of_closed_session_are_final BEFORE UPDATE OF status OR DELETE ON orders
+  FOR EACH ROW EXECUTE PROCEDURE ensure_order_session_open();
@@ -0,0 +1,7 @@
+DELETE FROM idempotency_keys;
+
+ALTER TABLE idempotency_keys
+  DROP CONSTRAINT idempotency_keys_pkey,
+  DROP COLUMN route,
+  DROP COLUMN staff_id,
+  ADD PRIMARY KEY (key);
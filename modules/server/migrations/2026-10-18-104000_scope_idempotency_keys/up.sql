@@ -0,0 +1,10 @@
+-- An idempotency key is only good for the staff member and the route it was first used with,
+-- so tablets can't run into, or replay, each other's keys.
+-- Keys kept so far can't be told apart, they are forgotten.
+DELETE FROM idempotency_keys;
+
+ALTER TABLE idempotency_keys
+  DROP CONSTRAINT idempotency_keys_pkey,
+  ADD COLUMN staff_id INTEGER NOT NULL REFERENCES staff_members(id) ON DELETE CASCADE,
+  ADD COLUMN route TEXT NOT NULL,
+  ADD PRIMARY KEY (staff_id, route, key);
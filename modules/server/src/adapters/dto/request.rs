@@ -1,19 +1,199 @@
 //! adapters/dto/request.rs
 
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::domain::entities::audit::AuditSort;
+use crate::domain::entities::item::ItemSort;
+use crate::domain::entities::order::{OrderSort, OrderStatus};
+use crate::domain::entities::payment::Tender;
+use crate::domain::entities::promotion::PromotionKind;
+use crate::domain::entities::staff::Role;
+use crate::domain::entities::table::{ServiceType, SessionStatus, TableSort};
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use crate::domain::money::{Currency, Money};
+use crate::domain::page::{PageRequest, Sort};
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct OrderCreateRequest {
     pub(crate) item_id: i32,
     pub(crate) table_id: i32,
     pub(crate) quantity: i32,
+    /// Modifiers chosen for the item, e.g. its toppings.
+    #[serde(default)]
+    pub(crate) modifiers: Vec<i32>,
+}
+
+/// How a batch of orders is committed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BatchMode {
+    /// Either every order is created, or none are.
+    #[default]
+    Atomic,
+    /// Create the orders that can be created, and report the others.
+    Partial,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct OrderBatchQuery {
+    #[serde(default)]
+    pub(crate) mode: BatchMode,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TicketLineRequest {
+    pub(crate) item_id: i32,
+    pub(crate) quantity: i32,
+    /// Modifiers chosen for the item, e.g. its toppings.
+    #[serde(default)]
+    pub(crate) modifiers: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TicketCreateRequest {
+    pub(crate) items: Vec<TicketLineRequest>,
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct ItemCreateRequest {
     pub(crate) description: String,
-    pub(crate) price: i32,
+    pub(crate) price: Money,
+    pub(crate) category_id: Option<i32>,
+}
+
+/// Changes to an item, what is not set is left as it is.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ItemUpdateRequest {
+    pub(crate) description: Option<String>,
+    /// In the currency the item is priced in.
+    pub(crate) price: Option<Money>,
+    pub(crate) category_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ItemAvailabilityRequest {
+    /// Whether the item can be ordered, it is sold out ("86") otherwise.
+    pub(crate) available: bool,
+    /// Portions left, not counted when not set.
+    pub(crate) remaining: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct IngredientCreateRequest {
+    pub(crate) name: String,
+    /// Unit the stock is counted in, e.g. `g`.
+    pub(crate) unit: String,
+    #[serde(default)]
+    pub(crate) stock: i32,
+    /// Stock at or below which the ingredient is reported low.
+    #[serde(default)]
+    pub(crate) low_stock: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RestockRequest {
+    /// Added to the stock, in the unit of the ingredient.
+    pub(crate) quantity: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeLineRequest {
+    pub(crate) ingredient_id: i32,
+    /// Used for a single portion, in the unit of the ingredient.
+    pub(crate) quantity: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeRequest {
+    pub(crate) ingredients: Vec<RecipeLineRequest>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ModifierCreateRequest {
+    pub(crate) name: String,
+    /// Added to the price of the item, free when not set.
+    pub(crate) price_delta: Option<Money>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ModifierGroupCreateRequest {
+    pub(crate) name: String,
+    /// Whether at least one modifier has to be chosen.
+    #[serde(default)]
+    pub(crate) required: bool,
+    /// Fewest modifiers to choose, one when required and none otherwise.
+    pub(crate) min_selections: Option<i32>,
+    /// Most modifiers to choose, the minimum but at least one when not set.
+    pub(crate) max_selections: Option<i32>,
+    pub(crate) modifiers: Vec<ModifierCreateRequest>,
+}
+
+impl ModifierGroupCreateRequest {
+    /// The fewest and most modifiers to choose.
+    pub(crate) fn selections(&self) -> ServerResult<(i32, i32)> {
+        let min = self.min_selections.unwrap_or(i32::from(self.required));
+        if self.required != (min > 0) {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "Only a required group has a minimum number of selections!",
+            ));
+        }
+        Ok((min, self.max_selections.unwrap_or(min.max(1))))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CategoryCreateRequest {
+    pub(crate) name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PromotionCreateRequest {
+    pub(crate) name: String,
+    pub(crate) kind: PromotionKind,
+    /// Percentage off, for `percentage` promotions.
+    pub(crate) percent: Option<i32>,
+    /// Amount off, for `fixed_amount` promotions.
+    pub(crate) amount: Option<Money>,
+    /// For `buy_get_free` promotions.
+    pub(crate) buy_quantity: Option<i32>,
+    pub(crate) free_quantity: Option<i32>,
+    /// Only apply to this item.
+    pub(crate) item_id: Option<i32>,
+    /// Only apply to items of this category.
+    pub(crate) category_id: Option<i32>,
+    /// Only apply to orders placed in this local time window, e.g. `17:00:00` to `19:00:00`.
+    pub(crate) starts_at: Option<NaiveTime>,
+    pub(crate) ends_at: Option<NaiveTime>,
+    /// Coupon code, the promotion then only applies to tables that redeem it.
+    pub(crate) code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TaxRateCreateRequest {
+    pub(crate) name: String,
+    /// Hundredths of a percent, 1250 is 12.5%.
+    pub(crate) basis_points: i32,
+    /// Whether prices already include the tax.
+    #[serde(default)]
+    pub(crate) inclusive: bool,
+    /// Only tax this kind of service, both when not set.
+    pub(crate) service: Option<ServiceType>,
+    /// Tax this item, its category's taxes no longer apply to it.
+    pub(crate) item_id: Option<i32>,
+    /// Tax the items of this category.
+    pub(crate) category_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CouponRedeemRequest {
+    pub(crate) code: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -24,4 +204,322 @@ pub(crate) struct TableGetRequest {
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct TableCreateRequest {
     pub(crate) table_number: i32,
+    #[serde(default)]
+    pub(crate) service: ServiceType,
+    /// Currency the table pays in, the configured default if not given.
+    pub(crate) currency: Option<Currency>,
+    /// Number of guests, checked against the seats of the table.
+    /// The size of the party booked if not given.
+    pub(crate) party_size: Option<i32>,
+    /// Reservation the party checks in for.
+    /// The one the table is booked for at the time if not given.
+    pub(crate) reservation_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TableTransferRequest {
+    /// Free table the party moves to.
+    pub(crate) table_number: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TableMergeRequest {
+    /// Table of the party that joins, its session is closed.
+    pub(crate) table_number: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DiningTableCreateRequest {
+    pub(crate) table_number: i32,
+    pub(crate) seats: i32,
+    /// Area of the floor, `main` if not given.
+    pub(crate) section: Option<String>,
+}
+
+/// Changes to a table, what is not set is left as it is.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DiningTableUpdateRequest {
+    pub(crate) seats: Option<i32>,
+    pub(crate) section: Option<String>,
+    /// Whether parties can be seated at the table.
+    pub(crate) active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ReservationCreateRequest {
+    pub(crate) name: String,
+    pub(crate) phone: Option<String>,
+    pub(crate) party_size: i32,
+    pub(crate) starts_at: DateTime<Utc>,
+    pub(crate) ends_at: DateTime<Utc>,
+    /// Table booked for the party, one can be assigned later.
+    pub(crate) table_number: Option<i32>,
+}
+
+/// Changes to a reservation, what is not set is left as it is.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ReservationUpdateRequest {
+    pub(crate) name: Option<String>,
+    pub(crate) phone: Option<String>,
+    pub(crate) party_size: Option<i32>,
+    pub(crate) starts_at: Option<DateTime<Utc>>,
+    pub(crate) ends_at: Option<DateTime<Utc>>,
+    pub(crate) table_number: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct AvailabilityQuery {
+    pub(crate) starts_at: DateTime<Utc>,
+    pub(crate) ends_at: DateTime<Utc>,
+    pub(crate) party_size: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct WaitlistCreateRequest {
+    pub(crate) name: String,
+    pub(crate) party_size: i32,
+    /// Phone number or pager label to call the party by.
+    pub(crate) contact: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct WaitlistSeatRequest {
+    pub(crate) table_number: i32,
+    /// Currency the table pays in, the configured default if not given.
+    pub(crate) currency: Option<Currency>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct OrderStatusRequest {
+    pub(crate) status: OrderStatus,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct OrderStatusQuery {
+    /// Comma separated statuses to include, e.g. `pending,cooking,ready`.
+    pub(crate) status: Option<String>,
+}
+
+impl OrderStatusQuery {
+    /// Parse the requested statuses, an empty list means every status.
+    pub(crate) fn statuses(&self) -> ServerResult<Vec<OrderStatus>> {
+        parse_list(self.status.as_ref())
+    }
+}
+
+/// Parse a comma separated list, an empty list when none is given.
+fn parse_list<T>(list: Option<&String>) -> ServerResult<Vec<T>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let Some(list) = list else {
+        return Ok(vec![]);
+    };
+    list.split(',')
+        .map(|s| {
+            s.trim().parse().map_err(|error: T::Err| {
+                ApiError::new(ErrorCode::InvalidRequest, error.to_string())
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct OrderListQuery {
+    /// Comma separated statuses to include, e.g. `pending,cooking,ready`.
+    pub(crate) status: Option<String>,
+    /// Number of the table the orders were taken at.
+    pub(crate) table_number: Option<i32>,
+    pub(crate) item_id: Option<i32>,
+    /// Orders taken from this time on.
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Orders taken before this time.
+    pub(crate) until: Option<DateTime<Utc>>,
+    /// `id`, `item_id` or `quantity`, descending with a `-` in front, by `id` if not given.
+    pub(crate) sort: Option<String>,
+    /// Orders on the page.
+    pub(crate) limit: Option<i64>,
+    /// Where the page starts, as given with the previous page.
+    pub(crate) cursor: Option<String>,
+}
+
+impl OrderListQuery {
+    /// Parse the requested statuses, an empty list means every status.
+    pub(crate) fn statuses(&self) -> ServerResult<Vec<OrderStatus>> {
+        parse_list(self.status.as_ref())
+    }
+
+    pub(crate) fn page(&self) -> ServerResult<PageRequest<OrderSort>> {
+        PageRequest::parse(
+            self.limit,
+            self.sort.as_ref(),
+            self.cursor.as_ref(),
+            Sort::asc(OrderSort::Id),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct TableListQuery {
+    pub(crate) table_number: Option<i32>,
+    /// Comma separated statuses to include, e.g. `open,billing`.
+    pub(crate) status: Option<String>,
+    /// Sessions opened from this time on.
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Sessions opened before this time.
+    pub(crate) until: Option<DateTime<Utc>>,
+    /// `opened_at` or `table_number`, descending with a `-` in front,
+    /// the latest opened first if not given.
+    pub(crate) sort: Option<String>,
+    /// Sessions on the page.
+    pub(crate) limit: Option<i64>,
+    /// Where the page starts, as given with the previous page.
+    pub(crate) cursor: Option<String>,
+}
+
+impl TableListQuery {
+    /// Parse the requested statuses, an empty list means every status.
+    pub(crate) fn statuses(&self) -> ServerResult<Vec<SessionStatus>> {
+        parse_list(self.status.as_ref())
+    }
+
+    pub(crate) fn page(&self) -> ServerResult<PageRequest<TableSort>> {
+        PageRequest::parse(
+            self.limit,
+            self.sort.as_ref(),
+            self.cursor.as_ref(),
+            Sort::desc(TableSort::OpenedAt),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ItemListQuery {
+    /// Comma separated ids of the items to include.
+    pub(crate) id: Option<String>,
+    pub(crate) category_id: Option<i32>,
+    /// Only items that can be ordered, or only sold out ones.
+    pub(crate) available: Option<bool>,
+    /// `id`, `description` or `price`, descending with a `-` in front, by `id` if not given.
+    pub(crate) sort: Option<String>,
+    /// Items on the page.
+    pub(crate) limit: Option<i64>,
+    /// Where the page starts, as given with the previous page.
+    pub(crate) cursor: Option<String>,
+}
+
+impl ItemListQuery {
+    pub(crate) fn ids(&self) -> ServerResult<Vec<i32>> {
+        parse_list(self.id.as_ref())
+    }
+
+    pub(crate) fn page(&self) -> ServerResult<PageRequest<ItemSort>> {
+        PageRequest::parse(
+            self.limit,
+            self.sort.as_ref(),
+            self.cursor.as_ref(),
+            Sort::asc(ItemSort::Id),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PaymentCreateRequest {
+    pub(crate) tender: Tender,
+    /// Paid towards the bill.
+    pub(crate) amount: Money,
+    /// Paid on top of the bill.
+    pub(crate) tip: Option<Money>,
+    /// Card authorization or voucher code.
+    pub(crate) reference: Option<String>,
+    /// Part of a split bill the payment is for.
+    pub(crate) part_id: Option<i32>,
+}
+
+/// Log in by name with a PIN or password.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub(crate) struct LoginRequest {
+    pub(crate) name: String,
+    pub(crate) secret: String,
+}
+
+impl fmt::Debug for LoginRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginRequest")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub(crate) struct StaffCreateRequest {
+    pub(crate) name: String,
+    pub(crate) role: Role,
+    /// PIN or password the member logs in with.
+    pub(crate) secret: String,
+}
+
+impl fmt::Debug for StaffCreateRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaffCreateRequest")
+            .field("name", &self.name)
+            .field("role", &self.role)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Changes to a staff account, what is not set is left as it is.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub(crate) struct StaffUpdateRequest {
+    pub(crate) role: Option<Role>,
+    /// Whether the member can log in.
+    pub(crate) active: Option<bool>,
+    /// New PIN or password.
+    pub(crate) secret: Option<String>,
+}
+
+impl fmt::Debug for StaffUpdateRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaffUpdateRequest")
+            .field("role", &self.role)
+            .field("active", &self.active)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct AuditQuery {
+    /// Kind of entity, e.g. `order`.
+    pub(crate) entity: Option<String>,
+    pub(crate) entity_id: Option<i32>,
+    /// Staff member who made the writes.
+    pub(crate) actor_id: Option<i32>,
+    /// Writes made from this time on.
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Writes made before this time.
+    pub(crate) until: Option<DateTime<Utc>>,
+    /// `created_at`, descending with a `-` in front, the latest first if not given.
+    pub(crate) sort: Option<String>,
+    /// Entries on the page.
+    pub(crate) limit: Option<i64>,
+    /// Where the page starts, as given with the previous page.
+    pub(crate) cursor: Option<String>,
+}
+
+impl AuditQuery {
+    pub(crate) fn page(&self) -> ServerResult<PageRequest<AuditSort>> {
+        PageRequest::parse(
+            self.limit,
+            self.sort.as_ref(),
+            self.cursor.as_ref(),
+            Sort::desc(AuditSort::CreatedAt),
+        )
+    }
 }
@@ -2,9 +2,26 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::domain::entities::item::Item;
+use crate::domain::entities::audit::AuditEntry;
+use crate::domain::entities::bill::Bill;
+use crate::domain::entities::bill_part::BillPart;
+use crate::domain::entities::category::Category;
+use crate::domain::entities::dining_table::DiningTable;
+use crate::domain::entities::ingredient::{Ingredient, RecipeIngredient};
+use crate::domain::entities::item::{Item, ItemPrice};
+use crate::domain::entities::modifier::{Modifier, ModifierGroup};
 use crate::domain::entities::order::Order;
+use crate::domain::entities::payment::Payment;
+use crate::domain::entities::promotion::Promotion;
+use crate::domain::entities::reservation::Reservation;
+use crate::domain::entities::staff::{Session, Staff};
 use crate::domain::entities::table::Table;
+use crate::domain::entities::table_move::TableMove;
+use crate::domain::entities::tax::TaxRate;
+use crate::domain::entities::ticket::Ticket;
+use crate::domain::entities::waitlist::WaitlistEntry;
+use crate::domain::error::ApiError;
+use crate::domain::money::Money;
 
 // TODO move these to a shared lib.
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -12,6 +29,32 @@ pub(crate) struct OrderResponse {
     pub(crate) data: Vec<Order>,
 }
 
+/// Page of orders.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct OrdersPageResponse {
+    pub(crate) data: Vec<Order>,
+    pub(crate) page: PageInfo,
+    pub(crate) links: PageLinks,
+}
+
+/// How a page of a collection was cut.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PageInfo {
+    pub(crate) limit: i64,
+    /// Sort the collection is in, e.g. `-opened_at`.
+    pub(crate) sort: String,
+    /// Cursor to get the next page with, none on the last page.
+    pub(crate) next_cursor: Option<String>,
+}
+
+/// Links to a page of a collection and to the page after it.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PageLinks {
+    #[serde(rename = "self")]
+    pub(crate) current: String,
+    pub(crate) next: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct ItemResponse {
     pub(crate) data: Item,
@@ -22,17 +65,291 @@ pub(crate) struct ItemsResponse {
     pub(crate) data: Vec<Item>,
 }
 
+/// Page of the menu.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ItemsPageResponse {
+    pub(crate) data: Vec<Item>,
+    pub(crate) page: PageInfo,
+    pub(crate) links: PageLinks,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ItemPricesResponse {
+    pub(crate) data: Vec<ItemPrice>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeResponse {
+    pub(crate) data: Vec<RecipeIngredient>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct IngredientResponse {
+    pub(crate) data: Ingredient,
+}
+
+/// Stock of an ingredient, and whether it is running low.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct InventoryLine {
+    #[serde(flatten)]
+    pub(crate) ingredient: Ingredient,
+    pub(crate) low: bool,
+}
+
+impl InventoryLine {
+    pub(crate) fn new(ingredient: Ingredient) -> Self {
+        InventoryLine {
+            low: ingredient.low(),
+            ingredient,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct InventoryResponse {
+    pub(crate) data: Vec<InventoryLine>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ModifierGroupDetails {
+    #[serde(flatten)]
+    pub(crate) group: ModifierGroup,
+    pub(crate) required: bool,
+    pub(crate) modifiers: Vec<Modifier>,
+}
+
+impl ModifierGroupDetails {
+    pub(crate) fn new(group: ModifierGroup, modifiers: Vec<Modifier>) -> Self {
+        ModifierGroupDetails {
+            required: group.required(),
+            group,
+            modifiers,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ModifierGroupResponse {
+    pub(crate) data: ModifierGroupDetails,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ModifierGroupsResponse {
+    pub(crate) data: Vec<ModifierGroupDetails>,
+}
+
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct TableResponse {
     pub(crate) data: Table,
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
-pub(crate) struct TablesResponse {
+pub(crate) struct TablesPageResponse {
     pub(crate) data: Vec<Table>,
+    pub(crate) page: PageInfo,
+    pub(crate) links: PageLinks,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TableHistoryResponse {
+    pub(crate) data: Vec<TableMove>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DiningTableResponse {
+    pub(crate) data: DiningTable,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DiningTablesResponse {
+    pub(crate) data: Vec<DiningTable>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ReservationResponse {
+    pub(crate) data: Reservation,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ReservationsResponse {
+    pub(crate) data: Vec<Reservation>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct WaitlistEntryResponse {
+    pub(crate) data: WaitlistEntry,
+}
+
+/// A waiting party, and how long it is expected to wait from now.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct WaitlistLine {
+    #[serde(flatten)]
+    pub(crate) entry: WaitlistEntry,
+    /// Not known when no table seats the party.
+    pub(crate) wait_minutes: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct WaitlistResponse {
+    pub(crate) data: Vec<WaitlistLine>,
+}
+
+/// A party seated from the waitlist, with the session it was checked in at.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct SeatedParty {
+    #[serde(flatten)]
+    pub(crate) entry: WaitlistEntry,
+    pub(crate) table: Table,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct SeatedPartyResponse {
+    pub(crate) data: SeatedParty,
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct CheckoutResponse {
-    pub(crate) data: i32,
+    pub(crate) data: Bill,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct BillResponse {
+    pub(crate) data: Bill,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct BillSplitDetails {
+    pub(crate) bill: Bill,
+    pub(crate) parts: Vec<BillPart>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct BillSplitResponse {
+    pub(crate) data: BillSplitDetails,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TicketDetails {
+    #[serde(flatten)]
+    pub(crate) ticket: Ticket,
+    pub(crate) items: Vec<Order>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TicketResponse {
+    pub(crate) data: TicketDetails,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TicketsResponse {
+    pub(crate) data: Vec<TicketDetails>,
+}
+
+/// Outcome of a single line in a batch of orders.
+#[derive(Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OrderLineStatus {
+    /// The order was created.
+    Created,
+    /// The order could not be created.
+    Failed,
+    /// The order could have been created, but another line failed the batch.
+    RolledBack,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct OrderLineResult {
+    /// Position of the line in the request.
+    pub(crate) index: usize,
+    pub(crate) item_id: i32,
+    pub(crate) table_id: i32,
+    pub(crate) status: OrderLineStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) order_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct OrderBatchResponse {
+    /// Whether the created orders were stored.
+    pub(crate) committed: bool,
+    pub(crate) data: Vec<OrderLineResult>,
+}
+
+/// Body of a batch of orders that created nothing.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(untagged)]
+pub(crate) enum OrderBatchRejection {
+    /// Every line was tried, see why each failed.
+    Lines(OrderBatchResponse),
+    /// The request was refused before any line was tried, e.g. its idempotency key was
+    /// used for another request.
+    Refused(ApiError),
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PaymentResponse {
+    pub(crate) data: Payment,
+}
+
+/// Payments of a table, with what is paid and what is still due.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PaymentsResponse {
+    pub(crate) paid: Money,
+    pub(crate) tips: Money,
+    pub(crate) due: Money,
+    pub(crate) data: Vec<Payment>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CategoryResponse {
+    pub(crate) data: Category,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CategoriesResponse {
+    pub(crate) data: Vec<Category>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PromotionResponse {
+    pub(crate) data: Promotion,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PromotionsResponse {
+    pub(crate) data: Vec<Promotion>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TaxRateResponse {
+    pub(crate) data: TaxRate,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct TaxRatesResponse {
+    pub(crate) data: Vec<TaxRate>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct SessionResponse {
+    pub(crate) data: Session,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct StaffResponse {
+    pub(crate) data: Staff,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct StaffListResponse {
+    pub(crate) data: Vec<Staff>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct AuditEntriesResponse {
+    pub(crate) data: Vec<AuditEntry>,
+    pub(crate) page: PageInfo,
+    pub(crate) links: PageLinks,
 }
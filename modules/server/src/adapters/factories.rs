@@ -1,186 +1,1246 @@
 //! Repository implementations.
 
+use std::fmt;
+
 use diesel::prelude::*;
 
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
+use diesel::sql_types::Timestamptz;
 use diesel::PgConnection;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
 use diesel::{ExpressionMethods, QueryDsl, SelectableHelper};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use log::error;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast::Sender;
 
+use crate::application::config::{
+    DEFAULT_TURN_MINUTES, LOGIN_LOCKOUT_MINUTES, MAX_FAILED_LOGINS, RESERVATION_EARLY_MINUTES,
+    TOKEN_TTL_MINUTES, TURN_TIME_SESSIONS,
+};
 use crate::application::features::{get_all_active_tables, get_table};
-use crate::application::repo::{ItemRepository, OrderRepository, TableRepository};
+use crate::application::repo::{
+    AuditRepository, BillRepository, CategoryRepository, DiningTableRepository,
+    IdempotencyRepository, InventoryRepository, ItemRepository, ModifierRepository,
+    OrderRepository, PaymentRepository, PromotionRepository, ReservationRepository,
+    StaffRepository, TableRepository, TaxRateRepository, TicketRepository, WaitlistRepository,
+};
 use crate::db_conn;
-use crate::domain::entities::item::{Item, NewItem};
-use crate::domain::entities::order::{NewOrder, Order};
-use crate::domain::entities::table::{NewTable, Table};
+use crate::domain::entities::audit::{Actor, AuditEntry, AuditFilter, AuditSort, NewAuditEntry};
+use crate::domain::entities::bill::{
+    Bill, BillCharge, BillDiscount, BillItem, BillLineKind, BillTax, BilledOrder, NewBill,
+};
+use crate::domain::entities::bill_part::{BillPart, BillSplit};
+use crate::domain::entities::category::{Category, NewCategory};
+use crate::domain::entities::dining_table::{DiningTable, DiningTableChanges, NewDiningTable};
+use crate::domain::entities::idempotency::{Attempt, IdempotencyKey, NewIdempotencyKey};
+use crate::domain::entities::ingredient::{Ingredient, NewIngredient, RecipeIngredient};
+use crate::domain::entities::item::{
+    Item, ItemAvailability, ItemChanges, ItemFilter, ItemPrice, ItemSort, NewItem,
+};
+use crate::domain::entities::modifier::{
+    modified, validate, Modifier, ModifierGroup, NewModifier, NewModifierEntry, NewModifierGroup,
+};
+use crate::domain::entities::order::{
+    NewOrder, NewOrderLine, Order, OrderFilter, OrderSort, OrderStatus,
+};
+use crate::domain::entities::payment::{NewPayment, NewPaymentEntry, Payment};
+use crate::domain::entities::promotion::{discounts, NewPromotion, Promotion};
+use crate::domain::entities::reservation::{
+    NewReservation, Reservation, ReservationChanges, ReservationStatus,
+};
+use crate::domain::entities::staff::{
+    hash_secret, reject_secret, verify_secret, Claims, NewStaff, Role, Session, Staff, StaffChanges,
+};
+use crate::domain::entities::table::{NewTable, SessionStatus, Table, TableFilter, TableSort};
+use crate::domain::entities::table_move::{MoveKind, NewTableMove, TableMove};
+use crate::domain::entities::tax::{taxes, NewTaxRate, TaxRate};
+use crate::domain::entities::ticket::{NewTicket, NewTicketLine, Ticket};
+use crate::domain::entities::waitlist::{
+    estimate_waits, turn_time, NewWaitlistEntry, WaitlistEntry, WaitlistStatus,
+};
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use crate::domain::events::KitchenEvent;
+use crate::domain::money::{Currency, Money};
+use crate::domain::page::{Cursor, Page, PageRequest};
 
-use super::{ServerError, ServerResult};
-use log::error;
+/// Advisory lock servers take turns creating the first admin account with.
+const BOOTSTRAP_LOCK: i64 = 0x5354_4146;
 
-/// Macro database query with ServerError handling.
+/// Macro database query with ApiError handling.
+/// Optionally takes the error code to use when nothing was found.
 macro_rules! db_query {
     ($query:expr, $error:expr) => {
+        db_query!($query, ErrorCode::NotFound, $error)
+    };
+    ($query:expr, $not_found:expr, $error:expr) => {
         match $query {
             Ok(result) => Ok(result),
-            Err(err) => {
-                error!("{:?}", err);
-                Err(ServerError {
-                    error: $error.to_string(),
-                })
-            }
+            Err(err) => Err(ApiError::from_db(err, $not_found, $error)),
         }
     };
 }
 
-/// Macro database query (optional) with ServerError handling.
-macro_rules! db_query_optional {
-    ($query:expr, $error:expr, $rnone:expr) => {
-        match $query {
-            Ok(Some(result)) => Ok(result),
-            Ok(None) => Ok($rnone),
-            Err(err) => {
-                error!("{:?}", err);
-                Err(ServerError {
-                    error: $error.to_string(),
+/// Macro sorting a boxed query for a page, by `column` and then by `id`.
+/// The page starts after its cursor, and one more entry than fits on it is loaded
+/// to tell whether there is a next page.
+macro_rules! paginate {
+    ($query:expr, $page:expr, $column:expr, $id:expr, $value:ty) => {{
+        let mut query = $query;
+        if let Some(cursor) = &$page.after {
+            let value: $value = cursor.value()?;
+            query = if $page.sort.descending {
+                query.filter(
+                    $column
+                        .lt(value.clone())
+                        .or($column.eq(value).and($id.lt(cursor.id))),
+                )
+            } else {
+                query.filter(
+                    $column
+                        .gt(value.clone())
+                        .or($column.eq(value).and($id.gt(cursor.id))),
+                )
+            };
+        }
+        if $page.sort.descending {
+            query.order(($column.desc(), $id.desc()))
+        } else {
+            query.order(($column.asc(), $id.asc()))
+        }
+        .limit($page.limit + 1)
+    }};
+}
+
+/// When an order was taken, it is kept as the RFC 3339 time it was published at.
+fn ordered_at() -> SqlLiteral<Timestamptz> {
+    sql("orders.published_at::timestamptz")
+}
+
+/// Find the checked in table with the given number, it is an error if there is none.
+fn open_table(conn: &mut PgConnection, number: &i32) -> ServerResult<Vec<Table>> {
+    let table = db_query!(get_table(conn, number), "Unable to find tables!")?;
+    if table.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::TableNotFound,
+            format!("Unable to find table {}!", number),
+        ));
+    }
+    Ok(table)
+}
+
+/// Only managers delete or cancel orders the kitchen started.
+fn ensure_deletable(found: &[Order], manages: bool) -> ServerResult {
+    match found.iter().find(|o| o.status.is_started()) {
+        Some(o) if !manages => Err(ApiError::new(
+            ErrorCode::Forbidden,
+            format!(
+                "Only managers can remove order {}, it is {}!",
+                o.id, o.status
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+tokio::task_local! {
+    /// Who the request being handled is made by.
+    pub(crate) static ACTOR: Actor;
+}
+
+/// What an entity looks like, as kept in the audit log.
+fn snapshot<T: Serialize>(entity: &T) -> Option<Value> {
+    serde_json::to_value(entity).ok()
+}
+
+/// Append a write to the audit log, made by the actor of the request being handled if any.
+/// It is made on the connection of the write, so it is only kept if the write is.
+fn audit(
+    conn: &mut PgConnection,
+    action: &str,
+    entity: &str,
+    entity_id: i32,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> QueryResult<()> {
+    use crate::domain::entities::audit_entries;
+    let actor = ACTOR.try_with(Actor::clone).ok();
+    let entry = NewAuditEntry {
+        actor_id: actor.as_ref().map(|actor| actor.staff_id),
+        device: actor.as_ref().and_then(|actor| actor.device.clone()),
+        action,
+        entity,
+        entity_id,
+        before,
+        after,
+        trace_id: actor.map(|actor| actor.trace_id),
+    };
+    diesel::insert_into(audit_entries::table)
+        .values(&entry)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Publish a kitchen event, having no display connected is not an error.
+fn publish(events: &Sender<KitchenEvent>, event: KitchenEvent) {
+    let _ = events.send(event);
+}
+
+/// Everything ordered on a table session by order, cancelled orders aren't billed.
+/// Orders are billed at the price they were ordered at.
+/// The modifiers chosen for an order are part of its description and unit price.
+fn billed_orders(conn: &mut PgConnection, table: &Table) -> ServerResult<Vec<BilledOrder>> {
+    use crate::domain::entities::{items, modifiers, order_modifiers, orders};
+    use chrono::prelude::*;
+    let ordered = Order::belonging_to(table)
+        .inner_join(items::table)
+        .filter(orders::status.ne(OrderStatus::Cancelled))
+        .order(orders::id)
+        .select((
+            orders::id,
+            orders::item_id,
+            items::category_id,
+            orders::published_at,
+            orders::description,
+            (orders::unit_price, orders::currency),
+            orders::quantity,
+        ))
+        .load::<(i32, i32, Option<i32>, String, String, Money, i32)>(conn)?;
+    let chosen = order_modifiers::table
+        .inner_join(modifiers::table)
+        .filter(order_modifiers::order_id.eq_any(ordered.iter().map(|order| order.0)))
+        .order((order_modifiers::order_id, modifiers::id))
+        .select((
+            order_modifiers::order_id,
+            modifiers::name,
+            (modifiers::price_delta, modifiers::currency),
+        ))
+        .load::<(i32, String, Money)>(conn)?;
+    ordered
+        .into_iter()
+        .map(
+            |(order_id, item_id, category_id, published_at, description, price, quantity)| {
+                let picked: Vec<(String, Money)> = chosen
+                    .iter()
+                    .filter(|(id, _, _)| *id == order_id)
+                    .map(|(_, name, delta)| (name.clone(), *delta))
+                    .collect();
+                let (description, price) = modified(description, price, &picked)?;
+                // The time ordered decides the discounts, guessing it would change the bill.
+                let ordered_at = DateTime::parse_from_rfc3339(&published_at).map_err(|err| {
+                    error!(
+                        "Order {} has an unreadable order time {:?}: {}",
+                        order_id, published_at, err
+                    );
+                    ApiError::new(
+                        ErrorCode::Internal,
+                        format!("Unable to bill order {}!", order_id),
+                    )
+                })?;
+                Ok(BilledOrder {
+                    order_id,
+                    item_id,
+                    category_id,
+                    ordered_at: ordered_at.time(),
+                    item: BillItem::new(description, price, quantity)?,
                 })
+            },
+        )
+        .collect()
+}
+
+/// Modifier groups of an item, each with the modifiers it offers.
+fn item_modifiers(
+    conn: &mut PgConnection,
+    item_id: &i32,
+) -> QueryResult<Vec<(ModifierGroup, Vec<Modifier>)>> {
+    use crate::domain::entities::{modifier_groups, modifiers};
+    let groups = modifier_groups::table
+        .filter(modifier_groups::item_id.eq(item_id))
+        .order(modifier_groups::id)
+        .select(ModifierGroup::as_select())
+        .load(conn)?;
+    let offered = Modifier::belonging_to(&groups)
+        .order(modifiers::id)
+        .select(Modifier::as_select())
+        .load(conn)?;
+    Ok(offered
+        .grouped_by(&groups)
+        .into_iter()
+        .zip(groups)
+        .map(|(modifiers, group)| (group, modifiers))
+        .collect())
+}
+
+/// Check the modifiers chosen for an order against the groups of its item, and record them.
+fn add_modifiers(conn: &mut PgConnection, order: &Order, chosen: &[i32]) -> ServerResult {
+    use crate::domain::entities::order_modifiers;
+    validate(&item_modifiers(conn, &order.item_id)?, chosen)?;
+    if !chosen.is_empty() {
+        diesel::insert_into(order_modifiers::table)
+            .values(
+                chosen
+                    .iter()
+                    .map(|id| {
+                        (
+                            order_modifiers::order_id.eq(order.id),
+                            order_modifiers::modifier_id.eq(id),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Take the portions of an order from a known item, it is an error when it is sold out or archived.
+/// Taking the last counted portions marks the item unavailable.
+/// What goes into them is taken from stock.
+fn reserve_item(conn: &mut PgConnection, item_id: &i32, quantity: &i32) -> ServerResult {
+    use crate::domain::entities::items;
+    let taken = diesel::update(items::table.find(item_id))
+        .filter(items::available)
+        .filter(items::archived_at.is_null())
+        .filter(items::remaining.is_null().or(items::remaining.ge(quantity)))
+        .set((
+            items::remaining.eq(items::remaining - quantity),
+            items::available.eq(items::remaining.is_distinct_from(quantity)),
+            items::sold_out_by_stock.eq(items::remaining.is_not_distinct_from(quantity)),
+        ))
+        .execute(conn)?;
+    if taken == 0 {
+        let archived = items::table
+            .find(item_id)
+            .select(items::archived_at.is_not_null())
+            .first::<bool>(conn)?;
+        if archived {
+            return Err(ApiError::new(
+                ErrorCode::ItemArchived,
+                format!("Item {} is no longer on the menu!", item_id),
+            ));
+        }
+        return Err(ApiError::new(
+            ErrorCode::ItemUnavailable,
+            format!("Item {} is sold out!", item_id),
+        ));
+    }
+    deplete_stock(conn, item_id, quantity)
+}
+
+/// Give back the portions of an order that is no longer made, and what goes into them.
+/// An item counted down to nothing can be ordered again, one sold out by hand stays sold out.
+fn release_item(conn: &mut PgConnection, item_id: &i32, quantity: &i32) -> QueryResult<()> {
+    use crate::domain::entities::items;
+    diesel::update(items::table.find(item_id))
+        .filter(items::remaining.is_not_null())
+        .set((
+            items::remaining.eq(items::remaining + quantity),
+            items::available.eq(items::available.or(items::sold_out_by_stock)),
+            items::sold_out_by_stock.eq(false),
+        ))
+        .execute(conn)?;
+    restore_stock(conn, item_id, quantity)
+}
+
+/// Take what goes into the portions of an item from stock, it is an error when there isn't enough.
+fn deplete_stock(conn: &mut PgConnection, item_id: &i32, quantity: &i32) -> ServerResult {
+    use crate::domain::entities::{ingredients, recipe_ingredients};
+    let recipe = recipe_ingredients::table
+        .filter(recipe_ingredients::item_id.eq(item_id))
+        .select(RecipeIngredient::as_select())
+        .load(conn)?;
+    for line in recipe.iter() {
+        let needed = line.needed(quantity)?;
+        let taken = diesel::update(ingredients::table.find(line.ingredient_id))
+            .filter(ingredients::stock.ge(needed))
+            .set(ingredients::stock.eq(ingredients::stock - needed))
+            .execute(conn)?;
+        if taken == 0 {
+            return Err(ApiError::new(
+                ErrorCode::ItemUnavailable,
+                format!(
+                    "Not enough of ingredient {} left for item {}!",
+                    line.ingredient_id, item_id
+                ),
+            ));
+        }
+    }
+    update_stocked(conn, &recipe)?;
+    Ok(())
+}
+
+/// Put what went into the portions of an item back in stock, as its recipe is now.
+fn restore_stock(conn: &mut PgConnection, item_id: &i32, quantity: &i32) -> QueryResult<()> {
+    use crate::domain::entities::{ingredients, recipe_ingredients};
+    let recipe = recipe_ingredients::table
+        .filter(recipe_ingredients::item_id.eq(item_id))
+        .select(RecipeIngredient::as_select())
+        .load(conn)?;
+    for line in recipe.iter() {
+        diesel::update(ingredients::table.find(line.ingredient_id))
+            .set(ingredients::stock.eq(ingredients::stock
+                + line.quantity.into_sql::<diesel::sql_types::Integer>() * quantity))
+            .execute(conn)?;
+    }
+    update_stocked(conn, &recipe)
+}
+
+/// Take the items using the ingredients of a recipe off the menu when one of them ran out,
+/// and put them back once there is enough of each for a portion again.
+fn update_stocked(conn: &mut PgConnection, recipe: &[RecipeIngredient]) -> QueryResult<()> {
+    use crate::domain::entities::recipe_ingredients;
+    let using = recipe_ingredients::table
+        .filter(
+            recipe_ingredients::ingredient_id.eq_any(recipe.iter().map(|line| line.ingredient_id)),
+        )
+        .select(recipe_ingredients::item_id)
+        .distinct()
+        .load::<i32>(conn)?;
+    stock_items(conn, &using)
+}
+
+/// Whether there is enough of every ingredient for a portion of each item.
+fn stock_items(conn: &mut PgConnection, item_ids: &[i32]) -> QueryResult<()> {
+    use crate::domain::entities::{ingredients, items, recipe_ingredients};
+    let short = recipe_ingredients::table
+        .inner_join(ingredients::table)
+        .filter(recipe_ingredients::item_id.eq_any(item_ids))
+        .filter(ingredients::stock.lt(recipe_ingredients::quantity))
+        .select(recipe_ingredients::item_id)
+        .distinct()
+        .load::<i32>(conn)?;
+    diesel::update(items::table.filter(items::id.eq_any(item_ids)))
+        .set(items::stocked.eq(diesel::dsl::not(items::id.eq_any(short))))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Keep the price an item has from now on in its price history.
+fn record_price(conn: &mut PgConnection, item: &Item) -> QueryResult<usize> {
+    use crate::domain::entities::item_prices;
+    diesel::insert_into(item_prices::table)
+        .values((
+            item_prices::item_id.eq(item.id),
+            item_prices::price.eq(item.price.amount),
+            item_prices::currency.eq(item.price.currency),
+        ))
+        .execute(conn)
+}
+
+/// Items are only ordered in the currency of the table session.
+fn ensure_currency(table: &Table, item_id: &i32, currency: Currency) -> ServerResult {
+    if currency != table.currency {
+        return Err(ApiError::new(
+            ErrorCode::CurrencyMismatch,
+            format!(
+                "Item {} is priced in {}, table {} pays in {}!",
+                item_id, currency, table.table_number, table.currency
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Work out the bill of a table session from what it ordered.
+/// Active promotions apply, coupons only when the table redeemed them.
+/// Taxes are worked out on what is left after the discounts.
+fn compute_bill(conn: &mut PgConnection, table: &Table) -> ServerResult<(Vec<BilledOrder>, Bill)> {
+    use crate::domain::entities::{promotions, table_coupons, tax_rates};
+    let ordered = billed_orders(conn, table)?;
+    let redeemed = table_coupons::table
+        .filter(table_coupons::table_id.eq(table.id))
+        .select(table_coupons::promotion_id);
+    let promotions = promotions::table
+        .filter(promotions::active.eq(true))
+        .filter(
+            promotions::code
+                .is_null()
+                .or(promotions::id.eq_any(redeemed)),
+        )
+        .order(promotions::id)
+        .select(Promotion::as_select())
+        .load(conn)?;
+    let rates = tax_rates::table
+        .filter(tax_rates::active.eq(true))
+        .order(tax_rates::id)
+        .select(TaxRate::as_select())
+        .load(conn)?;
+    let discounts = discounts(&promotions, &ordered, table.currency)?;
+    let discounted = Money::sum(
+        table.currency,
+        discounts.iter().map(|discount| &discount.amount),
+    )?;
+    let taxes = taxes(&rates, &ordered, table.service, table.currency, &discounted)?;
+    // No service charges are configured yet.
+    let bill = Bill::new(
+        table.table_number,
+        table.currency,
+        ordered.iter().map(|order| order.item.clone()).collect(),
+        discounts,
+        taxes,
+        vec![],
+    )?;
+    Ok((ordered, bill))
+}
+
+/// Lock the session of a seated table, for the rest of the transaction.
+fn lock_table(conn: &mut PgConnection, number: &i32) -> ServerResult<Table> {
+    use crate::domain::entities::tables;
+    tables::table
+        .filter(
+            tables::table_number
+                .eq(number)
+                .and(tables::status.ne(SessionStatus::Closed)),
+        )
+        .select(Table::as_select())
+        .for_update()
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::TableNotFound,
+                format!("Unable to find table {}!", number),
+            )
+        })
+}
+
+/// Issue and store the bill of a table session.
+fn issue_bill(conn: &mut PgConnection, table: &Table) -> ServerResult<(i32, Bill)> {
+    use crate::domain::entities::{bill_lines, bills};
+    let (_, mut bill) = compute_bill(conn, table)?;
+    let (bill_id, issued_at) = diesel::insert_into(bills::table)
+        .values(&NewBill {
+            table_id: &table.id,
+            subtotal: &bill.subtotal.amount,
+            total: &bill.total.amount,
+            currency: bill.total.currency,
+        })
+        .returning((bills::id, bills::issued_at))
+        .get_result(conn)?;
+    diesel::insert_into(bill_lines::table)
+        .values(bill.lines(bill_id))
+        .execute(conn)?;
+    bill.issued_at = Some(issued_at);
+    Ok((bill_id, bill))
+}
+
+/// The bill that was issued for a table session.
+fn stored_bill(conn: &mut PgConnection, table: &Table) -> ServerResult<(i32, Bill)> {
+    use crate::domain::entities::{bill_lines, bills};
+    let (bill_id, issued_at, currency) = bills::table
+        .filter(bills::table_id.eq(table.id))
+        .select((bills::id, bills::issued_at, bills::currency))
+        .first::<(i32, chrono::DateTime<chrono::Utc>, Currency)>(conn)?;
+    let lines = bill_lines::table
+        .filter(bill_lines::bill_id.eq(bill_id))
+        .order(bill_lines::id)
+        .select((
+            bill_lines::kind,
+            bill_lines::description,
+            bill_lines::unit_price,
+            bill_lines::quantity,
+            bill_lines::amount,
+            bill_lines::promotion_id,
+            bill_lines::tax_rate_id,
+        ))
+        .load::<(
+            BillLineKind,
+            String,
+            i64,
+            i32,
+            i64,
+            Option<i32>,
+            Option<i32>,
+        )>(conn)?;
+    let mut items = vec![];
+    let mut discounts = vec![];
+    let mut taxes = vec![];
+    let mut charges = vec![];
+    for (kind, description, unit_price, quantity, amount, promotion_id, tax_rate_id) in lines {
+        let amount = Money::new(amount, currency);
+        match kind {
+            BillLineKind::Item => items.push(BillItem::new(
+                description,
+                Money::new(unit_price, currency),
+                quantity,
+            )?),
+            BillLineKind::Discount => discounts.push(BillDiscount {
+                promotion_id: promotion_id.unwrap_or_default(),
+                description,
+                amount,
+            }),
+            BillLineKind::Tax | BillLineKind::IncludedTax => taxes.push(BillTax {
+                tax_rate_id: tax_rate_id.unwrap_or_default(),
+                description,
+                inclusive: kind == BillLineKind::IncludedTax,
+                amount,
+            }),
+            BillLineKind::Charge => charges.push(BillCharge {
+                description,
+                amount,
+            }),
+        }
+    }
+    let mut bill = Bill::new(
+        table.table_number,
+        currency,
+        items,
+        discounts,
+        taxes,
+        charges,
+    )?;
+    bill.issued_at = Some(issued_at);
+    Ok((bill_id, bill))
+}
+
+/// The bill of a table session being settled in parts, with its parts.
+fn split_bill(conn: &mut PgConnection, table: &Table) -> ServerResult<(Bill, Vec<BillPart>)> {
+    use crate::domain::entities::bill_parts;
+    if table.status != SessionStatus::Billing {
+        return Err(ApiError::new(
+            ErrorCode::BillNotSplit,
+            format!("The bill of table {} is not split!", table.table_number),
+        ));
+    }
+    let (bill_id, bill) = stored_bill(conn, table)?;
+    let parts = bill_parts::table
+        .filter(bill_parts::bill_id.eq(bill_id))
+        .order(bill_parts::position)
+        .select(BillPart::as_select())
+        .load(conn)?;
+    Ok((bill, parts))
+}
+
+/// Check out a table session with its final bill.
+fn close_table(conn: &mut PgConnection, table: &Table, bill: &Bill) -> QueryResult<()> {
+    use crate::domain::entities::tables;
+    let closed = diesel::update(tables::table.find(table.id))
+        .set((
+            tables::status.eq(SessionStatus::Closed),
+            tables::closed_at.eq(diesel::dsl::now),
+            tables::total.eq(bill.total.amount),
+        ))
+        .returning(Table::as_returning())
+        .get_result(conn)?;
+    audit(
+        conn,
+        "check_out",
+        "table",
+        table.id,
+        snapshot(table),
+        snapshot(&closed),
+    )
+}
+
+/// Find a table of the floor, for the rest of the transaction.
+fn lock_dining_table(conn: &mut PgConnection, number: &i32) -> ServerResult<DiningTable> {
+    use crate::domain::entities::dining_tables;
+    db_query!(
+        dining_tables::table
+            .filter(dining_tables::table_number.eq(number))
+            .select(DiningTable::as_select())
+            .for_update()
+            .first(conn),
+        ErrorCode::UnknownTable,
+        format!("Table {} is not on the floor!", number)
+    )
+}
+
+/// A booking must end after it starts.
+fn ensure_window(starts_at: &DateTime<Utc>, ends_at: &DateTime<Utc>) -> ServerResult {
+    if ends_at <= starts_at {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "A reservation must end after it starts!",
+        ));
+    }
+    Ok(())
+}
+
+/// Book a table for a party, no other booking may hold it at the same time.
+fn book_table(
+    conn: &mut PgConnection,
+    reservation: &NewReservation,
+    except: Option<&i32>,
+) -> ServerResult {
+    use crate::domain::entities::reservations;
+    let Some(number) = reservation.table_number else {
+        return Ok(());
+    };
+    lock_dining_table(conn, number)?.seat(Some(reservation.party_size))?;
+    let mut overlapping = reservations::table
+        .filter(reservations::table_number.eq(number))
+        .filter(reservations::status.eq(ReservationStatus::Booked))
+        .filter(reservations::starts_at.lt(reservation.ends_at))
+        .filter(reservations::ends_at.gt(reservation.starts_at))
+        .select(reservations::id)
+        .into_boxed();
+    if let Some(rid) = except {
+        overlapping = overlapping.filter(reservations::id.ne(rid));
+    }
+    if let Some(other) = overlapping.first::<i32>(conn).optional()? {
+        return Err(ApiError::new(
+            ErrorCode::TableBooked,
+            format!("Table {} is booked by reservation {}!", number, other),
+        ));
+    }
+    Ok(())
+}
+
+/// Lock a reservation that is still booked, for the rest of the transaction.
+fn booked_reservation(conn: &mut PgConnection, rid: &i32) -> ServerResult<Reservation> {
+    use crate::domain::entities::reservations;
+    let reservation = db_query!(
+        reservations::table
+            .find(rid)
+            .select(Reservation::as_select())
+            .for_update()
+            .first(conn),
+        ErrorCode::ReservationNotFound,
+        format!("Unable to find reservation {}!", rid)
+    )?;
+    if reservation.status != ReservationStatus::Booked {
+        return Err(ApiError::new(
+            ErrorCode::ReservationClosed,
+            format!("Reservation {} is {}!", reservation.id, reservation.status),
+        ));
+    }
+    Ok(reservation)
+}
+
+/// Seat the party of a reservation at a table, `None` if it has none.
+/// Unless the reservation is given, it is the one the table is booked for right now.
+fn seat_reservation(
+    conn: &mut PgConnection,
+    number: &i32,
+    rid: Option<&i32>,
+) -> ServerResult<Option<Reservation>> {
+    use crate::domain::entities::reservations;
+    let reservation = match rid {
+        Some(rid) => booked_reservation(conn, rid)?,
+        None => {
+            let now = Utc::now();
+            let booked = reservations::table
+                .filter(reservations::table_number.eq(number))
+                .filter(reservations::status.eq(ReservationStatus::Booked))
+                .filter(
+                    reservations::starts_at.le(now + Duration::minutes(RESERVATION_EARLY_MINUTES)),
+                )
+                .filter(reservations::ends_at.gt(now))
+                .order(reservations::starts_at)
+                .select(Reservation::as_select())
+                .for_update()
+                .first(conn)
+                .optional()?;
+            match booked {
+                Some(reservation) => reservation,
+                None => return Ok(None),
             }
         }
     };
+    if let Some(booked) = reservation.table_number.filter(|booked| booked != number) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!("Reservation {} is for table {}!", reservation.id, booked),
+        ));
+    }
+    let seated = diesel::update(reservations::table.find(reservation.id))
+        .set((
+            reservations::status.eq(ReservationStatus::Seated),
+            reservations::table_number.eq(number),
+        ))
+        .returning(Reservation::as_returning())
+        .get_result(conn)?;
+    audit(
+        conn,
+        "seat",
+        "reservation",
+        seated.id,
+        snapshot(&reservation),
+        snapshot(&seated),
+    )?;
+    Ok(Some(seated))
+}
+
+/// Seat a party at a table of the floor that fits it, opening a session.
+/// A party checking in for its reservation is linked to it.
+fn check_in(conn: &mut PgConnection, n: &NewTable) -> ServerResult<Table> {
+    use crate::domain::entities::tables;
+    let dining_table = lock_dining_table(conn, n.table_number)?;
+    let table = db_query!(
+        tables::table
+            .select(Table::as_select())
+            .filter(
+                tables::status
+                    .ne(SessionStatus::Closed)
+                    .and(tables::table_number.eq(n.table_number))
+            )
+            .load(conn),
+        "Unable to find tables!"
+    )?;
+    if !table.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::TableOccupied,
+            "Unable to checkin, table already occupied!",
+        ));
+    }
+    let reservation = seat_reservation(conn, n.table_number, n.reservation_id)?;
+    let n = NewTable {
+        party_size: n
+            .party_size
+            .or(reservation.as_ref().map(|booked| &booked.party_size)),
+        reservation_id: reservation.as_ref().map(|booked| &booked.id),
+        ..*n
+    };
+    dining_table.seat(n.party_size)?;
+
+    // Two check-ins racing for the same table are caught by the database.
+    let table = diesel::insert_into(tables::table)
+        .values(&n)
+        .returning(Table::as_returning())
+        .get_result(conn)
+        .map_err(|err| table_occupied(err, "Unable to checkin, table already occupied!"))?;
+    audit(conn, "check_in", "table", table.id, None, snapshot(&table))?;
+    Ok(table)
+}
+
+/// Another party was seated at the table first, as caught by the database.
+fn table_occupied(err: diesel::result::Error, occupied: &str) -> ApiError {
+    match ApiError::from_db(err, ErrorCode::NotFound, "Unable to seat table") {
+        ApiError {
+            code: ErrorCode::Conflict,
+            ..
+        } => ApiError::new(ErrorCode::TableOccupied, occupied),
+        err => err,
+    }
+}
+
+/// Lock a party that is still waiting for a table, for the rest of the transaction.
+fn waiting_party(conn: &mut PgConnection, wid: &i32) -> ServerResult<WaitlistEntry> {
+    use crate::domain::entities::waitlist_entries;
+    let entry = db_query!(
+        waitlist_entries::table
+            .find(wid)
+            .select(WaitlistEntry::as_select())
+            .for_update()
+            .first(conn),
+        ErrorCode::PartyNotFound,
+        format!("Unable to find party {} on the waitlist!", wid)
+    )?;
+    if entry.status != WaitlistStatus::Waiting {
+        return Err(ApiError::new(
+            ErrorCode::PartyNotWaiting,
+            format!("Party {} is {}!", entry.id, entry.status),
+        ));
+    }
+    Ok(entry)
+}
+
+/// Parties waiting for a table, in the order they arrived.
+fn waiting_parties(conn: &mut PgConnection) -> QueryResult<Vec<WaitlistEntry>> {
+    use crate::domain::entities::waitlist_entries;
+    waitlist_entries::table
+        .filter(waitlist_entries::status.eq(WaitlistStatus::Waiting))
+        .order(waitlist_entries::id)
+        .select(WaitlistEntry::as_select())
+        .load(conn)
+}
+
+/// Estimate how long each party waits, in queue order, with `party_size` joining last.
+/// Tables with a seated party are free once it stayed for the turn time.
+fn estimate_queue(
+    conn: &mut PgConnection,
+    waiting: &[WaitlistEntry],
+    party_size: Option<&i32>,
+) -> ServerResult<Vec<Option<i64>>> {
+    use crate::domain::entities::{dining_tables, tables};
+    let stays = tables::table
+        .filter(tables::status.eq(SessionStatus::Closed))
+        .order(tables::closed_at.desc())
+        .limit(TURN_TIME_SESSIONS)
+        .select((tables::opened_at, tables::closed_at.assume_not_null()))
+        .load::<(DateTime<Utc>, DateTime<Utc>)>(conn)?;
+    let turn = turn_time(&stays, Duration::minutes(DEFAULT_TURN_MINUTES));
+    let seated = db_query!(get_all_active_tables(conn), "Unable to find tables!")?;
+    let now = Utc::now();
+    let floor: Vec<(i32, DateTime<Utc>)> = dining_tables::table
+        .filter(dining_tables::active)
+        .select((dining_tables::table_number, dining_tables::seats))
+        .load::<(i32, i32)>(conn)?
+        .into_iter()
+        .map(|(number, seats)| {
+            let free_at = seated
+                .iter()
+                .find(|table| table.table_number == number)
+                .map_or(now, |table| table.opened_at + turn);
+            (seats, free_at)
+        })
+        .collect();
+    let parties: Vec<i32> = waiting
+        .iter()
+        .map(|entry| entry.party_size)
+        .chain(party_size.copied())
+        .collect();
+    Ok(estimate_waits(&floor, &parties, turn, now))
+}
+
+/// Describe why a single order line could not be created.
+fn order_line_error(line: &NewOrderLine, err: diesel::result::Error) -> ApiError {
+    use diesel::result::{DatabaseErrorKind, Error};
+    match err {
+        Error::NotFound => ApiError::new(
+            ErrorCode::TableNotFound,
+            format!("Unable to find table {}!", line.table_number),
+        ),
+        Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => ApiError::new(
+            ErrorCode::UnknownItem,
+            format!("Unable to find item {}!", line.item_id),
+        ),
+        err => ApiError::from_db(err, ErrorCode::NotFound, "Unable to create order!"),
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct OrderFactory {
     pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub(crate) events: Sender<KitchenEvent>,
 }
 
 #[async_trait(?Send)]
 impl OrderRepository for OrderFactory {
-    /// Calculate total for a table!
-    fn total(&self, tid: &i32) -> ServerResult<i32> {
-        let active_table = get_table(db_conn!(self), tid);
-        if active_table.is_err() {
-            return Err(ServerError {
-                error: "Unable to find tables!".to_string(),
-            });
-        }
-        let _orders = Order::belonging_to(&active_table.expect("Unable to find tables!"))
-            .select(Order::as_select())
-            .load(db_conn!(self))
-            .expect("Unable to find table!");
-        let mut total = 0;
-        for order in _orders {
-            let item = crate::domain::entities::items::dsl::items
-                .filter(crate::domain::entities::items::dsl::id.eq(order.item_id))
-                .select(Item::as_select())
-                .first(db_conn!(self))
-                .expect("Unable to find item for order!");
-            total += item.price * order.quantity
-        }
-
-        Ok(total)
-    }
     /// Find an order
     fn find(&self, oid: &i32) -> ServerResult<Vec<Order>> {
         use crate::domain::entities::orders::dsl::*;
-        let all_tables = get_all_active_tables(db_conn!(self));
-        if all_tables.is_err() {
-            return Err(ServerError {
-                error: "Unable to find tables!".to_string(),
-            });
-        }
-        db_query!(
-            Order::belonging_to(&all_tables.expect("Unable to find tables!"))
+        let conn = db_conn!(self);
+        let all_tables = db_query!(get_all_active_tables(conn), "Unable to find tables!")?;
+        let found = db_query!(
+            Order::belonging_to(&all_tables)
                 .filter(id.eq(oid))
                 .select(Order::as_select())
-                .load(db_conn!(self)),
+                .load(conn),
             "Unable to find order!"
-        )
+        )?;
+        if found.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::OrderNotFound,
+                format!("Unable to find order {}!", oid),
+            ));
+        }
+        Ok(found)
     }
 
-    /// Find orders for table
-    fn find_table(&self, cid: &i32) -> ServerResult<Vec<Order>> {
-        let table = get_table(db_conn!(self), cid);
-        if table.is_err() {
-            return Err(ServerError {
-                error: "Unable to find tables!".to_string(),
-            });
+    /// Find orders for table, optionally only those in one of the given statuses.
+    fn find_table(&self, cid: &i32, statuses: &[OrderStatus]) -> ServerResult<Vec<Order>> {
+        use crate::domain::entities::orders::dsl::*;
+        let conn = db_conn!(self);
+        let table = open_table(conn, cid)?;
+        let mut query = Order::belonging_to(&table)
+            .select(Order::as_select())
+            .into_boxed();
+        if !statuses.is_empty() {
+            query = query.filter(status.eq_any(statuses));
         }
-        db_query!(
-            Order::belonging_to(&table.expect("Unable to find table!"))
-                .select(Order::as_select())
-                .load(db_conn!(self)),
-            "Unable to find order for table"
-        )
+        db_query!(query.load(conn), "Unable to find order for table")
     }
 
-    /// Delete a tables order
-    fn delete_table_order(&self, cid: &i32, oid: &i32) -> ServerResult<String> {
+    /// Delete a tables order, only managers delete orders the kitchen started.
+    fn delete_table_order(&self, cid: &i32, oid: &i32, manages: bool) -> ServerResult<String> {
         use crate::domain::entities::orders::dsl::*;
-        let table = get_table(db_conn!(self), cid);
-        if table.is_err() {
-            return Err(ServerError {
-                error: "Unable to find tables!".to_string(),
-            });
-        }
-        let order = diesel::delete(
-            Order::belonging_to(&table.expect("Unable to find table!")).filter(id.eq(oid)),
-        )
-        .execute(db_conn!(self));
-        match order {
-            Ok(r) => {
-                if r == 0 {
-                    return Err(ServerError {
-                        error: "Unable to find order id!".to_string(),
-                    });
+        let r = db_conn!(self).transaction(|conn| {
+            let table = open_table(conn, cid)?;
+            let found = Order::belonging_to(&table)
+                .filter(id.eq(oid))
+                .select(Order::as_select())
+                .for_update()
+                .load(conn)?;
+            ensure_deletable(&found, manages)?;
+            let deleted = diesel::delete(Order::belonging_to(&table).filter(id.eq(oid)))
+                .returning(Order::as_returning())
+                .get_results(conn)?;
+            for o in deleted.iter() {
+                if o.status != OrderStatus::Cancelled {
+                    release_item(conn, &o.item_id, &o.quantity)?;
                 }
-                Ok("OK".to_string())
+                audit(conn, "delete", "order", o.id, snapshot(o), None)?;
             }
-            _ => Err(ServerError {
-                error: "Unable to find order!".to_string(),
-            }),
+            Ok::<_, ApiError>(deleted)
+        })?;
+        if r.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::OrderNotFound,
+                format!("Unable to find order {} for table {}!", oid, cid),
+            ));
         }
+        for o in r {
+            publish(
+                &self.events,
+                KitchenEvent::OrderDeleted {
+                    order_id: o.id,
+                    item_id: o.item_id,
+                },
+            );
+        }
+        Ok("OK".to_string())
     }
 
-    /// Find all orders
-    fn all(&self) -> ServerResult<Vec<Order>> {
+    /// Get a page of the orders matching the filter.
+    fn all(
+        &self,
+        filter: &OrderFilter,
+        page: &PageRequest<OrderSort>,
+    ) -> ServerResult<Page<Order>> {
         use crate::domain::entities::orders::dsl::*;
-        db_query_optional!(
-            orders
+        use crate::domain::entities::tables;
+        let mut query = orders.select(Order::as_select()).into_boxed();
+        if !filter.statuses.is_empty() {
+            query = query.filter(status.eq_any(filter.statuses));
+        }
+        if let Some(number) = filter.table_number {
+            let sessions = tables::table
+                .filter(tables::table_number.eq(number))
+                .select(tables::id);
+            query = query.filter(table_id.eq_any(sessions));
+        }
+        if let Some(item) = filter.item_id {
+            query = query.filter(item_id.eq(item));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(ordered_at().ge(from));
+        }
+        if let Some(until) = filter.until {
+            query = query.filter(ordered_at().lt(until));
+        }
+        let query = match page.sort.field {
+            OrderSort::Id => paginate!(query, page, id, id, i32),
+            OrderSort::ItemId => paginate!(query, page, item_id, id, i32),
+            OrderSort::Quantity => paginate!(query, page, quantity, id, i32),
+        };
+        let found = db_query!(query.load(db_conn!(self)), "Unable to find orders")?;
+        Ok(page.page(found, |order| {
+            let sort = &page.sort;
+            match sort.field {
+                OrderSort::Id => Cursor::new(sort, &order.id, order.id),
+                OrderSort::ItemId => Cursor::new(sort, &order.item_id, order.id),
+                OrderSort::Quantity => Cursor::new(sort, &order.quantity, order.id),
+            }
+        }))
+    }
+
+    /// Move an order along its lifecycle, rejecting illegal transitions.
+    fn set_status(&self, oid: &i32, next: &OrderStatus, manages: bool) -> ServerResult<Order> {
+        use crate::domain::entities::orders::dsl::*;
+        // Only update when the current status allows the transition, so concurrent
+        // updates can't skip a step.
+        // A cancelled order gives its portions back.
+        let updated = db_conn!(self).transaction(|conn| {
+            let before = orders
+                .filter(id.eq(oid))
                 .select(Order::as_select())
-                .load(db_conn!(self))
+                .for_update()
+                .first(conn)
+                .optional()?;
+            if *next == OrderStatus::Cancelled {
+                ensure_deletable(before.as_slice(), manages)?;
+            }
+            let updated = diesel::update(orders)
+                .filter(
+                    id.eq(oid)
+                        .and(status.eq_any(OrderStatus::predecessors(next))),
+                )
+                .set(status.eq(next))
+                .returning(Order::as_returning())
+                .get_result(conn)
+                .optional()?;
+            if let Some(order) = updated.as_ref() {
+                if *next == OrderStatus::Cancelled {
+                    release_item(conn, &order.item_id, &order.quantity)?;
+                }
+                let before = before.as_ref().and_then(snapshot);
+                audit(
+                    conn,
+                    "set_status",
+                    "order",
+                    order.id,
+                    before,
+                    snapshot(order),
+                )?;
+            }
+            Ok::<_, ApiError>(updated)
+        })?;
+        if let Some(order) = updated {
+            publish(
+                &self.events,
+                KitchenEvent::OrderStatusChanged {
+                    order_id: order.id,
+                    status: order.status,
+                },
+            );
+            return Ok(order);
+        }
+        let current = db_query!(
+            orders
+                .filter(id.eq(oid))
+                .select(status)
+                .first::<OrderStatus>(db_conn!(self))
                 .optional(),
-            "Unable to find orders",
-            vec![]
-        )
+            "Unable to find order!"
+        )?;
+        match current {
+            Some(current) => Err(ApiError::new(
+                ErrorCode::IllegalStatusTransition,
+                format!(
+                    "Illegal status transition for order {}: {} -> {}",
+                    oid, current, next
+                ),
+            )),
+            None => Err(ApiError::new(
+                ErrorCode::OrderNotFound,
+                format!("Unable to find order {}!", oid),
+            )),
+        }
     }
 
-    /// Create a new order
-    fn create(&self, o: &NewOrder) -> ServerResult<Order> {
-        use crate::domain::entities::orders;
-        db_query!(
-            diesel::insert_into(orders::table)
-                .values(o)
-                .returning(Order::as_returning())
-                .get_result(db_conn!(self)),
-            "Unable to create order!"
-        )
+    /// Create a batch of orders in a single transaction.
+    /// Unless `partial` is set, a single failing line rolls back the whole batch.
+    fn create_batch(
+        &self,
+        lines: &[NewOrderLine],
+        partial: bool,
+    ) -> ServerResult<(bool, Vec<ServerResult<Order>>)> {
+        use crate::domain::entities::{items, orders, tables};
+        use chrono::prelude::*;
+        let published_at = Local::now().to_rfc3339();
+        let mut results = Vec::with_capacity(lines.len());
+        let outcome = db_conn!(self).transaction(|conn| {
+            for line in lines {
+                if line.quantity <= 0 {
+                    results.push(Err(ApiError::new(
+                        ErrorCode::InvalidQuantity,
+                        "Quantity must be positive!",
+                    )));
+                    continue;
+                }
+                // Every line runs in its own savepoint, so a failing line doesn't abort the
+                // rest of the batch and all errors can be reported at once.
+                let created = conn.transaction(|conn| {
+                    let table = tables::table
+                        .filter(
+                            tables::table_number
+                                .eq(line.table_number)
+                                .and(tables::status.ne(SessionStatus::Closed)),
+                        )
+                        .select(Table::as_select())
+                        .first(conn)
+                        .map_err(|err| order_line_error(line, err))?;
+                    let Some((description, unit_price)) = items::table
+                        .find(line.item_id)
+                        .select((items::description, (items::price, items::currency)))
+                        .first::<(String, Money)>(conn)
+                        .optional()?
+                    else {
+                        return Err(ApiError::new(
+                            ErrorCode::UnknownItem,
+                            format!("Unable to find item {}!", line.item_id),
+                        ));
+                    };
+                    ensure_currency(&table, &line.item_id, unit_price.currency)?;
+                    reserve_item(conn, &line.item_id, &line.quantity)?;
+                    let order = diesel::insert_into(orders::table)
+                        .values(&NewOrder {
+                            item_id: &line.item_id,
+                            table_id: &table.id,
+                            published_at: &published_at,
+                            quantity: &line.quantity,
+                            ticket_id: None,
+                            description: &description,
+                            unit_price: &unit_price.amount,
+                            currency: unit_price.currency,
+                        })
+                        .returning(Order::as_returning())
+                        .get_result(conn)
+                        .map_err(|err| order_line_error(line, err))?;
+                    add_modifiers(conn, &order, &line.modifiers)?;
+                    audit(conn, "create", "order", order.id, None, snapshot(&order))?;
+                    Ok(order)
+                });
+                results.push(created);
+            }
+            if !partial && results.iter().any(Result::is_err) {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+            Ok(())
+        });
+        let committed = match outcome {
+            Ok(()) => true,
+            Err(diesel::result::Error::RollbackTransaction) => false,
+            Err(err) => {
+                return Err(ApiError::from_db(
+                    err,
+                    ErrorCode::NotFound,
+                    "Unable to create orders!",
+                ))
+            }
+        };
+        if committed {
+            for (line, order) in lines.iter().zip(results.iter()) {
+                if let Ok(order) = order {
+                    publish(
+                        &self.events,
+                        KitchenEvent::OrderCreated {
+                            table_number: line.table_number,
+                            order: order.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok((committed, results))
     }
 
-    /// Delete an order
-    fn delete(&self, i: &i32) -> ServerResult<()> {
+    /// Delete an order, only managers delete orders the kitchen started.
+    fn delete(&self, i: &i32, manages: bool) -> ServerResult<()> {
         use crate::domain::entities::orders::dsl::*;
-        let res = diesel::delete(orders.filter(id.eq(i))).execute(db_conn!(self));
-        match res {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                error!("{:?}", e);
-                Err(ServerError {
-                    error: "Unable to create order!".to_string(),
-                })
+        let r = db_conn!(self).transaction(|conn| {
+            let found = orders
+                .filter(id.eq(i))
+                .select(Order::as_select())
+                .for_update()
+                .load(conn)?;
+            ensure_deletable(&found, manages)?;
+            let deleted = diesel::delete(orders.filter(id.eq(i)))
+                .returning(Order::as_returning())
+                .get_results(conn)?;
+            for o in deleted.iter() {
+                if o.status != OrderStatus::Cancelled {
+                    release_item(conn, &o.item_id, &o.quantity)?;
+                }
+                audit(conn, "delete", "order", o.id, snapshot(o), None)?;
             }
+            Ok::<_, ApiError>(deleted)
+        })?;
+        if r.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::OrderNotFound,
+                format!("Unable to find order {}!", i),
+            ));
+        }
+        for o in r {
+            publish(
+                &self.events,
+                KitchenEvent::OrderDeleted {
+                    order_id: o.id,
+                    item_id: o.item_id,
+                },
+            );
         }
+        Ok(())
     }
 }
 
@@ -194,29 +1254,183 @@ impl ItemRepository for ItemFactory {
     /// Create an item
     fn create(&self, n: &NewItem) -> ServerResult<Item> {
         use crate::domain::entities::items;
-        db_query!(
-            diesel::insert_into(items::table)
-                .values(n)
-                .returning(Item::as_returning())
-                .get_result(db_conn!(self)),
-            "Unable to create item"
-        )
+        db_conn!(self).transaction(|conn| {
+            let item = db_query!(
+                diesel::insert_into(items::table)
+                    .values(n)
+                    .returning(Item::as_returning())
+                    .get_result(conn),
+                "Unable to create item"
+            )?;
+            record_price(conn, &item)?;
+            audit(conn, "create", "item", item.id, None, snapshot(&item))?;
+            Ok(item)
+        })
     }
 
-    /// Get all items
-    fn all(&self) -> ServerResult<Vec<Item>> {
+    /// Get a page of the items on the menu, archived ones are left out.
+    fn all(&self, filter: &ItemFilter, page: &PageRequest<ItemSort>) -> ServerResult<Page<Item>> {
         use crate::domain::entities::items::dsl::*;
-        db_query_optional!(
-            items
-                .select(Item::as_select())
-                .load(db_conn!(self))
-                .optional(),
-            "Unable to find all items ",
-            vec![]
+        let mut query = items
+            .filter(archived_at.is_null())
+            .select(Item::as_select())
+            .into_boxed();
+        if !filter.ids.is_empty() {
+            query = query.filter(id.eq_any(filter.ids));
+        }
+        if let Some(category) = filter.category_id {
+            query = query.filter(category_id.eq(category));
+        }
+        if let Some(orderable) = filter.available {
+            query = query.filter(available.and(stocked).eq(orderable));
+        }
+        let query = match page.sort.field {
+            ItemSort::Id => paginate!(query, page, id, id, i32),
+            ItemSort::Description => paginate!(query, page, description, id, String),
+            ItemSort::Price => paginate!(query, page, price, id, i64),
+        };
+        let found = db_query!(query.load(db_conn!(self)), "Unable to find all items")?;
+        Ok(page.page(found, |item| {
+            let sort = &page.sort;
+            match sort.field {
+                ItemSort::Id => Cursor::new(sort, &item.id, item.id),
+                ItemSort::Description => Cursor::new(sort, &item.description, item.id),
+                ItemSort::Price => Cursor::new(sort, &item.price.amount, item.id),
+            }
+        }))
+    }
+
+    /// Mark an item available or sold out, optionally counting its portions.
+    fn set_availability(&self, iid: &i32, availability: &ItemAvailability) -> ServerResult<Item> {
+        use crate::domain::entities::items;
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                items::table
+                    .find(iid)
+                    .select(Item::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::ItemNotFound,
+                format!("Unable to find item {}!", iid)
+            )?;
+            let item = diesel::update(items::table.find(iid))
+                .set(availability)
+                .returning(Item::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "set_availability",
+                "item",
+                item.id,
+                snapshot(&before),
+                snapshot(&item),
+            )?;
+            Ok(item)
+        })
+    }
+
+    /// Change an item that is still on the menu.
+    fn update(&self, iid: &i32, changes: &ItemChanges) -> ServerResult<Item> {
+        use crate::domain::entities::items;
+        if changes.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "Nothing to update!",
+            ));
+        }
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                items::table
+                    .find(iid)
+                    .filter(items::archived_at.is_null())
+                    .select(Item::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::ItemNotFound,
+                format!("Unable to find item {}!", iid)
+            )?;
+            let price = before.price;
+            if changes
+                .currency
+                .is_some_and(|priced| priced != price.currency)
+            {
+                return Err(ApiError::new(
+                    ErrorCode::CurrencyMismatch,
+                    format!("Item {} is priced in {}!", iid, price.currency),
+                ));
+            }
+            let item = db_query!(
+                diesel::update(items::table.find(iid))
+                    .set(changes)
+                    .returning(Item::as_returning())
+                    .get_result(conn),
+                "Unable to update item!"
+            )?;
+            if item.price != price {
+                record_price(conn, &item)?;
+            }
+            audit(
+                conn,
+                "update",
+                "item",
+                item.id,
+                snapshot(&before),
+                snapshot(&item),
+            )?;
+            Ok(item)
+        })
+    }
+
+    /// Every price an item had, the current one last.
+    fn prices(&self, iid: &i32) -> ServerResult<Vec<ItemPrice>> {
+        use crate::domain::entities::{item_prices, items};
+        let conn = db_conn!(self);
+        db_query!(
+            items::table.find(iid).select(items::id).first::<i32>(conn),
+            ErrorCode::ItemNotFound,
+            format!("Unable to find item {}!", iid)
+        )?;
+        db_query!(
+            item_prices::table
+                .filter(item_prices::item_id.eq(iid))
+                .order((item_prices::effective_from, item_prices::id))
+                .select(ItemPrice::as_select())
+                .load(conn),
+            "Unable to find item prices!"
         )
     }
 
-    /// Get an item base on id
+    /// Take an item off the menu, orders and bills still refer to it.
+    fn archive(&self, iid: &i32) -> ServerResult<Item> {
+        use crate::domain::entities::items;
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                items::table
+                    .find(iid)
+                    .filter(items::archived_at.is_null())
+                    .select(Item::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::ItemNotFound,
+                format!("Unable to find item {}!", iid)
+            )?;
+            let item = diesel::update(items::table.find(iid))
+                .set(items::archived_at.eq(diesel::dsl::now))
+                .returning(Item::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "archive",
+                "item",
+                item.id,
+                snapshot(&before),
+                snapshot(&item),
+            )?;
+            Ok(item)
+        })
+    }
+
+    /// Get an item base on id, archived ones included.
     fn get(&self, _id: &i32) -> ServerResult<Item> {
         use crate::domain::entities::items::dsl::*;
         db_query!(
@@ -224,84 +1438,1857 @@ impl ItemRepository for ItemFactory {
                 .filter(id.eq(_id))
                 .select(Item::as_select())
                 .first(db_conn!(self)),
-            "Unable to get item"
+            ErrorCode::ItemNotFound,
+            format!("Unable to find item {}!", _id)
         )
     }
 }
 
 #[derive(Clone, Debug)]
-pub(crate) struct TableFactory {
+pub(crate) struct ModifierFactory {
     pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
 }
 
 #[async_trait(?Send)]
-impl TableRepository for TableFactory {
-    /// Checkout a table by caclulating it's total
-    fn checkout(&self, _id: &i32, _total: &i32) -> ServerResult<()> {
-        use crate::domain::entities::tables::dsl::*;
-        let r = diesel::update(tables)
-            .filter(table_number.eq(_id).and(total.eq(-1_i32)))
-            .set(total.eq(_total))
-            .execute(db_conn!(self));
-        match r {
-            Ok(_r) => Ok(()),
-            Err(e) => {
-                error!("{:?}", e);
-                Err(ServerError {
-                    error: "Unable to create table!".to_string(),
-                })
-            }
+impl ModifierRepository for ModifierFactory {
+    /// Create a modifier group of an item, with the modifiers it offers.
+    /// Price deltas are in the currency of the item, modifiers without one are free.
+    fn create(
+        &self,
+        group: &NewModifierGroup,
+        entries: &[NewModifierEntry],
+    ) -> ServerResult<(ModifierGroup, Vec<Modifier>)> {
+        use crate::domain::entities::{items, modifier_groups, modifiers};
+        if entries.is_empty() || i64::from(*group.min_selections) > entries.len() as i64 {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "A modifier group needs enough modifiers to choose from!",
+            ));
         }
+        db_conn!(self).transaction(|conn| {
+            let currency = db_query!(
+                items::table
+                    .find(group.item_id)
+                    .select(items::currency)
+                    .first::<Currency>(conn),
+                ErrorCode::ItemNotFound,
+                format!("Unable to find item {}!", group.item_id)
+            )?;
+            let created = db_query!(
+                diesel::insert_into(modifier_groups::table)
+                    .values(group)
+                    .returning(ModifierGroup::as_returning())
+                    .get_result(conn),
+                "Unable to create modifier group!"
+            )?;
+            let offered = entries
+                .iter()
+                .map(|entry| {
+                    let delta = entry.price_delta.unwrap_or(Money::zero(currency));
+                    if delta.currency != currency {
+                        return Err(ApiError::new(
+                            ErrorCode::CurrencyMismatch,
+                            format!("Item {} is priced in {}!", group.item_id, currency),
+                        ));
+                    }
+                    db_query!(
+                        diesel::insert_into(modifiers::table)
+                            .values(&NewModifier {
+                                group_id: &created.id,
+                                name: &entry.name,
+                                price_delta: &delta.amount,
+                                currency,
+                            })
+                            .returning(Modifier::as_returning())
+                            .get_result(conn),
+                        "Unable to create modifier!"
+                    )
+                })
+                .collect::<ServerResult<Vec<Modifier>>>()?;
+            let after = snapshot(&(&created, &offered));
+            audit(conn, "create", "modifier_group", created.id, None, after)?;
+            Ok((created, offered))
+        })
     }
 
-    /// Create a table
-    fn create(&self, n: &NewTable) -> ServerResult<Table> {
-        use crate::domain::entities::tables;
-        use crate::domain::entities::tables::dsl::*;
+    /// Find the modifier groups of an item.
+    fn find_item(&self, iid: &i32) -> ServerResult<Vec<(ModifierGroup, Vec<Modifier>)>> {
+        use crate::domain::entities::items;
+        let conn = db_conn!(self);
+        db_query!(
+            items::table.find(iid).select(items::id).first::<i32>(conn),
+            ErrorCode::ItemNotFound,
+            format!("Unable to find item {}!", iid)
+        )?;
+        db_query!(item_modifiers(conn, iid), "Unable to find modifiers!")
+    }
+}
 
-        let table = tables
-            .select(Table::as_select())
-            .filter(total.eq(-1_i32).and(table_number.eq(n.table_number)))
-            .load(db_conn!(self));
-        if let Ok(c) = table {
-            if !c.is_empty() {
-                return Err(ServerError {
-                    error: "Unable to checkin, table already occupied!".to_string(),
-                });
-            }
-        }
+#[derive(Clone, Debug)]
+pub(crate) struct InventoryFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
 
+#[async_trait(?Send)]
+impl InventoryRepository for InventoryFactory {
+    /// Create an ingredient
+    fn create(&self, n: &NewIngredient) -> ServerResult<Ingredient> {
+        use crate::domain::entities::ingredients;
+        db_conn!(self).transaction(|conn| {
+            let ingredient = db_query!(
+                diesel::insert_into(ingredients::table)
+                    .values(n)
+                    .returning(Ingredient::as_returning())
+                    .get_result(conn),
+                "Unable to create ingredient"
+            )?;
+            audit(
+                conn,
+                "create",
+                "ingredient",
+                ingredient.id,
+                None,
+                snapshot(&ingredient),
+            )?;
+            Ok(ingredient)
+        })
+    }
+
+    /// Get all ingredients
+    fn all(&self) -> ServerResult<Vec<Ingredient>> {
+        use crate::domain::entities::ingredients;
         db_query!(
-            diesel::insert_into(tables::table)
-                .values(n)
-                .returning(Table::as_returning())
-                .get_result(db_conn!(self)),
-            "Unable to create table"
+            ingredients::table
+                .order(ingredients::name)
+                .select(Ingredient::as_select())
+                .load(db_conn!(self)),
+            "Unable to find all ingredients"
         )
     }
 
-    /// Read all tables
-    fn all(&self) -> ServerResult<Vec<Table>> {
-        use crate::domain::entities::tables::dsl::*;
-        db_query_optional!(
-            tables
-                .select(Table::as_select())
-                .load(db_conn!(self))
-                .optional(),
-            "Unable to find all tables!",
-            vec![]
+    /// Add to the stock of an ingredient, items it ran out for are back on the menu.
+    fn restock(&self, iid: &i32, quantity: &i32) -> ServerResult<Ingredient> {
+        use crate::domain::entities::{ingredients, recipe_ingredients};
+        if *quantity <= 0 {
+            return Err(ApiError::new(
+                ErrorCode::InvalidQuantity,
+                "Quantity must be positive!",
+            ));
+        }
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                ingredients::table
+                    .find(iid)
+                    .select(Ingredient::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::IngredientNotFound,
+                format!("Unable to find ingredient {}!", iid)
+            )?;
+            let ingredient = diesel::update(ingredients::table.find(iid))
+                .set(ingredients::stock.eq(ingredients::stock + quantity))
+                .returning(Ingredient::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "restock",
+                "ingredient",
+                ingredient.id,
+                snapshot(&before),
+                snapshot(&ingredient),
+            )?;
+            let using = recipe_ingredients::table
+                .filter(recipe_ingredients::ingredient_id.eq(iid))
+                .select(recipe_ingredients::item_id)
+                .load::<i32>(conn)?;
+            stock_items(conn, &using)?;
+            Ok(ingredient)
+        })
+    }
+
+    /// Replace the recipe of an item.
+    fn set_recipe(
+        &self,
+        iid: &i32,
+        recipe: &[RecipeIngredient],
+    ) -> ServerResult<Vec<RecipeIngredient>> {
+        use crate::domain::entities::{items, recipe_ingredients};
+        if recipe.iter().any(|line| line.quantity <= 0) {
+            return Err(ApiError::new(
+                ErrorCode::InvalidQuantity,
+                "Quantity must be positive!",
+            ));
+        }
+        db_conn!(self).transaction(|conn| {
+            db_query!(
+                items::table.find(iid).select(items::id).first::<i32>(conn),
+                ErrorCode::ItemNotFound,
+                format!("Unable to find item {}!", iid)
+            )?;
+            let before = diesel::delete(
+                recipe_ingredients::table.filter(recipe_ingredients::item_id.eq(iid)),
+            )
+            .returning(RecipeIngredient::as_returning())
+            .get_results(conn)?;
+            let created = if recipe.is_empty() {
+                vec![]
+            } else {
+                db_query!(
+                    diesel::insert_into(recipe_ingredients::table)
+                        .values(recipe)
+                        .returning(RecipeIngredient::as_returning())
+                        .get_results(conn),
+                    "Unable to create recipe!"
+                )?
+            };
+            stock_items(conn, &[*iid])?;
+            audit(
+                conn,
+                "set_recipe",
+                "item",
+                *iid,
+                snapshot(&before),
+                snapshot(&created),
+            )?;
+            Ok(created)
+        })
+    }
+
+    /// Find the recipe of an item.
+    fn recipe(&self, iid: &i32) -> ServerResult<Vec<RecipeIngredient>> {
+        use crate::domain::entities::{items, recipe_ingredients};
+        let conn = db_conn!(self);
+        db_query!(
+            items::table.find(iid).select(items::id).first::<i32>(conn),
+            ErrorCode::ItemNotFound,
+            format!("Unable to find item {}!", iid)
+        )?;
+        db_query!(
+            recipe_ingredients::table
+                .filter(recipe_ingredients::item_id.eq(iid))
+                .order(recipe_ingredients::ingredient_id)
+                .select(RecipeIngredient::as_select())
+                .load(conn),
+            "Unable to find recipe!"
         )
     }
+}
 
-    /// Get specific table..
-    fn get(&self, _id: &i32) -> ServerResult<Table> {
-        use crate::domain::entities::tables::dsl::*;
+#[derive(Clone, Debug)]
+pub(crate) struct DiningTableFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl DiningTableRepository for DiningTableFactory {
+    /// Register a table
+    fn create(&self, n: &NewDiningTable) -> ServerResult<DiningTable> {
+        use crate::domain::entities::dining_tables;
+        db_conn!(self).transaction(|conn| {
+            let table = db_query!(
+                diesel::insert_into(dining_tables::table)
+                    .values(n)
+                    .returning(DiningTable::as_returning())
+                    .get_result(conn),
+                "Unable to register table"
+            )?;
+            audit(
+                conn,
+                "create",
+                "dining_table",
+                table.id,
+                None,
+                snapshot(&table),
+            )?;
+            Ok(table)
+        })
+    }
+
+    /// Get all tables of the floor, by section.
+    fn all(&self) -> ServerResult<Vec<DiningTable>> {
+        use crate::domain::entities::dining_tables;
         db_query!(
-            tables
-                .filter(table_number.eq(_id).and(total.eq(-1_i32)))
-                .select(Table::as_select())
-                .first(db_conn!(self)),
-            "Unable to find specific table "
+            dining_tables::table
+                .order((dining_tables::section, dining_tables::table_number))
+                .select(DiningTable::as_select())
+                .load(db_conn!(self)),
+            "Unable to find all tables"
         )
     }
+
+    /// Update a table, a party already seated at it stays.
+    fn update(&self, number: &i32, changes: &DiningTableChanges) -> ServerResult<DiningTable> {
+        use crate::domain::entities::dining_tables;
+        if changes.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "Nothing to update!",
+            ));
+        }
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                dining_tables::table
+                    .filter(dining_tables::table_number.eq(number))
+                    .select(DiningTable::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::DiningTableNotFound,
+                format!("Unable to find table {}!", number)
+            )?;
+            let table = db_query!(
+                diesel::update(dining_tables::table.find(before.id))
+                    .set(changes)
+                    .returning(DiningTable::as_returning())
+                    .get_result(conn),
+                "Unable to update table!"
+            )?;
+            audit(
+                conn,
+                "update",
+                "dining_table",
+                table.id,
+                snapshot(&before),
+                snapshot(&table),
+            )?;
+            Ok(table)
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ReservationFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl ReservationRepository for ReservationFactory {
+    /// Create a reservation, its table is booked if it has one.
+    fn create(&self, n: &NewReservation) -> ServerResult<Reservation> {
+        use crate::domain::entities::reservations;
+        ensure_window(n.starts_at, n.ends_at)?;
+        db_conn!(self).transaction(|conn| {
+            book_table(conn, n, None)?;
+            let reservation = db_query!(
+                diesel::insert_into(reservations::table)
+                    .values(n)
+                    .returning(Reservation::as_returning())
+                    .get_result(conn),
+                "Unable to create reservation"
+            )?;
+            let after = snapshot(&reservation);
+            audit(conn, "create", "reservation", reservation.id, None, after)?;
+            Ok(reservation)
+        })
+    }
+
+    /// Get specific reservation.
+    fn get(&self, rid: &i32) -> ServerResult<Reservation> {
+        use crate::domain::entities::reservations;
+        db_query!(
+            reservations::table
+                .find(rid)
+                .select(Reservation::as_select())
+                .first(db_conn!(self)),
+            ErrorCode::ReservationNotFound,
+            format!("Unable to find reservation {}!", rid)
+        )
+    }
+
+    /// Get all reservations, by the time they start.
+    fn all(&self) -> ServerResult<Vec<Reservation>> {
+        use crate::domain::entities::reservations;
+        db_query!(
+            reservations::table
+                .order((reservations::starts_at, reservations::id))
+                .select(Reservation::as_select())
+                .load(db_conn!(self)),
+            "Unable to find all reservations"
+        )
+    }
+
+    /// Change a booked reservation, its table must still be free for the new time.
+    fn update(&self, rid: &i32, changes: &ReservationChanges) -> ServerResult<Reservation> {
+        use crate::domain::entities::reservations;
+        if changes.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "Nothing to update!",
+            ));
+        }
+        db_conn!(self).transaction(|conn| {
+            let current = booked_reservation(conn, rid)?;
+            let booking = NewReservation {
+                name: &current.name,
+                phone: current.phone.as_ref(),
+                party_size: changes.party_size.unwrap_or(&current.party_size),
+                starts_at: changes.starts_at.unwrap_or(&current.starts_at),
+                ends_at: changes.ends_at.unwrap_or(&current.ends_at),
+                table_number: changes.table_number.or(current.table_number.as_ref()),
+            };
+            ensure_window(booking.starts_at, booking.ends_at)?;
+            book_table(conn, &booking, Some(rid))?;
+            let reservation = db_query!(
+                diesel::update(reservations::table.find(rid))
+                    .set(changes)
+                    .returning(Reservation::as_returning())
+                    .get_result(conn),
+                "Unable to update reservation!"
+            )?;
+            audit(
+                conn,
+                "update",
+                "reservation",
+                reservation.id,
+                snapshot(&current),
+                snapshot(&reservation),
+            )?;
+            Ok(reservation)
+        })
+    }
+
+    /// Cancel a booked reservation, its table is free again.
+    fn cancel(&self, rid: &i32) -> ServerResult<Reservation> {
+        use crate::domain::entities::reservations;
+        db_conn!(self).transaction(|conn| {
+            let current = booked_reservation(conn, rid)?;
+            let reservation = db_query!(
+                diesel::update(reservations::table.find(rid))
+                    .set(reservations::status.eq(ReservationStatus::Cancelled))
+                    .returning(Reservation::as_returning())
+                    .get_result(conn),
+                "Unable to cancel reservation!"
+            )?;
+            audit(
+                conn,
+                "cancel",
+                "reservation",
+                reservation.id,
+                snapshot(&current),
+                snapshot(&reservation),
+            )?;
+            Ok(reservation)
+        })
+    }
+
+    /// Active dining tables that seat the party, are not booked during the window and are
+    /// not seated then, smallest first. A party still at a table is expected to stay for a
+    /// default turn, or until now if it has already stayed longer.
+    fn available(
+        &self,
+        starts_at: &DateTime<Utc>,
+        ends_at: &DateTime<Utc>,
+        party_size: &i32,
+    ) -> ServerResult<Vec<DiningTable>> {
+        use crate::domain::entities::{dining_tables, reservations, tables};
+        use diesel::dsl::{exists, not};
+        ensure_window(starts_at, ends_at)?;
+        let booked = reservations::table
+            .filter(reservations::table_number.eq(dining_tables::table_number.nullable()))
+            .filter(reservations::status.eq(ReservationStatus::Booked))
+            .filter(reservations::starts_at.lt(ends_at))
+            .filter(reservations::ends_at.gt(starts_at));
+        let seated_after = if *starts_at <= Utc::now() {
+            DateTime::UNIX_EPOCH
+        } else {
+            *starts_at - Duration::minutes(DEFAULT_TURN_MINUTES)
+        };
+        let seated = tables::table
+            .filter(tables::table_number.eq(dining_tables::table_number))
+            .filter(tables::status.ne(SessionStatus::Closed))
+            .filter(tables::opened_at.lt(ends_at))
+            .filter(tables::opened_at.gt(seated_after));
+        db_query!(
+            dining_tables::table
+                .filter(dining_tables::active)
+                .filter(dining_tables::seats.ge(party_size))
+                .filter(not(exists(booked)))
+                .filter(not(exists(seated)))
+                .order((dining_tables::seats, dining_tables::table_number))
+                .select(DiningTable::as_select())
+                .load(db_conn!(self)),
+            "Unable to find available tables"
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct WaitlistFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub(crate) events: Sender<KitchenEvent>,
+}
+
+#[async_trait(?Send)]
+impl WaitlistRepository for WaitlistFactory {
+    /// Add a party to the end of the queue, quoting how long it waits.
+    fn create(&self, n: &NewWaitlistEntry) -> ServerResult<WaitlistEntry> {
+        use crate::domain::entities::waitlist_entries;
+        db_conn!(self).transaction(|conn| {
+            let waiting = waiting_parties(conn)?;
+            let quoted = estimate_queue(conn, &waiting, Some(n.party_size))?
+                .pop()
+                .flatten()
+                .ok_or_else(|| {
+                    ApiError::new(
+                        ErrorCode::PartyTooLarge,
+                        format!("No table seats a party of {}!", n.party_size),
+                    )
+                })?;
+            let entry = db_query!(
+                diesel::insert_into(waitlist_entries::table)
+                    .values((
+                        n,
+                        waitlist_entries::quoted_minutes
+                            .eq(i32::try_from(quoted).unwrap_or(i32::MAX)),
+                    ))
+                    .returning(WaitlistEntry::as_returning())
+                    .get_result(conn),
+                "Unable to add party to the waitlist"
+            )?;
+            audit(
+                conn,
+                "create",
+                "waitlist_entry",
+                entry.id,
+                None,
+                snapshot(&entry),
+            )?;
+            Ok(entry)
+        })
+    }
+
+    /// Parties waiting for a table in queue order, with how long they are expected to wait.
+    fn waiting(&self) -> ServerResult<Vec<(WaitlistEntry, Option<i64>)>> {
+        db_conn!(self).transaction(|conn| {
+            let waiting = waiting_parties(conn)?;
+            let waits = estimate_queue(conn, &waiting, None)?;
+            Ok(waiting.into_iter().zip(waits).collect())
+        })
+    }
+
+    /// Check in a waiting party at a table, it leaves the queue.
+    fn seat(&self, wid: &i32, n: &NewTable) -> ServerResult<(WaitlistEntry, Table)> {
+        use crate::domain::entities::waitlist_entries;
+        use chrono::prelude::*;
+        let (entry, table) = db_conn!(self).transaction(|conn| {
+            let waiting = waiting_party(conn, wid)?;
+            let table = check_in(
+                conn,
+                &NewTable {
+                    party_size: Some(&waiting.party_size),
+                    ..*n
+                },
+            )?;
+            let entry = diesel::update(waitlist_entries::table.find(wid))
+                .set((
+                    waitlist_entries::status.eq(WaitlistStatus::Seated),
+                    waitlist_entries::left_at.eq(Utc::now()),
+                    waitlist_entries::table_id.eq(table.id),
+                ))
+                .returning(WaitlistEntry::as_returning())
+                .get_result(conn)?;
+            let (before, after) = (snapshot(&waiting), snapshot(&entry));
+            audit(conn, "seat", "waitlist_entry", entry.id, before, after)?;
+            Ok::<_, ApiError>((entry, table))
+        })?;
+        publish(
+            &self.events,
+            KitchenEvent::TableCheckedIn {
+                table: table.clone(),
+            },
+        );
+        Ok((entry, table))
+    }
+
+    /// Remove a waiting party from the queue.
+    fn remove(&self, wid: &i32) -> ServerResult<WaitlistEntry> {
+        use crate::domain::entities::waitlist_entries;
+        use chrono::prelude::*;
+        db_conn!(self).transaction(|conn| {
+            let waiting = waiting_party(conn, wid)?;
+            let entry = db_query!(
+                diesel::update(waitlist_entries::table.find(wid))
+                    .set((
+                        waitlist_entries::status.eq(WaitlistStatus::Removed),
+                        waitlist_entries::left_at.eq(Utc::now()),
+                    ))
+                    .returning(WaitlistEntry::as_returning())
+                    .get_result(conn),
+                "Unable to remove party from the waitlist!"
+            )?;
+            let (before, after) = (snapshot(&waiting), snapshot(&entry));
+            audit(conn, "remove", "waitlist_entry", entry.id, before, after)?;
+            Ok(entry)
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TableFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub(crate) events: Sender<KitchenEvent>,
+}
+
+#[async_trait(?Send)]
+impl TableRepository for TableFactory {
+    /// Create a table, the party is seated at a registered table that fits it.
+    /// A party checking in for its reservation is linked to it.
+    fn create(&self, n: &NewTable) -> ServerResult<Table> {
+        let table = db_conn!(self).transaction(|conn| check_in(conn, n))?;
+        publish(
+            &self.events,
+            KitchenEvent::TableCheckedIn {
+                table: table.clone(),
+            },
+        );
+        Ok(table)
+    }
+
+    /// Get a page of the table sessions matching the filter.
+    fn all(
+        &self,
+        filter: &TableFilter,
+        page: &PageRequest<TableSort>,
+    ) -> ServerResult<Page<Table>> {
+        use crate::domain::entities::tables::dsl::*;
+        let mut query = tables.select(Table::as_select()).into_boxed();
+        if let Some(number) = filter.table_number {
+            query = query.filter(table_number.eq(number));
+        }
+        if !filter.statuses.is_empty() {
+            query = query.filter(status.eq_any(filter.statuses));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(opened_at.ge(from));
+        }
+        if let Some(until) = filter.until {
+            query = query.filter(opened_at.lt(until));
+        }
+        let query = match page.sort.field {
+            TableSort::OpenedAt => paginate!(query, page, opened_at, id, DateTime<Utc>),
+            TableSort::TableNumber => paginate!(query, page, table_number, id, i32),
+        };
+        let found = db_query!(query.load(db_conn!(self)), "Unable to find all tables!")?;
+        Ok(page.page(found, |table| {
+            let sort = &page.sort;
+            match sort.field {
+                TableSort::OpenedAt => Cursor::new(sort, &table.opened_at, table.id),
+                TableSort::TableNumber => Cursor::new(sort, &table.table_number, table.id),
+            }
+        }))
+    }
+
+    /// Get specific table..
+    fn get(&self, _id: &i32) -> ServerResult<Table> {
+        use crate::domain::entities::tables::dsl::*;
+        db_query!(
+            tables
+                .filter(table_number.eq(_id).and(status.ne(SessionStatus::Closed)))
+                .select(Table::as_select())
+                .first(db_conn!(self)),
+            ErrorCode::TableNotFound,
+            format!("Unable to find table {}!", _id)
+        )
+    }
+
+    /// Move a seated party to another free table, everything it ordered goes with it.
+    fn transfer(&self, tid: &i32, to: &i32) -> ServerResult<Table> {
+        use crate::domain::entities::{table_moves, tables};
+        if tid == to {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!("Unable to transfer table {} to itself!", tid),
+            ));
+        }
+        let table = db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            lock_dining_table(conn, to)?.seat(table.party_size.as_ref())?;
+            let occupied = format!("Unable to transfer, table {} is occupied!", to);
+            if !db_query!(get_table(conn, to), "Unable to find tables!")?.is_empty() {
+                return Err(ApiError::new(ErrorCode::TableOccupied, occupied));
+            }
+            let moved = diesel::update(tables::table.find(table.id))
+                .set(tables::table_number.eq(to))
+                .returning(Table::as_returning())
+                .get_result(conn)
+                .map_err(|err| table_occupied(err, &occupied))?;
+            diesel::insert_into(table_moves::table)
+                .values(NewTableMove {
+                    kind: MoveKind::Transfer,
+                    table_id: table.id,
+                    from_number: *tid,
+                    to_number: *to,
+                    merged_table_id: None,
+                })
+                .execute(conn)?;
+            audit(
+                conn,
+                "transfer",
+                "table",
+                table.id,
+                snapshot(&table),
+                snapshot(&moved),
+            )?;
+            Ok::<_, ApiError>(moved)
+        })?;
+        publish(
+            &self.events,
+            KitchenEvent::TableTransferred {
+                from: *tid,
+                to: *to,
+            },
+        );
+        Ok(table)
+    }
+
+    /// Bring the party seated at `other` to the table, everything it ordered is billed here.
+    /// The session of `other` is closed, there is nothing left on it to pay.
+    /// The table must seat both parties.
+    fn merge(&self, tid: &i32, other: &i32) -> ServerResult<Table> {
+        use crate::domain::entities::{
+            orders, payments, table_coupons, table_moves, tables, tickets,
+        };
+        use chrono::prelude::*;
+        if tid == other {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!("Unable to merge table {} with itself!", tid),
+            ));
+        }
+        let table = db_conn!(self).transaction(|conn| {
+            // Both sessions are locked in the same order, merges of the same tables wait in line.
+            let first = lock_table(conn, tid.min(other))?;
+            let second = lock_table(conn, tid.max(other))?;
+            let (table, merged) = if first.table_number == *tid {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            if let Some(billing) = [&table, &merged]
+                .into_iter()
+                .find(|session| session.status == SessionStatus::Billing)
+            {
+                return Err(ApiError::new(
+                    ErrorCode::BillAlreadySplit,
+                    format!("The bill of table {} is split!", billing.table_number),
+                ));
+            }
+            if merged.currency != table.currency {
+                return Err(ApiError::new(
+                    ErrorCode::CurrencyMismatch,
+                    format!(
+                        "Table {} pays in {}, table {} pays in {}!",
+                        merged.table_number, merged.currency, table.table_number, table.currency
+                    ),
+                ));
+            }
+            // A party of unknown size joining doesn't make the known count unknown.
+            let party_size = match (table.party_size, merged.party_size) {
+                (Some(seated), Some(joined)) => Some(seated + joined),
+                (seated, joined) => seated.or(joined),
+            };
+            lock_dining_table(conn, tid)?.seat(party_size.as_ref())?;
+            diesel::update(orders::table.filter(orders::table_id.eq(merged.id)))
+                .set(orders::table_id.eq(table.id))
+                .execute(conn)?;
+            diesel::update(tickets::table.filter(tickets::table_id.eq(merged.id)))
+                .set(tickets::table_id.eq(table.id))
+                .execute(conn)?;
+            diesel::update(payments::table.filter(payments::table_id.eq(merged.id)))
+                .set(payments::table_id.eq(table.id))
+                .execute(conn)?;
+            let coupons =
+                diesel::delete(table_coupons::table.filter(table_coupons::table_id.eq(merged.id)))
+                    .returning(table_coupons::promotion_id)
+                    .get_results::<i32>(conn)?;
+            diesel::insert_into(table_coupons::table)
+                .values(
+                    coupons
+                        .iter()
+                        .map(|promotion| {
+                            (
+                                table_coupons::table_id.eq(table.id),
+                                table_coupons::promotion_id.eq(promotion),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            let closed = diesel::update(tables::table.find(merged.id))
+                .set((
+                    tables::status.eq(SessionStatus::Closed),
+                    tables::closed_at.eq(Utc::now()),
+                    tables::total.eq(0),
+                ))
+                .returning(Table::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "close",
+                "table",
+                merged.id,
+                snapshot(&merged),
+                snapshot(&closed),
+            )?;
+            let before = snapshot(&table);
+            let table = diesel::update(tables::table.find(table.id))
+                .set(tables::party_size.eq(party_size))
+                .returning(Table::as_returning())
+                .get_result(conn)?;
+            audit(conn, "merge", "table", table.id, before, snapshot(&table))?;
+            diesel::insert_into(table_moves::table)
+                .values(NewTableMove {
+                    kind: MoveKind::Merge,
+                    table_id: table.id,
+                    from_number: merged.table_number,
+                    to_number: table.table_number,
+                    merged_table_id: Some(merged.id),
+                })
+                .execute(conn)?;
+            Ok::<_, ApiError>(table)
+        })?;
+        publish(
+            &self.events,
+            KitchenEvent::TablesMerged {
+                table_number: *tid,
+                merged: *other,
+            },
+        );
+        Ok(table)
+    }
+
+    /// Every move of the session seated at the table, the latest last.
+    fn history(&self, tid: &i32) -> ServerResult<Vec<TableMove>> {
+        use crate::domain::entities::table_moves;
+        let conn = db_conn!(self);
+        let table = open_table(conn, tid)?;
+        db_query!(
+            table_moves::table
+                .filter(table_moves::table_id.eq(table[0].id))
+                .order(table_moves::id)
+                .select(TableMove::as_select())
+                .load(conn),
+            "Unable to find table history!"
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TicketFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub(crate) events: Sender<KitchenEvent>,
+}
+
+#[async_trait(?Send)]
+impl TicketRepository for TicketFactory {
+    /// Create a ticket and all of its lines, or nothing at all.
+    fn create(&self, tid: &i32, lines: &[NewTicketLine]) -> ServerResult<(Ticket, Vec<Order>)> {
+        use crate::domain::entities::{items, orders, tickets};
+        use chrono::prelude::*;
+        if lines.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "A ticket needs at least one item!",
+            ));
+        }
+        if lines.iter().any(|line| line.quantity <= 0) {
+            return Err(ApiError::new(
+                ErrorCode::InvalidQuantity,
+                "Quantity must be positive!",
+            ));
+        }
+        let published_at = Local::now().to_rfc3339();
+        let (ticket, created) = db_conn!(self).transaction(|conn| {
+            // The table is locked so it can't be checked out while the ticket is written.
+            let table = lock_table(conn, tid)?;
+            let known = db_query!(
+                items::table
+                    .filter(items::id.eq_any(lines.iter().map(|line| line.item_id)))
+                    .select((
+                        items::id,
+                        items::description,
+                        (items::price, items::currency)
+                    ))
+                    .load::<(i32, String, Money)>(conn),
+                "Unable to find items!"
+            )?;
+            let mut ordered = Vec::with_capacity(lines.len());
+            for line in lines {
+                match known.iter().find(|(id, _, _)| *id == line.item_id) {
+                    None => {
+                        return Err(ApiError::new(
+                            ErrorCode::UnknownItem,
+                            format!("Unable to find item {}!", line.item_id),
+                        ))
+                    }
+                    Some((_, description, unit_price)) => {
+                        ensure_currency(&table, &line.item_id, unit_price.currency)?;
+                        ordered.push((line, description, unit_price));
+                    }
+                }
+            }
+            let table_id = table.id;
+            let ticket = db_query!(
+                diesel::insert_into(tickets::table)
+                    .values(&NewTicket {
+                        table_id: &table_id,
+                        published_at: &published_at,
+                    })
+                    .returning(Ticket::as_returning())
+                    .get_result(conn),
+                "Unable to create ticket!"
+            )?;
+            for line in lines {
+                reserve_item(conn, &line.item_id, &line.quantity)?;
+            }
+            // Lines are inserted one by one, so each gets its own modifiers.
+            let mut created = Vec::with_capacity(ordered.len());
+            for (line, description, unit_price) in ordered {
+                let order = db_query!(
+                    diesel::insert_into(orders::table)
+                        .values(&NewOrder {
+                            item_id: &line.item_id,
+                            table_id: &table_id,
+                            published_at: &published_at,
+                            quantity: &line.quantity,
+                            ticket_id: Some(&ticket.id),
+                            description,
+                            unit_price: &unit_price.amount,
+                            currency: unit_price.currency,
+                        })
+                        .returning(Order::as_returning())
+                        .get_result(conn),
+                    "Unable to create ticket!"
+                )?;
+                add_modifiers(conn, &order, &line.modifiers)?;
+                created.push(order);
+            }
+            let after = snapshot(&(&ticket, &created));
+            audit(conn, "create", "ticket", ticket.id, None, after)?;
+            ServerResult::Ok((ticket, created))
+        })?;
+        for order in created.iter() {
+            publish(
+                &self.events,
+                KitchenEvent::OrderCreated {
+                    table_number: *tid,
+                    order: order.clone(),
+                },
+            );
+        }
+        Ok((ticket, created))
+    }
+
+    /// Find tickets, with their lines, for a table.
+    fn find_table(&self, tid: &i32) -> ServerResult<Vec<(Ticket, Vec<Order>)>> {
+        let conn = db_conn!(self);
+        let table = open_table(conn, tid)?;
+        let found = db_query!(
+            Ticket::belonging_to(&table)
+                .select(Ticket::as_select())
+                .load(conn),
+            "Unable to find tickets for table"
+        )?;
+        let lines = db_query!(
+            Order::belonging_to(&found)
+                .select(Order::as_select())
+                .load(conn),
+            "Unable to find ticket lines"
+        )?;
+        Ok(lines
+            .grouped_by(&found)
+            .into_iter()
+            .zip(found)
+            .map(|(lines, ticket)| (ticket, lines))
+            .collect())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct BillFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub(crate) events: Sender<KitchenEvent>,
+}
+
+#[async_trait(?Send)]
+impl BillRepository for BillFactory {
+    /// Bill of a table that is still seated.
+    /// Nothing is stored, unless the bill is already being settled in parts.
+    fn draft(&self, tid: &i32) -> ServerResult<Bill> {
+        let conn = db_conn!(self);
+        let table = open_table(conn, tid)?;
+        if table[0].status == SessionStatus::Billing {
+            let (_, bill) = stored_bill(conn, &table[0])?;
+            return Ok(bill);
+        }
+        let (_, bill) = compute_bill(conn, &table[0])?;
+        Ok(bill)
+    }
+
+    /// Close the table session and store its final bill.
+    fn checkout(&self, tid: &i32) -> ServerResult<Bill> {
+        let bill = db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            if table.status == SessionStatus::Billing {
+                return Err(ApiError::new(
+                    ErrorCode::BillAlreadySplit,
+                    "The bill is split, every part must be paid!",
+                ));
+            }
+            let payments = Payment::belonging_to(&table)
+                .select(Payment::as_select())
+                .load(conn)?;
+            let (_, bill) = issue_bill(conn, &table)?;
+            let paid = Payment::paid(&payments, table.currency)?;
+            if paid.amount < bill.total.amount {
+                return Err(ApiError::new(
+                    ErrorCode::InsufficientPayment,
+                    format!("Only {} of {} is paid!", paid, bill.total),
+                ));
+            }
+            close_table(conn, &table, &bill)?;
+            Ok(bill)
+        })?;
+        publish(
+            &self.events,
+            KitchenEvent::TableCheckedOut {
+                table_number: *tid,
+                total: bill.total,
+            },
+        );
+        Ok(bill)
+    }
+
+    /// Issue the bill and split it between the guests, the table takes no more orders.
+    fn split(&self, tid: &i32, split: &BillSplit) -> ServerResult<(Bill, Vec<BillPart>)> {
+        use crate::domain::entities::{bill_parts, tables};
+        db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            if table.status == SessionStatus::Billing {
+                return Err(ApiError::new(
+                    ErrorCode::BillAlreadySplit,
+                    "The bill is already split!",
+                ));
+            }
+            let orders: Vec<(i32, Money)> = billed_orders(conn, &table)?
+                .iter()
+                .map(|order| (order.order_id, order.item.line_total))
+                .collect();
+            let (bill_id, bill) = issue_bill(conn, &table)?;
+            let parts = split.parts(&bill, &orders)?;
+            let parts = diesel::insert_into(bill_parts::table)
+                .values(
+                    parts
+                        .into_iter()
+                        .map(|part| {
+                            (
+                                bill_parts::bill_id.eq(bill_id),
+                                bill_parts::position.eq(part.position),
+                                bill_parts::amount.eq(part.amount.amount),
+                                bill_parts::currency.eq(part.amount.currency),
+                                bill_parts::order_ids.eq(part.order_ids),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .returning(BillPart::as_returning())
+                .get_results(conn)?;
+            diesel::update(tables::table.find(table.id))
+                .set(tables::status.eq(SessionStatus::Billing))
+                .execute(conn)?;
+            audit(
+                conn,
+                "split",
+                "bill",
+                bill_id,
+                None,
+                snapshot(&(&bill, &parts)),
+            )?;
+            Ok((bill, parts))
+        })
+    }
+
+    /// The split bill of a table, with what is left to be paid.
+    fn parts(&self, tid: &i32) -> ServerResult<(Bill, Vec<BillPart>)> {
+        let conn = db_conn!(self);
+        let table = open_table(conn, tid)?;
+        split_bill(conn, &table[0])
+    }
+
+    /// Settle a part of a split bill, the table is checked out with the last part.
+    fn settle(&self, tid: &i32, pid: &i32) -> ServerResult<(Bill, Vec<BillPart>)> {
+        use crate::domain::entities::{bill_parts, payments};
+        let (bill, parts, closed) = db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            let (bill, parts) = split_bill(conn, &table)?;
+            let Some(part) = parts.iter().find(|part| part.id == *pid) else {
+                return Err(ApiError::new(
+                    ErrorCode::BillPartNotFound,
+                    format!("Unable to find part {} of the bill!", pid),
+                ));
+            };
+            if part.paid_at.is_some() {
+                return Err(ApiError::new(
+                    ErrorCode::BillPartPaid,
+                    format!("Part {} of the bill is already paid!", pid),
+                ));
+            }
+            let payments = Payment::belonging_to(&table)
+                .filter(payments::bill_part_id.eq(pid))
+                .select(Payment::as_select())
+                .load(conn)?;
+            let paid = Payment::paid(&payments, table.currency)?;
+            if paid.amount < part.amount.amount {
+                return Err(ApiError::new(
+                    ErrorCode::InsufficientPayment,
+                    format!("Only {} of {} is paid!", paid, part.amount),
+                ));
+            }
+            let paid = diesel::update(bill_parts::table.find(pid))
+                .set(bill_parts::paid_at.eq(diesel::dsl::now))
+                .returning(BillPart::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "settle",
+                "bill_part",
+                paid.id,
+                snapshot(part),
+                snapshot(&paid),
+            )?;
+            let parts: Vec<BillPart> = parts
+                .into_iter()
+                .map(|part| {
+                    if part.id == paid.id {
+                        paid.clone()
+                    } else {
+                        part
+                    }
+                })
+                .collect();
+            let closed = parts.iter().all(|part| part.paid_at.is_some());
+            if closed {
+                close_table(conn, &table, &bill)?;
+            }
+            Ok::<_, ApiError>((bill, parts, closed))
+        })?;
+        if closed {
+            publish(
+                &self.events,
+                KitchenEvent::TableCheckedOut {
+                    table_number: *tid,
+                    total: bill.total,
+                },
+            );
+        }
+        Ok((bill, parts))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct PaymentFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl PaymentRepository for PaymentFactory {
+    /// Record a payment for a table that is still seated.
+    fn create(&self, tid: &i32, entry: &NewPaymentEntry) -> ServerResult<Payment> {
+        use crate::domain::entities::payments;
+        let tip = entry.tip.unwrap_or(Money::zero(entry.amount.currency));
+        if !entry.amount.is_positive() || tip.is_negative() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidAmount,
+                "A payment needs a positive amount!",
+            ));
+        }
+        entry.amount.same_currency(&tip)?;
+        db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            if entry.amount.currency != table.currency {
+                return Err(ApiError::new(
+                    ErrorCode::CurrencyMismatch,
+                    format!("Table {} pays in {}!", table.table_number, table.currency),
+                ));
+            }
+            if let Some(pid) = &entry.bill_part_id {
+                let (_, parts) = split_bill(conn, &table)?;
+                match parts.iter().find(|part| part.id == *pid) {
+                    None => {
+                        return Err(ApiError::new(
+                            ErrorCode::BillPartNotFound,
+                            format!("Unable to find part {} of the bill!", pid),
+                        ))
+                    }
+                    Some(part) if part.paid_at.is_some() => {
+                        return Err(ApiError::new(
+                            ErrorCode::BillPartPaid,
+                            format!("Part {} of the bill is already paid!", pid),
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            let payment = diesel::insert_into(payments::table)
+                .values(&NewPayment {
+                    table_id: &table.id,
+                    bill_part_id: entry.bill_part_id.as_ref(),
+                    tender: entry.tender,
+                    amount: &entry.amount.amount,
+                    tip: &tip.amount,
+                    currency: entry.amount.currency,
+                    reference: entry.reference.as_ref(),
+                })
+                .returning(Payment::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "create",
+                "payment",
+                payment.id,
+                None,
+                snapshot(&payment),
+            )?;
+            Ok(payment)
+        })
+    }
+
+    /// Every payment made by a table that is still seated, voided ones included.
+    fn find_table(&self, tid: &i32) -> ServerResult<Vec<Payment>> {
+        use crate::domain::entities::payments;
+        let conn = db_conn!(self);
+        let table = open_table(conn, tid)?;
+        db_query!(
+            Payment::belonging_to(&table)
+                .order(payments::id)
+                .select(Payment::as_select())
+                .load(conn),
+            "Unable to find payments for table!"
+        )
+    }
+
+    /// Void a payment, as long as the table hasn't checked out with it.
+    fn void(&self, tid: &i32, pid: &i32) -> ServerResult<Payment> {
+        use crate::domain::entities::{bill_parts, payments};
+        db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            let Some(payment) = Payment::belonging_to(&table)
+                .filter(payments::id.eq(pid))
+                .select(Payment::as_select())
+                .first(conn)
+                .optional()?
+            else {
+                return Err(ApiError::new(
+                    ErrorCode::PaymentNotFound,
+                    format!("Unable to find payment {}!", pid),
+                ));
+            };
+            if payment.voided_at.is_some() {
+                return Err(ApiError::new(
+                    ErrorCode::PaymentVoided,
+                    format!("Payment {} is already voided!", pid),
+                ));
+            }
+            if let Some(part) = payment.bill_part_id {
+                let settled: Option<chrono::DateTime<chrono::Utc>> = bill_parts::table
+                    .find(part)
+                    .select(bill_parts::paid_at)
+                    .first(conn)?;
+                if settled.is_some() {
+                    return Err(ApiError::new(
+                        ErrorCode::PaymentSettled,
+                        format!("Payment {} settled part of the bill!", pid),
+                    ));
+                }
+            }
+            let voided = diesel::update(payments::table.find(pid))
+                .set(payments::voided_at.eq(diesel::dsl::now))
+                .returning(Payment::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "void",
+                "payment",
+                voided.id,
+                snapshot(&payment),
+                snapshot(&voided),
+            )?;
+            Ok(voided)
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CategoryFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl CategoryRepository for CategoryFactory {
+    /// Create a category
+    fn create(&self, n: &NewCategory) -> ServerResult<Category> {
+        use crate::domain::entities::categories;
+        db_conn!(self).transaction(|conn| {
+            let created = db_query!(
+                diesel::insert_into(categories::table)
+                    .values(n)
+                    .returning(Category::as_returning())
+                    .get_result(conn),
+                "Unable to create category"
+            )?;
+            audit(
+                conn,
+                "create",
+                "category",
+                created.id,
+                None,
+                snapshot(&created),
+            )?;
+            Ok(created)
+        })
+    }
+
+    /// Get all categories
+    fn all(&self) -> ServerResult<Vec<Category>> {
+        use crate::domain::entities::categories;
+        db_query!(
+            categories::table
+                .order(categories::name)
+                .select(Category::as_select())
+                .load(db_conn!(self)),
+            "Unable to find all categories"
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct PromotionFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl PromotionRepository for PromotionFactory {
+    /// Create a promotion
+    fn create(&self, n: &NewPromotion) -> ServerResult<Promotion> {
+        use crate::domain::entities::promotions;
+        db_conn!(self).transaction(|conn| {
+            let created = db_query!(
+                diesel::insert_into(promotions::table)
+                    .values(n)
+                    .returning(Promotion::as_returning())
+                    .get_result(conn),
+                "Unable to create promotion"
+            )?;
+            audit(
+                conn,
+                "create",
+                "promotion",
+                created.id,
+                None,
+                snapshot(&created),
+            )?;
+            Ok(created)
+        })
+    }
+
+    /// Get all promotions, inactive ones included.
+    fn all(&self) -> ServerResult<Vec<Promotion>> {
+        use crate::domain::entities::promotions;
+        db_query!(
+            promotions::table
+                .order(promotions::id)
+                .select(Promotion::as_select())
+                .load(db_conn!(self)),
+            "Unable to find all promotions"
+        )
+    }
+
+    /// Stop applying a promotion, bills issued before keep their discount.
+    fn deactivate(&self, pid: &i32) -> ServerResult<Promotion> {
+        use crate::domain::entities::promotions;
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                promotions::table
+                    .find(pid)
+                    .select(Promotion::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::PromotionNotFound,
+                format!("Unable to find promotion {}!", pid)
+            )?;
+            let deactivated = diesel::update(promotions::table.find(pid))
+                .set(promotions::active.eq(false))
+                .returning(Promotion::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "deactivate",
+                "promotion",
+                deactivated.id,
+                snapshot(&before),
+                snapshot(&deactivated),
+            )?;
+            Ok(deactivated)
+        })
+    }
+
+    /// Redeem a coupon for a table, it applies to the bill from now on.
+    fn redeem(&self, tid: &i32, code: &str) -> ServerResult<Promotion> {
+        use crate::domain::entities::{promotions, table_coupons};
+        db_conn!(self).transaction(|conn| {
+            let table = lock_table(conn, tid)?;
+            let Some(promotion) = promotions::table
+                .filter(promotions::code.eq(code).and(promotions::active.eq(true)))
+                .select(Promotion::as_select())
+                .first(conn)
+                .optional()?
+            else {
+                return Err(ApiError::new(
+                    ErrorCode::PromotionNotFound,
+                    format!("Unable to find coupon {:?}!", code),
+                ));
+            };
+            let redeemed = diesel::insert_into(table_coupons::table)
+                .values((
+                    table_coupons::table_id.eq(table.id),
+                    table_coupons::promotion_id.eq(promotion.id),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            if redeemed == 0 {
+                return Err(ApiError::new(
+                    ErrorCode::CouponRedeemed,
+                    format!("Coupon {:?} is already redeemed!", code),
+                ));
+            }
+            let after = snapshot(&(&table, &promotion));
+            audit(conn, "redeem", "table", table.id, None, after)?;
+            Ok(promotion)
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TaxRateFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl TaxRateRepository for TaxRateFactory {
+    /// Create a tax rate
+    fn create(&self, n: &NewTaxRate) -> ServerResult<TaxRate> {
+        use crate::domain::entities::tax_rates;
+        db_conn!(self).transaction(|conn| {
+            let created = db_query!(
+                diesel::insert_into(tax_rates::table)
+                    .values(n)
+                    .returning(TaxRate::as_returning())
+                    .get_result(conn),
+                "Unable to create tax rate"
+            )?;
+            audit(
+                conn,
+                "create",
+                "tax_rate",
+                created.id,
+                None,
+                snapshot(&created),
+            )?;
+            Ok(created)
+        })
+    }
+
+    /// Get all tax rates, inactive ones included.
+    fn all(&self) -> ServerResult<Vec<TaxRate>> {
+        use crate::domain::entities::tax_rates;
+        db_query!(
+            tax_rates::table
+                .order(tax_rates::id)
+                .select(TaxRate::as_select())
+                .load(db_conn!(self)),
+            "Unable to find all tax rates"
+        )
+    }
+
+    /// Stop charging a tax, bills issued before keep it.
+    fn deactivate(&self, rid: &i32) -> ServerResult<TaxRate> {
+        use crate::domain::entities::tax_rates;
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                tax_rates::table
+                    .find(rid)
+                    .select(TaxRate::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::TaxRateNotFound,
+                format!("Unable to find tax rate {}!", rid)
+            )?;
+            let deactivated = diesel::update(tax_rates::table.find(rid))
+                .set(tax_rates::active.eq(false))
+                .returning(TaxRate::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "deactivate",
+                "tax_rate",
+                deactivated.id,
+                snapshot(&before),
+                snapshot(&deactivated),
+            )?;
+            Ok(deactivated)
+        })
+    }
+}
+
+/// Keys login tokens are signed and checked with, they are never logged.
+#[derive(Clone)]
+pub(crate) struct TokenKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl TokenKeys {
+    pub(crate) fn new(secret: &[u8]) -> Self {
+        TokenKeys {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+        }
+    }
+}
+
+impl fmt::Debug for TokenKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenKeys(..)")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct StaffFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub(crate) keys: TokenKeys,
+}
+
+#[async_trait(?Send)]
+impl StaffRepository for StaffFactory {
+    /// Create a staff account
+    fn create(&self, n: &NewStaff) -> ServerResult<Staff> {
+        use crate::domain::entities::staff_members;
+        db_conn!(self).transaction(|conn| {
+            let staff = db_query!(
+                diesel::insert_into(staff_members::table)
+                    .values(n)
+                    .returning(Staff::as_returning())
+                    .get_result(conn),
+                "Unable to create staff account"
+            )?;
+            audit(conn, "create", "staff", staff.id, None, snapshot(&staff))?;
+            Ok(staff)
+        })
+    }
+
+    /// Get all staff accounts, by name.
+    fn all(&self) -> ServerResult<Vec<Staff>> {
+        use crate::domain::entities::staff_members;
+        db_query!(
+            staff_members::table
+                .order(staff_members::name)
+                .select(Staff::as_select())
+                .load(db_conn!(self)),
+            "Unable to find staff accounts"
+        )
+    }
+
+    /// Update a staff account, it applies to tokens issued before as well.
+    /// Tokens stop working once the account is deactivated.
+    fn update(&self, sid: &i32, changes: &StaffChanges) -> ServerResult<Staff> {
+        use crate::domain::entities::staff_members;
+        if changes.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "Nothing to update!",
+            ));
+        }
+        db_conn!(self).transaction(|conn| {
+            let before = db_query!(
+                staff_members::table
+                    .find(sid)
+                    .select(Staff::as_select())
+                    .for_update()
+                    .first(conn),
+                ErrorCode::StaffNotFound,
+                format!("Unable to find staff member {}!", sid)
+            )?;
+            let staff = diesel::update(staff_members::table.find(sid))
+                .set(changes)
+                .returning(Staff::as_returning())
+                .get_result(conn)?;
+            audit(
+                conn,
+                "update",
+                "staff",
+                staff.id,
+                snapshot(&before),
+                snapshot(&staff),
+            )?;
+            Ok(staff)
+        })
+    }
+
+    /// Create an admin account, unless there is an active admin to create accounts already.
+    /// Servers starting together take turns, only the first one creates the admin.
+    fn bootstrap(&self, name: &str, secret: &str) -> ServerResult<Option<Staff>> {
+        use crate::domain::entities::staff_members;
+        db_conn!(self).transaction(|conn| {
+            db_query!(
+                diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+                    .bind::<diesel::sql_types::BigInt, _>(BOOTSTRAP_LOCK)
+                    .execute(conn),
+                "Unable to lock staff accounts"
+            )?;
+            let admins: i64 = db_query!(
+                staff_members::table
+                    .filter(staff_members::role.eq(Role::Admin))
+                    .filter(staff_members::active.eq(true))
+                    .count()
+                    .get_result(conn),
+                "Unable to find admins"
+            )?;
+            if admins > 0 {
+                return Ok(None);
+            }
+            let admin = NewStaff {
+                name: &name.to_string(),
+                role: Role::Admin,
+                secret_hash: hash_secret(secret)?,
+            };
+            let staff = db_query!(
+                diesel::insert_into(staff_members::table)
+                    .values(&admin)
+                    .returning(Staff::as_returning())
+                    .get_result(conn),
+                "Unable to create admin account"
+            )?;
+            audit(conn, "bootstrap", "staff", staff.id, None, snapshot(&staff))?;
+            Ok(Some(staff))
+        })
+    }
+
+    /// Log in with a PIN or password, issuing a signed token.
+    /// Every attempt is counted before the secret is verified, too many failed ones in a row
+    /// lock the account for a while.
+    fn login(&self, name: &str, secret: &str) -> ServerResult<Session> {
+        use crate::domain::entities::staff_members;
+        let invalid = || ApiError::new(ErrorCode::InvalidCredentials, "Invalid name or PIN!");
+        let conn = db_conn!(self);
+        let now = Utc::now();
+        let attempt = db_query!(
+            diesel::update(staff_members::table)
+                .filter(staff_members::name.eq(name))
+                .filter(staff_members::active.eq(true))
+                .filter(
+                    staff_members::locked_until
+                        .is_null()
+                        .or(staff_members::locked_until.le(now)),
+                )
+                .set(staff_members::failed_logins.eq(staff_members::failed_logins + 1))
+                .returning((
+                    Staff::as_returning(),
+                    staff_members::secret_hash,
+                    staff_members::failed_logins,
+                ))
+                .get_result::<(Staff, String, i32)>(conn)
+                .optional(),
+            "Unable to find staff member"
+        )?;
+        let Some((staff, secret_hash, attempts)) = attempt else {
+            reject_secret(secret);
+            let locked = db_query!(
+                staff_members::table
+                    .filter(staff_members::name.eq(name))
+                    .filter(staff_members::active.eq(true))
+                    .filter(staff_members::locked_until.gt(now))
+                    .count()
+                    .get_result::<i64>(conn),
+                "Unable to find staff member"
+            )?;
+            if locked > 0 {
+                return Err(ApiError::new(
+                    ErrorCode::AccountLocked,
+                    "Too many failed logins, try again later!",
+                ));
+            }
+            return Err(invalid());
+        };
+        if !verify_secret(secret, &secret_hash) {
+            if attempts >= MAX_FAILED_LOGINS {
+                db_query!(
+                    diesel::update(staff_members::table.find(staff.id))
+                        .set((
+                            staff_members::failed_logins.eq(0),
+                            staff_members::locked_until
+                                .eq(now + Duration::minutes(LOGIN_LOCKOUT_MINUTES)),
+                        ))
+                        .execute(conn),
+                    "Unable to lock staff account"
+                )?;
+            }
+            return Err(invalid());
+        }
+        db_query!(
+            diesel::update(staff_members::table.find(staff.id))
+                .set((
+                    staff_members::failed_logins.eq(0),
+                    staff_members::locked_until.eq(None::<DateTime<Utc>>),
+                ))
+                .execute(conn),
+            "Unable to reset failed logins"
+        )?;
+        let expires_at = Utc::now() + Duration::minutes(TOKEN_TTL_MINUTES);
+        let claims = Claims {
+            sub: staff.id,
+            role: staff.role,
+            exp: expires_at.timestamp(),
+        };
+        let token = encode(&Header::default(), &claims, &self.keys.encoding)
+            .map_err(|_| ApiError::new(ErrorCode::Internal, "Unable to issue token!"))?;
+        Ok(Session {
+            token,
+            expires_at,
+            staff,
+        })
+    }
+
+    /// Find the active staff member a token was issued to, the token must not have expired.
+    fn authenticate(&self, token: &str) -> ServerResult<Staff> {
+        use crate::domain::entities::staff_members;
+        let invalid = || ApiError::new(ErrorCode::Unauthenticated, "Invalid or expired token!");
+        let claims = decode::<Claims>(token, &self.keys.decoding, &Validation::default())
+            .map_err(|_| invalid())?
+            .claims;
+        db_query!(
+            staff_members::table
+                .find(claims.sub)
+                .filter(staff_members::active.eq(true))
+                .select(Staff::as_select())
+                .first(db_conn!(self))
+                .optional(),
+            "Unable to find staff member"
+        )?
+        .ok_or_else(invalid)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AuditFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+#[async_trait(?Send)]
+impl AuditRepository for AuditFactory {
+    /// Get a page of the audit entries matching the filter.
+    fn find(
+        &self,
+        filter: &AuditFilter,
+        page: &PageRequest<AuditSort>,
+    ) -> ServerResult<Page<AuditEntry>> {
+        use crate::domain::entities::audit_entries::dsl::*;
+        let mut query = audit_entries.select(AuditEntry::as_select()).into_boxed();
+        if let Some(kind) = filter.entity {
+            query = query.filter(entity.eq(kind));
+        }
+        if let Some(eid) = filter.entity_id {
+            query = query.filter(entity_id.eq(eid));
+        }
+        if let Some(staff_id) = filter.actor_id {
+            query = query.filter(actor_id.eq(staff_id));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(created_at.ge(from));
+        }
+        if let Some(until) = filter.until {
+            query = query.filter(created_at.lt(until));
+        }
+        let query = match page.sort.field {
+            AuditSort::CreatedAt => paginate!(query, page, created_at, id, DateTime<Utc>),
+        };
+        let found = db_query!(query.load(db_conn!(self)), "Unable to find audit entries")?;
+        Ok(page.page(found, |entry| match page.sort.field {
+            AuditSort::CreatedAt => Cursor::new(&page.sort, &entry.created_at, entry.id),
+        }))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct IdempotencyFactory {
+    pub(crate) connection_pool: Pool<ConnectionManager<PgConnection>>,
+    /// How long a key is kept, a retry after that is handled as a new request.
+    pub(crate) ttl: Duration,
+}
+
+#[async_trait(?Send)]
+impl IdempotencyRepository for IdempotencyFactory {
+    /// Claim a key of a staff member on a route for a request, unless it was claimed for the
+    /// same request before. Expired keys are forgotten first.
+    fn begin(&self, sid: &i32, r: &str, k: &str, print: &str) -> ServerResult<Attempt> {
+        use crate::domain::entities::idempotency_keys::dsl::*;
+        db_conn!(self).transaction(|conn| {
+            diesel::delete(idempotency_keys.filter(expires_at.le(diesel::dsl::now)))
+                .execute(conn)?;
+            let claimed = diesel::insert_into(idempotency_keys)
+                .values(&NewIdempotencyKey {
+                    staff_id: *sid,
+                    route: r,
+                    key: k,
+                    fingerprint: print,
+                    expires_at: Utc::now() + self.ttl,
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            if claimed > 0 {
+                return Ok(Attempt::First);
+            }
+            let stored = idempotency_keys
+                .find((sid, r, k))
+                .select(IdempotencyKey::as_select())
+                .first(conn)?;
+            if stored.fingerprint != print {
+                return Err(ApiError::new(
+                    ErrorCode::IdempotencyKeyReused,
+                    format!("Idempotency key {:?} was used for another request!", k),
+                ));
+            }
+            match (stored.status_code, stored.body) {
+                (Some(code), Some(response)) => Ok(Attempt::Replay {
+                    status_code: code as u16,
+                    body: response,
+                }),
+                _ => Err(ApiError::new(
+                    ErrorCode::IdempotencyKeyInFlight,
+                    format!("The request with idempotency key {:?} is still handled!", k),
+                )),
+            }
+        })
+    }
+
+    /// Keep the response to the request a key was claimed for.
+    fn complete(&self, sid: &i32, r: &str, k: &str, code: u16, response: &[u8]) -> ServerResult {
+        use crate::domain::entities::idempotency_keys::dsl::*;
+        db_query!(
+            diesel::update(idempotency_keys.find((sid, r, k)))
+                .set((status_code.eq(code as i16), body.eq(response)))
+                .execute(db_conn!(self)),
+            "Unable to keep response for idempotency key"
+        )?;
+        Ok(())
+    }
+
+    /// Forget a key whose request could not be handled, so it can be retried.
+    fn release(&self, sid: &i32, r: &str, k: &str) -> ServerResult {
+        use crate::domain::entities::idempotency_keys::dsl::*;
+        db_query!(
+            diesel::delete(idempotency_keys.find((sid, r, k))).execute(db_conn!(self)),
+            "Unable to release idempotency key"
+        )?;
+        Ok(())
+    }
 }
@@ -5,20 +5,23 @@ pub(crate) mod state;
 
 use anyhow::Result;
 use axum::{
+    http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
 use diesel::{Connection, PgConnection};
-use serde::Serialize;
 use std::env::var;
-use utoipa::ToSchema;
 
-/// Macro to connect to database as a mutable reference (otherwise return ServerError)
+use crate::domain::error::{ApiError, ErrorKind};
+
+/// Macro to connect to database as a mutable reference (otherwise return ApiError)
 #[macro_export]
 macro_rules! db_conn {
     ($self:ident) => {{
         let conn = $self.connection_pool.clone();
-        &mut conn.get().expect("Unable to connect to db")
+        &mut conn
+            .get()
+            .map_err($crate::domain::error::ApiError::unavailable)?
     }};
 }
 
@@ -30,15 +33,18 @@ pub(crate) fn db_connect() -> Result<PgConnection> {
     Ok(conn)
 }
 
-#[derive(Serialize, ToSchema, Debug)]
-pub(crate) struct ServerError {
-    error: String,
-}
-pub(crate) type ServerResult<T = ()> = Result<T, ServerError>;
-
-impl IntoResponse for ServerError {
+impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let code = axum::http::StatusCode::INTERNAL_SERVER_ERROR;
+        let code = match self.code.kind() {
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::Conflict => StatusCode::CONFLICT,
+            ErrorKind::Unprocessable => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorKind::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        };
         (code, Json(self)).into_response()
     }
 }
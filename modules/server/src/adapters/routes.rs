@@ -1,36 +1,98 @@
 //! Routes
 
+use std::fmt;
+
 use crate::{
-    adapters::state::ServerState,
-    application::repo::{ItemRepository, OrderRepository, TableRepository},
-    domain::entities::{
-        item::NewItem,
-        order::{NewOrder, Order},
-        table::NewTable,
+    adapters::{factories::ACTOR, state::ServerState},
+    application::config::{
+        DEFAULT_CURRENCY, DEVICE_HEADER, IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER,
+        MAX_IDEMPOTENCY_KEY_LENGTH, MAX_IDEMPOTENT_BODY_BYTES, WEBSOCKET_TOKEN_PROTOCOL,
+    },
+    application::repo::{
+        AuditRepository, BillRepository, CategoryRepository, DiningTableRepository,
+        IdempotencyRepository, InventoryRepository, ItemRepository, ModifierRepository,
+        OrderRepository, PaymentRepository, PromotionRepository, ReservationRepository,
+        StaffRepository, TableRepository, TaxRateRepository, TicketRepository, WaitlistRepository,
+    },
+    domain::{
+        entities::{
+            audit::{Actor, AuditEntry, AuditFilter},
+            bill::{Bill, BillCharge, BillDiscount, BillItem, BillTax},
+            bill_part::{BillPart, BillSplit},
+            category::{Category, NewCategory},
+            dining_table::{DiningTable, DiningTableChanges, NewDiningTable},
+            idempotency::Attempt,
+            ingredient::{Ingredient, NewIngredient, RecipeIngredient},
+            item::{ItemAvailability, ItemChanges, ItemFilter, ItemPrice, NewItem},
+            modifier::{Modifier, ModifierGroup, NewModifierEntry, NewModifierGroup},
+            order::{NewOrderLine, Order, OrderFilter, OrderStatus},
+            payment::{NewPaymentEntry, Payment, Tender},
+            promotion::{NewPromotion, Promotion, PromotionKind},
+            reservation::{NewReservation, Reservation, ReservationChanges, ReservationStatus},
+            staff::{hash_secret, Access, NewStaff, Role, Session, Staff, StaffChanges},
+            table::{NewTable, ServiceType, TableFilter},
+            table_move::{MoveKind, TableMove},
+            tax::{NewTaxRate, TaxRate},
+            ticket::NewTicketLine,
+            waitlist::{NewWaitlistEntry, WaitlistEntry, WaitlistStatus},
+        },
+        error::{ApiError, ErrorCode, ServerResult},
+        events::KitchenEvent,
+        money::{Currency, Money},
+        page::{Cursor, Page, PageRequest},
     },
 };
 use axum::{
-    extract::{Path, Request, State},
-    http::StatusCode,
+    body::{to_bytes, Body},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        MatchedPath, OriginalUri, Path, Query, Request, State,
+    },
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE, SEC_WEBSOCKET_PROTOCOL},
+        HeaderMap, HeaderValue, Method, StatusCode, Uri,
+    },
     middleware::{self, Next},
     response::Response,
-    routing::{delete, get, post},
-    Json, Router,
+    routing::{delete, get, patch, post},
+    Extension, Json, Router,
 };
-use log::error;
+use fastrace::future::FutureExt;
+use fastrace::prelude::{Span, SpanContext};
+use log::{error, warn};
 use rand::Rng;
-use utoipa::OpenApi;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
-use super::{
-    dto::{
-        request::{ItemCreateRequest, OrderCreateRequest, TableCreateRequest, TableGetRequest},
-        response::{
-            CheckoutResponse, ItemResponse, ItemsResponse, OrderResponse, TableResponse,
-            TablesResponse,
-        },
+use super::dto::{
+    request::{
+        AuditQuery, AvailabilityQuery, BatchMode, CategoryCreateRequest, CouponRedeemRequest,
+        DiningTableCreateRequest, DiningTableUpdateRequest, IngredientCreateRequest,
+        ItemAvailabilityRequest, ItemCreateRequest, ItemListQuery, ItemUpdateRequest, LoginRequest,
+        ModifierCreateRequest, ModifierGroupCreateRequest, OrderBatchQuery, OrderCreateRequest,
+        OrderListQuery, OrderStatusQuery, OrderStatusRequest, PaymentCreateRequest,
+        PromotionCreateRequest, RecipeLineRequest, RecipeRequest, ReservationCreateRequest,
+        ReservationUpdateRequest, RestockRequest, StaffCreateRequest, StaffUpdateRequest,
+        TableCreateRequest, TableGetRequest, TableListQuery, TableMergeRequest,
+        TableTransferRequest, TaxRateCreateRequest, TicketCreateRequest, TicketLineRequest,
+        WaitlistCreateRequest, WaitlistSeatRequest,
+    },
+    response::{
+        AuditEntriesResponse, BillResponse, BillSplitDetails, BillSplitResponse,
+        CategoriesResponse, CategoryResponse, CheckoutResponse, DiningTableResponse,
+        DiningTablesResponse, IngredientResponse, InventoryLine, InventoryResponse,
+        ItemPricesResponse, ItemResponse, ItemsPageResponse, ItemsResponse, ModifierGroupDetails,
+        ModifierGroupResponse, ModifierGroupsResponse, OrderBatchRejection, OrderBatchResponse,
+        OrderLineResult, OrderLineStatus, OrderResponse, OrdersPageResponse, PageInfo, PageLinks,
+        PaymentResponse, PaymentsResponse, PromotionResponse, PromotionsResponse, RecipeResponse,
+        ReservationResponse, ReservationsResponse, SeatedParty, SeatedPartyResponse,
+        SessionResponse, StaffListResponse, StaffResponse, TableHistoryResponse, TableResponse,
+        TablesPageResponse, TaxRateResponse, TaxRatesResponse, TicketDetails, TicketResponse,
+        TicketsResponse, WaitlistEntryResponse, WaitlistLine, WaitlistResponse,
     },
-    ServerResult,
 };
 
 #[allow(unused)] // Fallback function is used, false positive.
@@ -46,6 +108,161 @@ async fn is_checked_table_checked_in(req: Request, next: Next) -> ServerResult<R
     Ok(response)
 }
 
+/// Bearer token of a request, from its authorization header. A websocket is opened without one
+/// from a browser, it offers the token as subprotocol after `bearer` instead.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    if let Some(value) = headers.get(AUTHORIZATION) {
+        return value.to_str().ok()?.strip_prefix("Bearer ");
+    }
+    let mut protocols = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .map(str::trim);
+    if protocols.next()? != WEBSOCKET_TOKEN_PROTOCOL {
+        return None;
+    }
+    protocols.next()
+}
+
+/// Authenticate the bearer token of a request, handlers find the staff member in its extensions.
+/// The request is traced as part of the trace given in its `traceparent` header, if any,
+/// and the writes it makes are audited as made by the staff member.
+async fn authenticate(
+    State(state): State<ServerState>,
+    mut req: Request,
+    next: Next,
+) -> ServerResult<Response> {
+    let token = bearer_token(req.headers())
+        .ok_or_else(|| ApiError::new(ErrorCode::Unauthenticated, "Missing bearer token!"))?;
+    let staff = state.staff_repository.authenticate(token)?;
+    let headers = req.headers();
+    let parent = headers
+        .get("traceparent")
+        .and_then(|value| value.to_str().ok())
+        .and_then(SpanContext::decode_w3c_traceparent)
+        .unwrap_or_else(SpanContext::random);
+    let actor = Actor {
+        staff_id: staff.id,
+        device: headers
+            .get(DEVICE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        trace_id: parent.trace_id.0.to_string(),
+    };
+    req.extensions_mut().insert(staff);
+    let root = Span::root(format!("{} {}", req.method(), req.uri().path()), parent);
+    Ok(ACTOR.scope(actor, next.run(req).in_span(root)).await)
+}
+
+/// Only let staff through whose role is allowed to use the routes.
+async fn authorize(
+    State(access): State<Access>,
+    req: Request,
+    next: Next,
+) -> ServerResult<Response> {
+    let Some(staff) = req.extensions().get::<Staff>() else {
+        return Err(ApiError::new(ErrorCode::Unauthenticated, "Not logged in!"));
+    };
+    let write = !matches!(*req.method(), Method::GET | Method::HEAD);
+    if !access.allows(&staff.role, write) {
+        return Err(ApiError::new(
+            ErrorCode::Forbidden,
+            format!("The {} role is not allowed to do that!", staff.role),
+        ));
+    }
+    Ok(next.run(req).await)
+}
+
+/// Answer a retried request with the response it got the first time, when it is made with an
+/// `Idempotency-Key` header. A key is only good for one request, its keys are kept apart per
+/// staff member and route. Requests that failed on the server side aren't kept, they may be retried.
+async fn idempotent(
+    State(state): State<ServerState>,
+    Extension(staff): Extension<Staff>,
+    matched: MatchedPath,
+    req: Request,
+    next: Next,
+) -> ServerResult<Response> {
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(req).await);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "An idempotency key needs 1 to {} visible characters!",
+                    MAX_IDEMPOTENCY_KEY_LENGTH
+                ),
+            )
+        })?;
+    let route = format!("{} {}", req.method(), matched.as_str());
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_IDEMPOTENT_BODY_BYTES)
+        .await
+        .map_err(|_| ApiError::new(ErrorCode::InvalidRequest, "Request body is too large!"))?;
+    let mut fingerprint = Sha256::new();
+    fingerprint.update(parts.method.as_str());
+    fingerprint.update(parts.uri.to_string());
+    fingerprint.update(&body);
+    let fingerprint = hex::encode(fingerprint.finalize());
+    if let Attempt::Replay { status_code, body } =
+        state
+            .idempotency_repository
+            .begin(&staff.id, &route, &key, &fingerprint)?
+    {
+        let mut response = Response::new(Body::from(body));
+        *response.status_mut() =
+            StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+        return Ok(response);
+    }
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        state
+            .idempotency_repository
+            .release(&staff.id, &route, &key)?;
+        return Ok(response);
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_IDEMPOTENT_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(err) => {
+            state
+                .idempotency_repository
+                .release(&staff.id, &route, &key)?;
+            error!(
+                "Unable to read response for idempotency key {:?}: {}",
+                key, err
+            );
+            return Err(ApiError::new(
+                ErrorCode::Internal,
+                "Unable to read response!",
+            ));
+        }
+    };
+    // The request is handled, failing to keep its response only refuses retries until the key expires.
+    if let Err(err) =
+        state
+            .idempotency_repository
+            .complete(&staff.id, &route, &key, parts.status.as_u16(), &body)
+    {
+        error!(
+            "Unable to keep response for idempotency key {:?}: {}",
+            key, err.error
+        );
+    }
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
 /// Find order by table number.
 #[fastrace::trace]
 #[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
@@ -55,7 +272,9 @@ async fn is_checked_table_checked_in(req: Request, next: Next) -> ServerResult<R
         path = "/api/v1/orders/:id",
         responses(
             (status = 200, description = "Success found order", body = [OrderResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 404, description = "Order not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
 async fn get_order_by_id(
@@ -68,113 +287,252 @@ async fn get_order_by_id(
     }
 }
 
-/// Find all orders
+/// How a page was cut and links to it, the next link is the request made with the next cursor.
+fn page_meta<F: fmt::Display, T>(
+    uri: &Uri,
+    request: &PageRequest<F>,
+    page: &Page<T>,
+) -> (PageInfo, PageLinks) {
+    let next_cursor = page.next.as_ref().map(Cursor::encode);
+    let next = next_cursor.as_ref().map(|cursor| {
+        let mut params: Vec<&str> = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|param| !param.is_empty() && !param.starts_with("cursor="))
+            .collect();
+        let cursor = format!("cursor={}", cursor);
+        params.push(&cursor);
+        format!("{}?{}", uri.path(), params.join("&"))
+    });
+    (
+        PageInfo {
+            limit: request.limit,
+            sort: request.sort.to_string(),
+            next_cursor,
+        },
+        PageLinks {
+            current: uri.to_string(),
+            next,
+        },
+    )
+}
+
+/// Find orders, a page at a time.
 #[fastrace::trace]
-#[logcall::logcall(input = "state = {state:?}")]
+#[logcall::logcall(input = "state = {state:?}, query = {query:?}")]
 #[utoipa::path(
         get,
-        path = "/api/v1/orders/:id",
+        path = "/api/v1/orders",
+        params(OrderListQuery),
         responses(
-            (status = 200, description = "Success found order", body = [OrderResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Success found orders", body = OrdersPageResponse),
+            (status = 422, description = "Invalid filter, sort or cursor", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_orders(State(state): State<ServerState>) -> ServerResult<Json<OrderResponse>> {
-    match state.order_repository.all() {
-        Ok(res) => Ok(Json(OrderResponse { data: res })),
+async fn get_orders(
+    State(state): State<ServerState>,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<OrderListQuery>,
+) -> ServerResult<Json<OrdersPageResponse>> {
+    let statuses = query.statuses()?;
+    let filter = OrderFilter {
+        statuses: &statuses,
+        table_number: query.table_number.as_ref(),
+        item_id: query.item_id.as_ref(),
+        from: query.from,
+        until: query.until,
+    };
+    let request = query.page()?;
+    match state.order_repository.all(&filter, &request) {
+        Ok(page) => {
+            let (info, links) = page_meta(&uri, &request, &page);
+            Ok(Json(OrdersPageResponse {
+                data: page.entries,
+                page: info,
+                links,
+            }))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Advance an order along its lifecycle.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, staff = {staff:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = OrderStatusRequest,
+        path = "/api/v1/orders/:id/status",
+        responses(
+            (status = 200, description = "Success updated order status", body = [OrderResponse]),
+            (status = 403, description = "Order started, only managers can cancel it", body = ApiError),
+            (status = 404, description = "Order not found", body = ApiError),
+            (status = 409, description = "Illegal status transition or table checked out", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn update_order_status(
+    State(state): State<ServerState>,
+    Extension(staff): Extension<Staff>,
+    Path(id): Path<i32>,
+    Json(req): Json<OrderStatusRequest>,
+) -> ServerResult<Json<OrderResponse>> {
+    match state
+        .order_repository
+        .set_status(&id, &req.status, staff.role.manages())
+    {
+        Ok(res) => Ok(Json(OrderResponse { data: vec![res] })),
         Err(err) => Err(err),
     }
 }
 
-/// Create an order.
+/// Create a batch of orders.
 #[fastrace::trace]
-#[logcall::logcall(input = "state = {state:?}, reqs = {reqs:?}")]
+#[logcall::logcall(input = "state = {state:?}, query = {query:?}, reqs = {reqs:?}")]
 #[utoipa::path(
         post,
         request_body = Vec<OrderCreateRequest>,
         path = "/api/v1/orders",
+        params(
+            OrderBatchQuery,
+            ("Idempotency-Key" = Option<String>, Header, description = "Key to retry the request with, a retry gets the original response"),
+        ),
         responses(
-            (status = 200, description = "Success created order", body = [String]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Success created every order", body = OrderBatchResponse),
+            (status = 207, description = "Partially created orders, see each line", body = OrderBatchResponse),
+            (status = 409, description = "Request with the idempotency key still handled", body = ApiError),
+            (status = 422, description = "Nothing was created: the outcome of each line, or an error if the idempotency key was used for another request", body = OrderBatchRejection),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
 async fn create_order(
     State(state): State<ServerState>,
+    Query(query): Query<OrderBatchQuery>,
     Json(reqs): Json<Vec<OrderCreateRequest>>,
-) -> ServerResult<String> {
-    use chrono::prelude::*;
-    let mut responses = Vec::new();
-    for req in reqs.iter() {
-        let table_id = state.table_repository.get(&req.table_id);
-        if table_id.is_err() {
-            return Err(crate::adapters::ServerError {
-                error: "Unable to find table!".to_string(),
-            });
-        }
-        let order = NewOrder {
-            item_id: &req.item_id,
-            table_id: &table_id.expect("Unable to find table id").id,
-            published_at: &Local::now().to_rfc3339(),
-            quantity: &req.quantity,
-        };
-        match state.order_repository.create(&order) {
-            Ok(_) => responses.push(format!(
-                "Order for item_id {} added successfully.",
-                req.item_id
-            )),
-            Err(err) => responses.push(format!(
-                "Failed to add order for item_id {}: {:?}",
-                req.item_id, err
-            )),
-        }
-    }
-    Ok(responses.join("\n"))
+) -> ServerResult<(StatusCode, Json<OrderBatchResponse>)> {
+    let lines: Vec<NewOrderLine> = reqs
+        .iter()
+        .map(|req| NewOrderLine {
+            table_number: req.table_id,
+            item_id: req.item_id,
+            quantity: req.quantity,
+            modifiers: req.modifiers.clone(),
+        })
+        .collect();
+    let (committed, results) = state
+        .order_repository
+        .create_batch(&lines, query.mode == BatchMode::Partial)?;
+    let data: Vec<OrderLineResult> = reqs
+        .iter()
+        .zip(results)
+        .enumerate()
+        .map(|(index, (req, result))| {
+            let (status, order_id, error) = match result {
+                Ok(order) if committed => (OrderLineStatus::Created, Some(order.id), None),
+                Ok(_) => (OrderLineStatus::RolledBack, None, None),
+                Err(err) => (OrderLineStatus::Failed, None, Some(err)),
+            };
+            OrderLineResult {
+                index,
+                item_id: req.item_id,
+                table_id: req.table_id,
+                status,
+                order_id,
+                error,
+            }
+        })
+        .collect();
+    let code = if !committed {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else if data
+        .iter()
+        .any(|line| line.status == OrderLineStatus::Failed)
+    {
+        StatusCode::MULTI_STATUS
+    } else {
+        StatusCode::OK
+    };
+    Ok((code, Json(OrderBatchResponse { committed, data })))
 }
 
 /// Delete an order.
 #[fastrace::trace]
-#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[logcall::logcall(input = "state = {state:?}, staff = {staff:?}, id = {id:?}")]
 #[utoipa::path(
         delete,
         request_body = i32,
         path = "/api/v1/orders/:id",
         responses(
             (status = 204, description = "Success deleted order", body = [String]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 403, description = "Order started, only managers can delete it", body = ApiError),
+            (status = 404, description = "Order not found", body = ApiError),
+            (status = 409, description = "Table checked out", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
 async fn delete_order(
     State(state): State<ServerState>,
+    Extension(staff): Extension<Staff>,
     Path(id): Path<i32>,
 ) -> ServerResult<StatusCode> {
-    match state.order_repository.delete(&id) {
+    match state.order_repository.delete(&id, staff.role.manages()) {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(err) => Err(err),
     }
 }
 
-fn order_routes() -> Router<ServerState> {
+fn order_routes(state: &ServerState) -> Router<ServerState> {
+    let idempotent = middleware::from_fn_with_state(state.clone(), idempotent);
     Router::new()
-        .route("/", post(create_order).get(get_orders))
+        .route("/", post(create_order).layer(idempotent).get(get_orders))
         .route("/:id", get(get_order_by_id).delete(delete_order))
+        .route("/:id/status", post(update_order_status))
         .route_layer(middleware::from_fn(is_checked_table_checked_in))
+        .route_layer(middleware::from_fn_with_state(Access::SERVICE, authorize))
 }
 
 // TODO These can be converted to macros
-/// Get items.
+/// Get items, a page at a time.
 #[fastrace::trace]
-#[logcall::logcall(input = "state = {state:?}")]
+#[logcall::logcall(input = "state = {state:?}, query = {query:?}")]
 #[utoipa::path(
         get,
         path = "/api/v1/items",
+        params(ItemListQuery),
         responses(
-            (status = 200, description = "Successfully found items", body = [ItemsResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully found items", body = ItemsPageResponse),
+            (status = 422, description = "Invalid filter, sort or cursor", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_items(State(state): State<ServerState>) -> ServerResult<Json<ItemsResponse>> {
-    match state.item_repository.all() {
-        Ok(res) => Ok(Json(ItemsResponse { data: res })),
+async fn get_items(
+    State(state): State<ServerState>,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<ItemListQuery>,
+) -> ServerResult<Json<ItemsPageResponse>> {
+    let ids = query.ids()?;
+    let filter = ItemFilter {
+        ids: &ids,
+        category_id: query.category_id.as_ref(),
+        available: query.available,
+    };
+    let request = query.page()?;
+    match state.item_repository.all(&filter, &request) {
+        Ok(page) => {
+            let (info, links) = page_meta(&uri, &request, &page);
+            Ok(Json(ItemsPageResponse {
+                data: page.entries,
+                page: info,
+                links,
+            }))
+        }
         Err(err) => Err(err),
     }
 }
@@ -187,7 +545,9 @@ async fn get_items(State(state): State<ServerState>) -> ServerResult<Json<ItemsR
         path = "/api/v1/items/:id",
         responses(
             (status = 200, description = "Successfully found item", body = [ItemResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
 async fn get_item(
@@ -209,7 +569,9 @@ async fn get_item(
         path = "/api/v1/items",
         responses(
             (status = 200, description = "Successfully created item", body = [ItemResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 422, description = "Unknown category", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
 async fn create_item(
@@ -220,7 +582,9 @@ async fn create_item(
     let item = NewItem {
         description: &req.description,
         estimated_minutes: &rng.gen_range(5..=15),
-        price: &req.price,
+        price: &req.price.amount,
+        currency: req.price.currency,
+        category_id: req.category_id.as_ref(),
     };
     match state.item_repository.create(&item) {
         Ok(res) => Ok(Json(ItemResponse { data: res })),
@@ -228,390 +592,4384 @@ async fn create_item(
     }
 }
 
-fn item_routes() -> Router<ServerState> {
-    Router::new()
-        .route("/", post(create_item).get(get_items))
-        .route("/:id", get(get_item))
+/// Update item.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        patch,
+        request_body = ItemUpdateRequest,
+        path = "/api/v1/items/:id",
+        responses(
+            (status = 200, description = "Successfully updated item", body = [ItemResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 422, description = "Nothing to update, unknown category or price in another currency", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn update_item(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<ItemUpdateRequest>,
+) -> ServerResult<Json<ItemResponse>> {
+    let changes = ItemChanges {
+        description: req.description.as_ref(),
+        price: req.price.as_ref().map(|price| &price.amount),
+        currency: req.price.map(|price| price.currency),
+        category_id: req.category_id.as_ref(),
+    };
+    match state.item_repository.update(&id, &changes) {
+        Ok(res) => Ok(Json(ItemResponse { data: res })),
+        Err(err) => Err(err),
+    }
 }
 
-/// Get table.
+/// Get the price history of an item.
+#[fastrace::trace]
 #[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
 #[utoipa::path(
         get,
-        path = "/api/v1/tables/:id",
+        path = "/api/v1/items/:id/prices",
         responses(
-            (status = 200, description = "Successfully found item", body = [TableResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully found item prices", body = [ItemPricesResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_table(
+async fn get_item_prices(
     State(state): State<ServerState>,
     Path(id): Path<i32>,
-) -> ServerResult<Json<TableResponse>> {
-    match state.table_repository.get(&id) {
-        Ok(res) => Ok(Json(TableResponse { data: res })),
+) -> ServerResult<Json<ItemPricesResponse>> {
+    match state.item_repository.prices(&id) {
+        Ok(res) => Ok(Json(ItemPricesResponse { data: res })),
         Err(err) => Err(err),
     }
 }
 
-/// Get table orders.
+/// Archive item, it is taken off the menu.
+#[fastrace::trace]
 #[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
 #[utoipa::path(
-        get,
-        path = "/api/v1/tables/:id/orders",
+        delete,
+        path = "/api/v1/items/:id",
         responses(
-            (status = 200, description = "Successfully found item", body = [OrderResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully archived item", body = [ItemResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_table_orders(
+async fn archive_item(
     State(state): State<ServerState>,
     Path(id): Path<i32>,
-) -> ServerResult<Json<OrderResponse>> {
-    match state.order_repository.find_table(&id) {
-        Ok(res) => Ok(Json(OrderResponse { data: res })),
+) -> ServerResult<Json<ItemResponse>> {
+    match state.item_repository.archive(&id) {
+        Ok(res) => Ok(Json(ItemResponse { data: res })),
         Err(err) => Err(err),
     }
 }
 
-/// Get table order.
-#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+/// Mark an item available or sold out.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = ItemAvailabilityRequest,
+        path = "/api/v1/items/:id/availability",
+        responses(
+            (status = 200, description = "Successfully updated item availability", body = [ItemResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 422, description = "Invalid remaining portions", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn set_item_availability(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<ItemAvailabilityRequest>,
+) -> ServerResult<Json<ItemResponse>> {
+    let availability = ItemAvailability::new(req.available, req.remaining.as_ref());
+    match state.item_repository.set_availability(&id, &availability) {
+        Ok(res) => Ok(Json(ItemResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get the modifier groups of an item.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
 #[utoipa::path(
         get,
-        path = "/api/v1/tables/:id/orders/:id",
+        path = "/api/v1/items/:id/modifier_groups",
         responses(
-            (status = 200, description = "Successfully found order", body = [OrderResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully found modifier groups", body = [ModifierGroupsResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_table_order(
+async fn get_modifier_groups(
     State(state): State<ServerState>,
-    Path(ids): Path<(i32, i32)>,
-) -> ServerResult<Json<OrderResponse>> {
-    match state.order_repository.find_table(&ids.0) {
-        Ok(res) => {
-            let r: Vec<Order> = res.into_iter().filter(|i| i.id == ids.1).collect();
-            Ok(Json(OrderResponse { data: r }))
-        }
+    Path(id): Path<i32>,
+) -> ServerResult<Json<ModifierGroupsResponse>> {
+    match state.modifier_repository.find_item(&id) {
+        Ok(res) => Ok(Json(ModifierGroupsResponse {
+            data: res
+                .into_iter()
+                .map(|(group, modifiers)| ModifierGroupDetails::new(group, modifiers))
+                .collect(),
+        })),
         Err(err) => Err(err),
     }
 }
 
-/// Get table items.
-#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+/// Create a modifier group of an item.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = ModifierGroupCreateRequest,
+        path = "/api/v1/items/:id/modifier_groups",
+        responses(
+            (status = 200, description = "Successfully created modifier group", body = [ModifierGroupResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 409, description = "Modifier group already exists", body = ApiError),
+            (status = 422, description = "Invalid selections or price delta", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_modifier_group(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<ModifierGroupCreateRequest>,
+) -> ServerResult<Json<ModifierGroupResponse>> {
+    let (min_selections, max_selections) = req.selections()?;
+    let group = NewModifierGroup {
+        item_id: &id,
+        name: &req.name,
+        min_selections: &min_selections,
+        max_selections: &max_selections,
+    };
+    let entries: Vec<NewModifierEntry> = req
+        .modifiers
+        .iter()
+        .map(|modifier| NewModifierEntry {
+            name: modifier.name.clone(),
+            price_delta: modifier.price_delta,
+        })
+        .collect();
+    match state.modifier_repository.create(&group, &entries) {
+        Ok((group, modifiers)) => Ok(Json(ModifierGroupResponse {
+            data: ModifierGroupDetails::new(group, modifiers),
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get the recipe of an item.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
 #[utoipa::path(
         get,
-        path = "/api/v1/tables/:id/items/:id",
+        path = "/api/v1/items/:id/recipe",
         responses(
-            (status = 200, description = "Successfully found order", body = [ItemsResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully found recipe", body = [RecipeResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_table_items(
+async fn get_recipe(
     State(state): State<ServerState>,
-    Path(ids): Path<(i32, i32)>,
-) -> ServerResult<Json<ItemsResponse>> {
-    match state.order_repository.find_table(&ids.0) {
-        Ok(res) => {
-            let r: Vec<Order> = res.into_iter().filter(|i| i.item_id == ids.1).collect();
-            let mut items = vec![];
-            for order in r {
-                match state.item_repository.get(&order.item_id) {
-                    Ok(item) => items.push(item),
-                    Err(err) => {
-                        error!("Failed to fetch item with ID {}: {:?}", order.item_id, err);
-                        return Err(err);
-                    }
-                }
-            }
+    Path(id): Path<i32>,
+) -> ServerResult<Json<RecipeResponse>> {
+    match state.inventory_repository.recipe(&id) {
+        Ok(res) => Ok(Json(RecipeResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
 
-            Ok(Json(ItemsResponse { data: items }))
-        }
+/// Replace the recipe of an item, its orders take the ingredients from stock.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = RecipeRequest,
+        path = "/api/v1/items/:id/recipe",
+        responses(
+            (status = 200, description = "Successfully updated recipe", body = [RecipeResponse]),
+            (status = 404, description = "Item not found", body = ApiError),
+            (status = 409, description = "Ingredient used twice", body = ApiError),
+            (status = 422, description = "Invalid quantity or unknown ingredient", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn set_recipe(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<RecipeRequest>,
+) -> ServerResult<Json<RecipeResponse>> {
+    let recipe: Vec<RecipeIngredient> = req
+        .ingredients
+        .iter()
+        .map(|line| RecipeIngredient {
+            item_id: id,
+            ingredient_id: line.ingredient_id,
+            quantity: line.quantity,
+        })
+        .collect();
+    match state.inventory_repository.set_recipe(&id, &recipe) {
+        Ok(res) => Ok(Json(RecipeResponse { data: res })),
         Err(err) => Err(err),
     }
 }
 
-/// Delete table order.
-#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+fn item_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_item).get(get_items))
+        .route(
+            "/:id",
+            get(get_item).patch(update_item).delete(archive_item),
+        )
+        .route("/:id/prices", get(get_item_prices))
+        .route("/:id/availability", post(set_item_availability))
+        .route("/:id/recipe", post(set_recipe).get(get_recipe))
+        .route(
+            "/:id/modifier_groups",
+            post(create_modifier_group).get(get_modifier_groups),
+        )
+        .route_layer(middleware::from_fn_with_state(Access::SETUP, authorize))
+}
+
+/// Get categories.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
 #[utoipa::path(
-        delete,
-        path = "/api/v1/tables/:id/orders/:id",
+        get,
+        path = "/api/v1/categories",
         responses(
-            (status = 204, description = "Successfully deleted item", body = [String]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully found categories", body = [CategoriesResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn delete_table_order(
+async fn get_categories(
     State(state): State<ServerState>,
-    Path(ids): Path<(i32, i32)>,
-) -> ServerResult<StatusCode> {
-    match state.order_repository.delete_table_order(&ids.0, &ids.1) {
-        Ok(_) => Ok(StatusCode::NO_CONTENT),
+) -> ServerResult<Json<CategoriesResponse>> {
+    match state.category_repository.all() {
+        Ok(res) => Ok(Json(CategoriesResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create category.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = CategoryCreateRequest,
+        path = "/api/v1/categories",
+        responses(
+            (status = 200, description = "Successfully created category", body = [CategoryResponse]),
+            (status = 409, description = "Category already exists", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_category(
+    State(state): State<ServerState>,
+    Json(req): Json<CategoryCreateRequest>,
+) -> ServerResult<Json<CategoryResponse>> {
+    match state
+        .category_repository
+        .create(&NewCategory { name: &req.name })
+    {
+        Ok(res) => Ok(Json(CategoryResponse { data: res })),
         Err(err) => Err(err),
     }
 }
 
-/// Get tables.
+fn category_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_category).get(get_categories))
+        .route_layer(middleware::from_fn_with_state(Access::SETUP, authorize))
+}
+
+/// Get the inventory, with the ingredients running low.
+#[fastrace::trace]
 #[logcall::logcall(input = "state = {state:?}")]
 #[utoipa::path(
         get,
-        path = "/api/v1/tables",
+        path = "/api/v1/inventory",
         responses(
-            (status = 200, description = "Successfully found item", body = [TablesResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully found inventory", body = [InventoryResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn get_tables(State(state): State<ServerState>) -> ServerResult<Json<TablesResponse>> {
-    match state.table_repository.all() {
-        Ok(res) => Ok(Json(TablesResponse { data: res })),
+async fn get_inventory(State(state): State<ServerState>) -> ServerResult<Json<InventoryResponse>> {
+    match state.inventory_repository.all() {
+        Ok(res) => Ok(Json(InventoryResponse {
+            data: res.into_iter().map(InventoryLine::new).collect(),
+        })),
         Err(err) => Err(err),
     }
 }
 
-/// Checks in a table.
+/// Create ingredient.
+#[fastrace::trace]
 #[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
 #[utoipa::path(
         post,
-        request_body = TableCreateRequest,
-        path = "/api/v1/tables/check_in",
+        request_body = IngredientCreateRequest,
+        path = "/api/v1/inventory",
         responses(
-            (status = 200, description = "Checks in a table", body = [TableResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully created ingredient", body = [IngredientResponse]),
+            (status = 409, description = "Ingredient already exists", body = ApiError),
+            (status = 422, description = "Invalid stock", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn create_table(
+async fn create_ingredient(
     State(state): State<ServerState>,
-    Json(req): Json<TableCreateRequest>,
-) -> ServerResult<Json<TableResponse>> {
-    use chrono::prelude::*;
-    let table = &NewTable {
-        table_number: &req.table_number,
-        checked_in_time: &Local::now().to_rfc3339(),
-        total: &-1_i32,
+    Json(req): Json<IngredientCreateRequest>,
+) -> ServerResult<Json<IngredientResponse>> {
+    let ingredient = NewIngredient {
+        name: &req.name,
+        unit: &req.unit,
+        stock: &req.stock,
+        low_stock: &req.low_stock,
     };
-    match state.table_repository.create(table) {
-        Ok(res) => Ok(Json(TableResponse { data: res })),
+    match state.inventory_repository.create(&ingredient) {
+        Ok(res) => Ok(Json(IngredientResponse { data: res })),
         Err(err) => Err(err),
     }
 }
 
-/// Checks out a table.
-#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+/// Restock ingredient.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
 #[utoipa::path(
         post,
-        path = "/api/v1/tables/:id/check_out",
+        request_body = RestockRequest,
+        path = "/api/v1/inventory/:id/restock",
         responses(
-            (status = 200, description = "Checks in a table", body = [CheckoutResponse]),
-            (status = 500, description = "Internal server error", body = [crate::adapters::ServerError])
+            (status = 200, description = "Successfully restocked ingredient", body = [IngredientResponse]),
+            (status = 404, description = "Ingredient not found", body = ApiError),
+            (status = 422, description = "Invalid quantity", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
     )]
-async fn checkout_table(
+async fn restock_ingredient(
     State(state): State<ServerState>,
     Path(id): Path<i32>,
-) -> ServerResult<Json<CheckoutResponse>> {
-    let total = state
-        .order_repository
-        .total(&id)
-        .expect("Unable to calculate total!");
-    match state.table_repository.checkout(&id, &total) {
-        Ok(_r) => Ok(Json(CheckoutResponse { data: total })),
+    Json(req): Json<RestockRequest>,
+) -> ServerResult<Json<IngredientResponse>> {
+    match state.inventory_repository.restock(&id, &req.quantity) {
+        Ok(res) => Ok(Json(IngredientResponse { data: res })),
         Err(err) => Err(err),
     }
 }
 
-fn table_routes() -> Router<ServerState> {
+fn inventory_routes() -> Router<ServerState> {
     Router::new()
-        .route("/", get(get_tables))
-        .route("/:id", get(get_table))
-        .route("/:id/orders", get(get_table_orders))
-        .route(
-            "/:id/orders/:id",
-            delete(delete_table_order).get(get_table_order),
-        )
-        .route("/:id/items/:id", get(get_table_items))
-        .route("/check_in", post(create_table))
-        .route("/:id/check_out", post(checkout_table))
+        .route("/", post(create_ingredient).get(get_inventory))
+        .route("/:id/restock", post(restock_ingredient))
+        .route_layer(middleware::from_fn_with_state(Access::STOCK, authorize))
 }
-#[derive(OpenApi)]
-#[openapi(
-    info(
-        version = "v0.1.0",
-        title = "Simple Restaurant API",
-    ),
-    paths(
 
-        // Table endpoints
-        get_table,
-        get_tables,
-        get_table_orders,
-        get_table_order,
-        get_table_items,
-        create_table,
-        checkout_table,
-        delete_table_order,
+/// Get the tables of the floor.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/dining_tables",
+        responses(
+            (status = 200, description = "Successfully found tables", body = [DiningTablesResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_dining_tables(
+    State(state): State<ServerState>,
+) -> ServerResult<Json<DiningTablesResponse>> {
+    match state.dining_table_repository.all() {
+        Ok(res) => Ok(Json(DiningTablesResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
 
-        // Item endpoints
-        get_item,
-        get_items,
-        create_item,
+/// Register a table.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = DiningTableCreateRequest,
+        path = "/api/v1/dining_tables",
+        responses(
+            (status = 200, description = "Successfully registered table", body = [DiningTableResponse]),
+            (status = 409, description = "Table number already taken", body = ApiError),
+            (status = 422, description = "Invalid seats", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_dining_table(
+    State(state): State<ServerState>,
+    Json(req): Json<DiningTableCreateRequest>,
+) -> ServerResult<Json<DiningTableResponse>> {
+    let table = NewDiningTable {
+        table_number: &req.table_number,
+        seats: &req.seats,
+        section: req.section.as_ref(),
+    };
+    match state.dining_table_repository.create(&table) {
+        Ok(res) => Ok(Json(DiningTableResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
 
-        // Order endpoints
-        create_order,
-        get_order_by_id,
-        get_orders,
-        delete_order,
-    ),
-    components(
-        schemas(
-            TableGetRequest,
-            ItemCreateRequest,
-            OrderCreateRequest,
-            TableResponse,
-            ItemResponse,
-            OrderResponse,
-            ItemsResponse,
-            TablesResponse,
-            CheckoutResponse,
-            crate::adapters::ServerError,
+/// Update a table, e.g. take it out of use.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, number = {number:?}, req = {req:?}")]
+#[utoipa::path(
+        patch,
+        request_body = DiningTableUpdateRequest,
+        path = "/api/v1/dining_tables/:number",
+        responses(
+            (status = 200, description = "Successfully updated table", body = [DiningTableResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 422, description = "Nothing to update or invalid seats", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
         )
-    ),
-    tags(
-        (name = "Table Operations", description = "API operations related to tables"),
-        (name = "Item Operations", description = "API operations related to menu items"),
-        (name = "Order Operations", description = "API operations related to orders"),
-    )
-)]
-pub(crate) struct Doc {}
+    )]
+async fn update_dining_table(
+    State(state): State<ServerState>,
+    Path(number): Path<i32>,
+    Json(req): Json<DiningTableUpdateRequest>,
+) -> ServerResult<Json<DiningTableResponse>> {
+    let changes = DiningTableChanges {
+        seats: req.seats.as_ref(),
+        section: req.section.as_ref(),
+        active: req.active,
+    };
+    match state.dining_table_repository.update(&number, &changes) {
+        Ok(res) => Ok(Json(DiningTableResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
 
-/// Creates server application routes.
-pub(crate) fn routes(state: ServerState) -> Router {
-    let router = Router::new()
-        .nest("/api/v1/orders", order_routes())
-        .nest("/api/v1/items", item_routes())
-        .nest("/api/v1/tables", table_routes())
-        .merge(SwaggerUi::new("/docs").url("/docs/openapi.json", Doc::openapi()));
-    router.fallback(api_fallback).with_state(state)
+fn dining_table_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_dining_table).get(get_dining_tables))
+        .route("/:number", patch(update_dining_table))
+        .route_layer(middleware::from_fn_with_state(Access::SETUP, authorize))
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::infrastructure::db::get_connection_pool;
+/// Get reservations.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/reservations",
+        responses(
+            (status = 200, description = "Successfully found reservations", body = [ReservationsResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_reservations(
+    State(state): State<ServerState>,
+) -> ServerResult<Json<ReservationsResponse>> {
+    match state.reservation_repository.all() {
+        Ok(res) => Ok(Json(ReservationsResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
 
-    use super::*;
-    use axum_test::TestServer;
-    fn get_test_routes() -> Router {
-        routes(ServerState::new(get_connection_pool()).expect("unable to create server state."))
+/// Get reservation by id.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/reservations/:id",
+        responses(
+            (status = 200, description = "Successfully found reservation", body = [ReservationResponse]),
+            (status = 404, description = "Reservation not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_reservation(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<ReservationResponse>> {
+    match state.reservation_repository.get(&id) {
+        Ok(res) => Ok(Json(ReservationResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create reservation.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = ReservationCreateRequest,
+        path = "/api/v1/reservations",
+        responses(
+            (status = 200, description = "Successfully created reservation", body = [ReservationResponse]),
+            (status = 409, description = "Table booked at the time or not in use", body = ApiError),
+            (status = 422, description = "Invalid time window, unknown table or party too large for it", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_reservation(
+    State(state): State<ServerState>,
+    Json(req): Json<ReservationCreateRequest>,
+) -> ServerResult<Json<ReservationResponse>> {
+    let reservation = NewReservation {
+        name: &req.name,
+        phone: req.phone.as_ref(),
+        party_size: &req.party_size,
+        starts_at: &req.starts_at,
+        ends_at: &req.ends_at,
+        table_number: req.table_number.as_ref(),
+    };
+    match state.reservation_repository.create(&reservation) {
+        Ok(res) => Ok(Json(ReservationResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Update reservation, e.g. move it or assign it a table.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        patch,
+        request_body = ReservationUpdateRequest,
+        path = "/api/v1/reservations/:id",
+        responses(
+            (status = 200, description = "Successfully updated reservation", body = [ReservationResponse]),
+            (status = 404, description = "Reservation not found", body = ApiError),
+            (status = 409, description = "Reservation not booked, table booked at the time or not in use", body = ApiError),
+            (status = 422, description = "Nothing to update, invalid time window, unknown table or party too large for it", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn update_reservation(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<ReservationUpdateRequest>,
+) -> ServerResult<Json<ReservationResponse>> {
+    let changes = ReservationChanges {
+        name: req.name.as_ref(),
+        phone: req.phone.as_ref(),
+        party_size: req.party_size.as_ref(),
+        starts_at: req.starts_at.as_ref(),
+        ends_at: req.ends_at.as_ref(),
+        table_number: req.table_number.as_ref(),
+    };
+    match state.reservation_repository.update(&id, &changes) {
+        Ok(res) => Ok(Json(ReservationResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Cancel reservation.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        delete,
+        path = "/api/v1/reservations/:id",
+        responses(
+            (status = 200, description = "Successfully cancelled reservation", body = [ReservationResponse]),
+            (status = 404, description = "Reservation not found", body = ApiError),
+            (status = 409, description = "Reservation not booked", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn cancel_reservation(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<ReservationResponse>> {
+    match state.reservation_repository.cancel(&id) {
+        Ok(res) => Ok(Json(ReservationResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find the tables free for a party at the given time.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, query = {query:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/reservations/availability",
+        params(AvailabilityQuery),
+        responses(
+            (status = 200, description = "Successfully found free tables", body = [DiningTablesResponse]),
+            (status = 422, description = "Invalid time window", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_availability(
+    State(state): State<ServerState>,
+    Query(query): Query<AvailabilityQuery>,
+) -> ServerResult<Json<DiningTablesResponse>> {
+    match state.reservation_repository.available(
+        &query.starts_at,
+        &query.ends_at,
+        &query.party_size,
+    ) {
+        Ok(res) => Ok(Json(DiningTablesResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn reservation_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_reservation).get(get_reservations))
+        .route("/availability", get(get_availability))
+        .route(
+            "/:id",
+            get(get_reservation)
+                .patch(update_reservation)
+                .delete(cancel_reservation),
+        )
+        .route_layer(middleware::from_fn_with_state(Access::FLOOR, authorize))
+}
+
+/// Get the parties waiting for a table, in queue order.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/waitlist",
+        responses(
+            (status = 200, description = "Successfully found waitlist", body = [WaitlistResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_waitlist(State(state): State<ServerState>) -> ServerResult<Json<WaitlistResponse>> {
+    match state.waitlist_repository.waiting() {
+        Ok(res) => Ok(Json(WaitlistResponse {
+            data: res
+                .into_iter()
+                .map(|(entry, wait_minutes)| WaitlistLine {
+                    entry,
+                    wait_minutes,
+                })
+                .collect(),
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Add a party to the waitlist, it is quoted how long it waits.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = WaitlistCreateRequest,
+        path = "/api/v1/waitlist",
+        responses(
+            (status = 200, description = "Successfully added party", body = [WaitlistEntryResponse]),
+            (status = 422, description = "Invalid party size or no table seats the party", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_waitlist_entry(
+    State(state): State<ServerState>,
+    Json(req): Json<WaitlistCreateRequest>,
+) -> ServerResult<Json<WaitlistEntryResponse>> {
+    let entry = NewWaitlistEntry {
+        name: &req.name,
+        party_size: &req.party_size,
+        contact: req.contact.as_ref(),
+    };
+    match state.waitlist_repository.create(&entry) {
+        Ok(res) => Ok(Json(WaitlistEntryResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Seat a waiting party, checking in its table.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = WaitlistSeatRequest,
+        path = "/api/v1/waitlist/:id/seat",
+        responses(
+            (status = 200, description = "Successfully seated party", body = [SeatedPartyResponse]),
+            (status = 404, description = "Party not found", body = ApiError),
+            (status = 409, description = "Party not waiting, table occupied or not in use", body = ApiError),
+            (status = 422, description = "Unknown table or party too large for it", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn seat_waitlist_entry(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<WaitlistSeatRequest>,
+) -> ServerResult<Json<SeatedPartyResponse>> {
+    let table = NewTable {
+        table_number: &req.table_number,
+        service: ServiceType::DineIn,
+        currency: req.currency.unwrap_or(DEFAULT_CURRENCY),
+        party_size: None,
+        reservation_id: None,
+    };
+    match state.waitlist_repository.seat(&id, &table) {
+        Ok((entry, table)) => Ok(Json(SeatedPartyResponse {
+            data: SeatedParty { entry, table },
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Remove a waiting party from the waitlist.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        delete,
+        path = "/api/v1/waitlist/:id",
+        responses(
+            (status = 200, description = "Successfully removed party", body = [WaitlistEntryResponse]),
+            (status = 404, description = "Party not found", body = ApiError),
+            (status = 409, description = "Party not waiting", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn remove_waitlist_entry(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<WaitlistEntryResponse>> {
+    match state.waitlist_repository.remove(&id) {
+        Ok(res) => Ok(Json(WaitlistEntryResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn waitlist_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_waitlist_entry).get(get_waitlist))
+        .route("/:id", delete(remove_waitlist_entry))
+        .route("/:id/seat", post(seat_waitlist_entry))
+        .route_layer(middleware::from_fn_with_state(Access::FLOOR, authorize))
+}
+
+/// Get promotions.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/promotions",
+        responses(
+            (status = 200, description = "Successfully found promotions", body = [PromotionsResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_promotions(
+    State(state): State<ServerState>,
+) -> ServerResult<Json<PromotionsResponse>> {
+    match state.promotion_repository.all() {
+        Ok(res) => Ok(Json(PromotionsResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create promotion.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = PromotionCreateRequest,
+        path = "/api/v1/promotions",
+        responses(
+            (status = 200, description = "Successfully created promotion", body = [PromotionResponse]),
+            (status = 409, description = "Coupon code already in use", body = ApiError),
+            (status = 422, description = "Invalid promotion", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_promotion(
+    State(state): State<ServerState>,
+    Json(req): Json<PromotionCreateRequest>,
+) -> ServerResult<Json<PromotionResponse>> {
+    let promotion = NewPromotion {
+        name: &req.name,
+        kind: req.kind,
+        percent: req.percent.as_ref(),
+        amount: req.amount.as_ref().map(|amount| &amount.amount),
+        currency: req.amount.map(|amount| amount.currency),
+        buy_quantity: req.buy_quantity.as_ref(),
+        free_quantity: req.free_quantity.as_ref(),
+        item_id: req.item_id.as_ref(),
+        category_id: req.category_id.as_ref(),
+        starts_at: req.starts_at.as_ref(),
+        ends_at: req.ends_at.as_ref(),
+        code: req.code.as_ref(),
+    };
+    match state.promotion_repository.create(&promotion) {
+        Ok(res) => Ok(Json(PromotionResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Deactivate promotion.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        delete,
+        path = "/api/v1/promotions/:id",
+        responses(
+            (status = 200, description = "Successfully deactivated promotion", body = [PromotionResponse]),
+            (status = 404, description = "Promotion not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn deactivate_promotion(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<PromotionResponse>> {
+    match state.promotion_repository.deactivate(&id) {
+        Ok(res) => Ok(Json(PromotionResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn promotion_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_promotion).get(get_promotions))
+        .route("/:id", delete(deactivate_promotion))
+        .route_layer(middleware::from_fn_with_state(Access::SETUP, authorize))
+}
+
+/// Get tax rates.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tax_rates",
+        responses(
+            (status = 200, description = "Successfully found tax rates", body = [TaxRatesResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_tax_rates(State(state): State<ServerState>) -> ServerResult<Json<TaxRatesResponse>> {
+    match state.tax_rate_repository.all() {
+        Ok(res) => Ok(Json(TaxRatesResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create tax rate.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = TaxRateCreateRequest,
+        path = "/api/v1/tax_rates",
+        responses(
+            (status = 200, description = "Successfully created tax rate", body = [TaxRateResponse]),
+            (status = 422, description = "Invalid tax rate, it needs either an item or a category", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_tax_rate(
+    State(state): State<ServerState>,
+    Json(req): Json<TaxRateCreateRequest>,
+) -> ServerResult<Json<TaxRateResponse>> {
+    let rate = NewTaxRate {
+        name: &req.name,
+        basis_points: &req.basis_points,
+        inclusive: &req.inclusive,
+        service: req.service,
+        item_id: req.item_id.as_ref(),
+        category_id: req.category_id.as_ref(),
+    };
+    match state.tax_rate_repository.create(&rate) {
+        Ok(res) => Ok(Json(TaxRateResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Deactivate tax rate.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        delete,
+        path = "/api/v1/tax_rates/:id",
+        responses(
+            (status = 200, description = "Successfully deactivated tax rate", body = [TaxRateResponse]),
+            (status = 404, description = "Tax rate not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn deactivate_tax_rate(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<TaxRateResponse>> {
+    match state.tax_rate_repository.deactivate(&id) {
+        Ok(res) => Ok(Json(TaxRateResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn tax_rate_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_tax_rate).get(get_tax_rates))
+        .route("/:id", delete(deactivate_tax_rate))
+        .route_layer(middleware::from_fn_with_state(Access::SETUP, authorize))
+}
+
+/// Get table.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id",
+        responses(
+            (status = 200, description = "Successfully found item", body = [TableResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<TableResponse>> {
+    match state.table_repository.get(&id) {
+        Ok(res) => Ok(Json(TableResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get table orders.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, query = {query:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/orders",
+        params(OrderStatusQuery),
+        responses(
+            (status = 200, description = "Successfully found item", body = [OrderResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 422, description = "Invalid status filter", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_orders(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Query(query): Query<OrderStatusQuery>,
+) -> ServerResult<Json<OrderResponse>> {
+    match state.order_repository.find_table(&id, &query.statuses()?) {
+        Ok(res) => Ok(Json(OrderResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get table order.
+#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/orders/:id",
+        responses(
+            (status = 200, description = "Successfully found order", body = [OrderResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_order(
+    State(state): State<ServerState>,
+    Path(ids): Path<(i32, i32)>,
+) -> ServerResult<Json<OrderResponse>> {
+    match state.order_repository.find_table(&ids.0, &[]) {
+        Ok(res) => {
+            let r: Vec<Order> = res.into_iter().filter(|i| i.id == ids.1).collect();
+            Ok(Json(OrderResponse { data: r }))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get table items.
+#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/items/:id",
+        responses(
+            (status = 200, description = "Successfully found order", body = [ItemsResponse]),
+            (status = 404, description = "Table or item not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_items(
+    State(state): State<ServerState>,
+    Path(ids): Path<(i32, i32)>,
+) -> ServerResult<Json<ItemsResponse>> {
+    match state.order_repository.find_table(&ids.0, &[]) {
+        Ok(res) => {
+            let r: Vec<Order> = res.into_iter().filter(|i| i.item_id == ids.1).collect();
+            let mut items = vec![];
+            for order in r {
+                match state.item_repository.get(&order.item_id) {
+                    Ok(item) => items.push(item),
+                    Err(err) => {
+                        error!("Failed to fetch item with ID {}: {:?}", order.item_id, err);
+                        return Err(err);
+                    }
+                }
+            }
+
+            Ok(Json(ItemsResponse { data: items }))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Delete table order.
+#[logcall::logcall(input = "state = {state:?}, staff = {staff:?}, ids = {ids:?}")]
+#[utoipa::path(
+        delete,
+        path = "/api/v1/tables/:id/orders/:id",
+        responses(
+            (status = 204, description = "Successfully deleted item", body = [String]),
+            (status = 403, description = "Order started, only managers can delete it", body = ApiError),
+            (status = 404, description = "Table or order not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn delete_table_order(
+    State(state): State<ServerState>,
+    Extension(staff): Extension<Staff>,
+    Path(ids): Path<(i32, i32)>,
+) -> ServerResult<StatusCode> {
+    match state
+        .order_repository
+        .delete_table_order(&ids.0, &ids.1, staff.role.manages())
+    {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create a ticket, i.e. several order lines for a table at once.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = TicketCreateRequest,
+        path = "/api/v1/tables/:id/tickets",
+        responses(
+            (status = 200, description = "Successfully created ticket", body = [TicketResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 409, description = "Item sold out", body = ApiError),
+            (status = 422, description = "Empty ticket, invalid quantity, unknown item or invalid modifiers", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_ticket(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<TicketCreateRequest>,
+) -> ServerResult<Json<TicketResponse>> {
+    let lines: Vec<NewTicketLine> = req
+        .items
+        .iter()
+        .map(|line| NewTicketLine {
+            item_id: line.item_id,
+            quantity: line.quantity,
+            modifiers: line.modifiers.clone(),
+        })
+        .collect();
+    match state.ticket_repository.create(&id, &lines) {
+        Ok((ticket, items)) => Ok(Json(TicketResponse {
+            data: TicketDetails { ticket, items },
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get table tickets.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/tickets",
+        responses(
+            (status = 200, description = "Successfully found tickets", body = [TicketsResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_tickets(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<TicketsResponse>> {
+    match state.ticket_repository.find_table(&id) {
+        Ok(res) => Ok(Json(TicketsResponse {
+            data: res
+                .into_iter()
+                .map(|(ticket, items)| TicketDetails { ticket, items })
+                .collect(),
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get table sessions, a page at a time.
+#[logcall::logcall(input = "state = {state:?}, query = {query:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables",
+        params(TableListQuery),
+        responses(
+            (status = 200, description = "Successfully found tables", body = TablesPageResponse),
+            (status = 422, description = "Invalid filter, sort or cursor", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_tables(
+    State(state): State<ServerState>,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<TableListQuery>,
+) -> ServerResult<Json<TablesPageResponse>> {
+    let statuses = query.statuses()?;
+    let filter = TableFilter {
+        table_number: query.table_number.as_ref(),
+        statuses: &statuses,
+        from: query.from,
+        until: query.until,
+    };
+    let request = query.page()?;
+    match state.table_repository.all(&filter, &request) {
+        Ok(page) => {
+            let (info, links) = page_meta(&uri, &request, &page);
+            Ok(Json(TablesPageResponse {
+                data: page.entries,
+                page: info,
+                links,
+            }))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Checks in a table.
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = TableCreateRequest,
+        path = "/api/v1/tables/check_in",
+        params(
+            ("Idempotency-Key" = Option<String>, Header, description = "Key to retry the request with, a retry gets the original response"),
+        ),
+        responses(
+            (status = 200, description = "Checks in a table", body = [TableResponse]),
+            (status = 404, description = "Reservation not found", body = ApiError),
+            (status = 409, description = "Table already occupied or not in use, reservation not booked or request with the idempotency key still handled", body = ApiError),
+            (status = 422, description = "Unknown table, party too large for it, reservation for another table or idempotency key used for another request", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_table(
+    State(state): State<ServerState>,
+    Json(req): Json<TableCreateRequest>,
+) -> ServerResult<Json<TableResponse>> {
+    let table = &NewTable {
+        table_number: &req.table_number,
+        service: req.service,
+        currency: req.currency.unwrap_or(DEFAULT_CURRENCY),
+        party_size: req.party_size.as_ref(),
+        reservation_id: req.reservation_id.as_ref(),
+    };
+    match state.table_repository.create(table) {
+        Ok(res) => Ok(Json(TableResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Checks out a table.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        post,
+        path = "/api/v1/tables/:id/check_out",
+        params(
+            ("Idempotency-Key" = Option<String>, Header, description = "Key to retry the request with, a retry gets the original response"),
+        ),
+        responses(
+            (status = 200, description = "Checks out a table, returns the final bill", body = [CheckoutResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 409, description = "Request with the idempotency key still handled", body = ApiError),
+            (status = 422, description = "Idempotency key used for another request", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn checkout_table(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<CheckoutResponse>> {
+    match state.bill_repository.checkout(&id) {
+        Ok(res) => Ok(Json(CheckoutResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Itemized bill of a table, without checking it out.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/bill",
+        responses(
+            (status = 200, description = "Current bill of the table", body = [BillResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_bill(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<BillResponse>> {
+    match state.bill_repository.draft(&id) {
+        Ok(res) => Ok(Json(BillResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Split the bill of a table, the table takes no more orders.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = BillSplit,
+        path = "/api/v1/tables/:id/split",
+        responses(
+            (status = 200, description = "The bill and its parts", body = [BillSplitResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 409, description = "Bill already split", body = ApiError),
+            (status = 422, description = "Invalid split", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn split_table_bill(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<BillSplit>,
+) -> ServerResult<Json<BillSplitResponse>> {
+    match state.bill_repository.split(&id, &req) {
+        Ok((bill, parts)) => Ok(Json(BillSplitResponse {
+            data: BillSplitDetails { bill, parts },
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Record a payment for a table.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = PaymentCreateRequest,
+        path = "/api/v1/tables/:id/payments",
+        responses(
+            (status = 200, description = "Recorded payment", body = [PaymentResponse]),
+            (status = 404, description = "Table or part of the bill not found", body = ApiError),
+            (status = 409, description = "Part of the bill already paid", body = ApiError),
+            (status = 422, description = "Invalid amount", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_payment(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<PaymentCreateRequest>,
+) -> ServerResult<Json<PaymentResponse>> {
+    let entry = NewPaymentEntry {
+        tender: req.tender,
+        amount: req.amount,
+        tip: req.tip,
+        reference: req.reference,
+        bill_part_id: req.part_id,
+    };
+    match state.payment_repository.create(&id, &entry) {
+        Ok(res) => Ok(Json(PaymentResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// List the payments of a table.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/payments",
+        responses(
+            (status = 200, description = "Payments of the table", body = [PaymentsResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_payments(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<PaymentsResponse>> {
+    let payments = state.payment_repository.find_table(&id)?;
+    let bill = state.bill_repository.draft(&id)?;
+    let paid = Payment::paid(&payments, bill.total.currency)?;
+    let due = bill.total.checked_sub(&paid)?;
+    Ok(Json(PaymentsResponse {
+        paid,
+        tips: Payment::tips(&payments, bill.total.currency)?,
+        due: if due.is_negative() {
+            Money::zero(due.currency)
+        } else {
+            due
+        },
+        data: payments,
+    }))
+}
+
+/// Void a payment of a table.
+#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+#[utoipa::path(
+        post,
+        path = "/api/v1/tables/:id/payments/:payment/void",
+        responses(
+            (status = 200, description = "Voided payment", body = [PaymentResponse]),
+            (status = 404, description = "Table or payment not found", body = ApiError),
+            (status = 409, description = "Payment already voided or settled", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn void_payment(
+    State(state): State<ServerState>,
+    Path(ids): Path<(i32, i32)>,
+) -> ServerResult<Json<PaymentResponse>> {
+    match state.payment_repository.void(&ids.0, &ids.1) {
+        Ok(res) => Ok(Json(PaymentResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get the split bill of a table.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/split",
+        responses(
+            (status = 200, description = "The bill and its parts", body = [BillSplitResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 409, description = "Bill not split", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_split(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<BillSplitResponse>> {
+    match state.bill_repository.parts(&id) {
+        Ok((bill, parts)) => Ok(Json(BillSplitResponse {
+            data: BillSplitDetails { bill, parts },
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Pay a part of a split bill, the table is checked out once every part is paid.
+#[logcall::logcall(input = "state = {state:?}, ids = {ids:?}")]
+#[utoipa::path(
+        post,
+        path = "/api/v1/tables/:id/split/:part/pay",
+        responses(
+            (status = 200, description = "The bill and its parts", body = [BillSplitResponse]),
+            (status = 404, description = "Table or part not found", body = ApiError),
+            (status = 409, description = "Bill not split or part already paid", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn pay_bill_part(
+    State(state): State<ServerState>,
+    Path(ids): Path<(i32, i32)>,
+) -> ServerResult<Json<BillSplitResponse>> {
+    match state.bill_repository.settle(&ids.0, &ids.1) {
+        Ok((bill, parts)) => Ok(Json(BillSplitResponse {
+            data: BillSplitDetails { bill, parts },
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Redeem a coupon for a table.
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = CouponRedeemRequest,
+        path = "/api/v1/tables/:id/coupons",
+        responses(
+            (status = 200, description = "Redeemed coupon, the bill now includes its discount", body = [PromotionResponse]),
+            (status = 404, description = "Table or coupon not found", body = ApiError),
+            (status = 409, description = "Coupon already redeemed", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn redeem_coupon(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<CouponRedeemRequest>,
+) -> ServerResult<Json<PromotionResponse>> {
+    match state.promotion_repository.redeem(&id, &req.code) {
+        Ok(res) => Ok(Json(PromotionResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Move the party at a table to another free table, with everything it ordered.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = TableTransferRequest,
+        path = "/api/v1/tables/:id/transfer",
+        responses(
+            (status = 200, description = "Successfully transferred table", body = [TableResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 409, description = "Table occupied or not in use", body = ApiError),
+            (status = 422, description = "Unknown table or party too large for it", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn transfer_table(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<TableTransferRequest>,
+) -> ServerResult<Json<TableResponse>> {
+    match state.table_repository.transfer(&id, &req.table_number) {
+        Ok(res) => Ok(Json(TableResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Merge the party at another table into this one, they are billed together.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = TableMergeRequest,
+        path = "/api/v1/tables/:id/merge",
+        responses(
+            (status = 200, description = "Successfully merged tables", body = [TableResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 409, description = "Bill of either table already split", body = ApiError),
+            (status = 422, description = "Same table, tables paying in different currencies or party too large for the table", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn merge_table(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<TableMergeRequest>,
+) -> ServerResult<Json<TableResponse>> {
+    match state.table_repository.merge(&id, &req.table_number) {
+        Ok(res) => Ok(Json(TableResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Get the transfers and merges of the party at a table.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/tables/:id/history",
+        responses(
+            (status = 200, description = "Successfully found table history", body = [TableHistoryResponse]),
+            (status = 404, description = "Table not found", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_table_history(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+) -> ServerResult<Json<TableHistoryResponse>> {
+    match state.table_repository.history(&id) {
+        Ok(res) => Ok(Json(TableHistoryResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn table_routes(state: &ServerState) -> Router<ServerState> {
+    let idempotent = middleware::from_fn_with_state(state.clone(), idempotent);
+    Router::new()
+        .route("/", get(get_tables))
+        .route("/:id", get(get_table))
+        .route("/:id/orders", get(get_table_orders))
+        .route(
+            "/:id/orders/:id",
+            delete(delete_table_order).get(get_table_order),
+        )
+        .route("/:id/items/:id", get(get_table_items))
+        .route("/:id/tickets", post(create_ticket).get(get_table_tickets))
+        .route("/check_in", post(create_table).layer(idempotent.clone()))
+        .route("/:id/check_out", post(checkout_table).layer(idempotent))
+        .route("/:id/bill", get(get_table_bill))
+        .route("/:id/split", post(split_table_bill).get(get_table_split))
+        .route("/:id/split/:id/pay", post(pay_bill_part))
+        .route(
+            "/:id/payments",
+            post(create_payment).get(get_table_payments),
+        )
+        .route("/:id/payments/:id/void", post(void_payment))
+        .route("/:id/coupons", post(redeem_coupon))
+        .route("/:id/transfer", post(transfer_table))
+        .route("/:id/merge", post(merge_table))
+        .route("/:id/history", get(get_table_history))
+        .route_layer(middleware::from_fn_with_state(Access::FLOOR, authorize))
+}
+/// Stream kitchen events.
+/// A browser authenticates with the `bearer, <token>` subprotocols, `bearer` is accepted.
+#[utoipa::path(
+        get,
+        path = "/api/v1/kitchen/ws",
+        responses(
+            (status = 101, description = "Switching to websocket, every message is a kitchen event", body = KitchenEvent),
+        )
+    )]
+async fn kitchen_ws(ws: WebSocketUpgrade, State(state): State<ServerState>) -> Response {
+    let events = state.events.subscribe();
+    ws.protocols([WEBSOCKET_TOKEN_PROTOCOL])
+        .on_upgrade(move |socket| stream_kitchen_events(socket, events))
+}
+
+/// Forward kitchen events to a connected display until either side hangs up.
+async fn stream_kitchen_events(mut socket: WebSocket, mut events: Receiver<KitchenEvent>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Kitchen display lagged behind, missed {} events", missed);
+                        KitchenEvent::Resync { missed }
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let payload = match serde_json::to_string(&event) {
+                    Ok(payload) => payload,
+                    Err(err) => {
+                        error!("Unable to serialize kitchen event {:?}: {:?}", event, err);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+fn kitchen_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/ws", get(kitchen_ws))
+        .route_layer(middleware::from_fn_with_state(Access::SERVICE, authorize))
+}
+
+/// Log in with a PIN or password, the token authenticates further requests.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}", output = "")]
+#[utoipa::path(
+        post,
+        request_body = LoginRequest,
+        path = "/api/v1/auth/login",
+        responses(
+            (status = 200, description = "Successfully logged in", body = [SessionResponse]),
+            (status = 401, description = "Invalid name or PIN", body = ApiError),
+            (status = 429, description = "Account locked after too many failed logins", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn login(
+    State(state): State<ServerState>,
+    Json(req): Json<LoginRequest>,
+) -> ServerResult<Json<SessionResponse>> {
+    match state.staff_repository.login(&req.name, &req.secret) {
+        Ok(res) => Ok(Json(SessionResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn auth_routes() -> Router<ServerState> {
+    Router::new().route("/login", post(login))
+}
+
+/// Get staff accounts.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/staff",
+        responses(
+            (status = 200, description = "Successfully found staff accounts", body = [StaffListResponse]),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_staff(State(state): State<ServerState>) -> ServerResult<Json<StaffListResponse>> {
+    match state.staff_repository.all() {
+        Ok(res) => Ok(Json(StaffListResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create a staff account.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, req = {req:?}")]
+#[utoipa::path(
+        post,
+        request_body = StaffCreateRequest,
+        path = "/api/v1/staff",
+        responses(
+            (status = 200, description = "Successfully created staff account", body = [StaffResponse]),
+            (status = 409, description = "Name already taken", body = ApiError),
+            (status = 422, description = "PIN or password too short", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn create_staff(
+    State(state): State<ServerState>,
+    Json(req): Json<StaffCreateRequest>,
+) -> ServerResult<Json<StaffResponse>> {
+    let staff = NewStaff {
+        name: &req.name,
+        role: req.role,
+        secret_hash: hash_secret(&req.secret)?,
+    };
+    match state.staff_repository.create(&staff) {
+        Ok(res) => Ok(Json(StaffResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Update a staff account, e.g. deactivate it when someone leaves.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, id = {id:?}, req = {req:?}")]
+#[utoipa::path(
+        patch,
+        request_body = StaffUpdateRequest,
+        path = "/api/v1/staff/:id",
+        responses(
+            (status = 200, description = "Successfully updated staff account", body = [StaffResponse]),
+            (status = 404, description = "Staff member not found", body = ApiError),
+            (status = 422, description = "Nothing to update or PIN or password too short", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn update_staff(
+    State(state): State<ServerState>,
+    Path(id): Path<i32>,
+    Json(req): Json<StaffUpdateRequest>,
+) -> ServerResult<Json<StaffResponse>> {
+    let changes = StaffChanges {
+        role: req.role,
+        active: req.active,
+        secret_hash: req.secret.as_deref().map(hash_secret).transpose()?,
+    };
+    match state.staff_repository.update(&id, &changes) {
+        Ok(res) => Ok(Json(StaffResponse { data: res })),
+        Err(err) => Err(err),
+    }
+}
+
+fn staff_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", post(create_staff).get(get_staff))
+        .route("/:id", patch(update_staff))
+        .route_layer(middleware::from_fn_with_state(Access::STAFF, authorize))
+}
+
+/// Find writes in the audit log, a page at a time.
+#[fastrace::trace]
+#[logcall::logcall(input = "state = {state:?}, query = {query:?}")]
+#[utoipa::path(
+        get,
+        path = "/api/v1/audit",
+        params(AuditQuery),
+        responses(
+            (status = 200, description = "Successfully found audit entries", body = AuditEntriesResponse),
+            (status = 422, description = "Invalid sort or cursor", body = ApiError),
+            (status = 503, description = "Database unavailable", body = ApiError),
+            (status = 500, description = "Internal server error", body = ApiError)
+        )
+    )]
+async fn get_audit(
+    State(state): State<ServerState>,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<AuditQuery>,
+) -> ServerResult<Json<AuditEntriesResponse>> {
+    let filter = AuditFilter {
+        entity: query.entity.as_ref(),
+        entity_id: query.entity_id.as_ref(),
+        actor_id: query.actor_id.as_ref(),
+        from: query.from,
+        until: query.until,
+    };
+    let request = query.page()?;
+    match state.audit_repository.find(&filter, &request) {
+        Ok(page) => {
+            let (info, links) = page_meta(&uri, &request, &page);
+            Ok(Json(AuditEntriesResponse {
+                data: page.entries,
+                page: info,
+                links,
+            }))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn audit_routes() -> Router<ServerState> {
+    Router::new()
+        .route("/", get(get_audit))
+        .route_layer(middleware::from_fn_with_state(Access::AUDIT, authorize))
+}
+
+/// Documents the bearer token every route but the login needs.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        openapi.security = Some(vec![SecurityRequirement::new(
+            "bearer",
+            Vec::<String>::new(),
+        )]);
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        version = "v0.1.0",
+        title = "Simple Restaurant API",
+    ),
+    paths(
+
+        // Table endpoints
+        get_table,
+        get_tables,
+        get_table_orders,
+        get_table_order,
+        get_table_items,
+        create_table,
+        checkout_table,
+        get_table_bill,
+        split_table_bill,
+        get_table_split,
+        pay_bill_part,
+        create_payment,
+        get_table_payments,
+        void_payment,
+        redeem_coupon,
+        delete_table_order,
+        create_ticket,
+        get_table_tickets,
+        transfer_table,
+        merge_table,
+        get_table_history,
+
+        // Dining table endpoints
+        get_dining_tables,
+        create_dining_table,
+        update_dining_table,
+
+        // Reservation endpoints
+        get_reservations,
+        get_reservation,
+        create_reservation,
+        update_reservation,
+        cancel_reservation,
+        get_availability,
+
+        // Waitlist endpoints
+        get_waitlist,
+        create_waitlist_entry,
+        seat_waitlist_entry,
+        remove_waitlist_entry,
+
+        // Item endpoints
+        get_item,
+        get_items,
+        create_item,
+        update_item,
+        archive_item,
+        get_item_prices,
+        set_item_availability,
+        get_modifier_groups,
+        create_modifier_group,
+        get_recipe,
+        set_recipe,
+
+        // Inventory endpoints
+        get_inventory,
+        create_ingredient,
+        restock_ingredient,
+
+        // Category endpoints
+        get_categories,
+        create_category,
+
+        // Promotion endpoints
+        get_promotions,
+        create_promotion,
+        deactivate_promotion,
+
+        // Tax endpoints
+        get_tax_rates,
+        create_tax_rate,
+        deactivate_tax_rate,
+
+        // Order endpoints
+        create_order,
+        get_order_by_id,
+        get_orders,
+        update_order_status,
+        delete_order,
+
+        // Kitchen endpoints
+        kitchen_ws,
+
+        // Staff endpoints
+        login,
+        get_staff,
+        create_staff,
+        update_staff,
+
+        // Audit endpoints
+        get_audit,
+    ),
+    components(
+        schemas(
+            TableGetRequest,
+            DiningTableCreateRequest,
+            DiningTableUpdateRequest,
+            DiningTable,
+            DiningTableResponse,
+            DiningTablesResponse,
+            ReservationCreateRequest,
+            ReservationUpdateRequest,
+            Reservation,
+            ReservationStatus,
+            ReservationResponse,
+            ReservationsResponse,
+            WaitlistCreateRequest,
+            WaitlistSeatRequest,
+            WaitlistEntry,
+            WaitlistStatus,
+            WaitlistEntryResponse,
+            WaitlistLine,
+            WaitlistResponse,
+            SeatedParty,
+            SeatedPartyResponse,
+            ItemCreateRequest,
+            ItemUpdateRequest,
+            ItemAvailabilityRequest,
+            ModifierGroupCreateRequest,
+            ModifierCreateRequest,
+            ModifierGroup,
+            Modifier,
+            ModifierGroupDetails,
+            ModifierGroupResponse,
+            ModifierGroupsResponse,
+            RecipeRequest,
+            RecipeLineRequest,
+            RecipeIngredient,
+            RecipeResponse,
+            IngredientCreateRequest,
+            RestockRequest,
+            Ingredient,
+            IngredientResponse,
+            InventoryLine,
+            InventoryResponse,
+            OrderCreateRequest,
+            BatchMode,
+            OrderBatchResponse,
+            OrderBatchRejection,
+            OrderLineResult,
+            OrderLineStatus,
+            OrderStatusRequest,
+            OrderStatus,
+            TicketCreateRequest,
+            TicketLineRequest,
+            TableResponse,
+            TableTransferRequest,
+            TableMergeRequest,
+            TableMove,
+            MoveKind,
+            TableHistoryResponse,
+            ItemResponse,
+            ItemPrice,
+            ItemPricesResponse,
+            OrderResponse,
+            OrdersPageResponse,
+            ItemsResponse,
+            ItemsPageResponse,
+            TablesPageResponse,
+            PageInfo,
+            PageLinks,
+            TicketDetails,
+            TicketResponse,
+            TicketsResponse,
+            CheckoutResponse,
+            Bill,
+            BillItem,
+            BillDiscount,
+            BillTax,
+            BillCharge,
+            BillResponse,
+            BillSplit,
+            BillPart,
+            BillSplitDetails,
+            BillSplitResponse,
+            PaymentCreateRequest,
+            Payment,
+            Tender,
+            PaymentResponse,
+            PaymentsResponse,
+            CategoryCreateRequest,
+            Category,
+            CategoryResponse,
+            CategoriesResponse,
+            PromotionCreateRequest,
+            Promotion,
+            PromotionKind,
+            PromotionResponse,
+            PromotionsResponse,
+            CouponRedeemRequest,
+            ServiceType,
+            Money,
+            Currency,
+            TaxRateCreateRequest,
+            TaxRate,
+            TaxRateResponse,
+            TaxRatesResponse,
+            KitchenEvent,
+            LoginRequest,
+            Session,
+            SessionResponse,
+            StaffCreateRequest,
+            StaffUpdateRequest,
+            Staff,
+            Role,
+            StaffResponse,
+            StaffListResponse,
+            AuditEntry,
+            AuditEntriesResponse,
+            ApiError,
+            ErrorCode,
+        )
+    ),
+    tags(
+        (name = "Table Operations", description = "API operations related to tables"),
+        (name = "Floor Operations", description = "Physical tables parties are seated at"),
+        (name = "Reservation Operations", description = "Bookings of tables ahead of time"),
+        (name = "Waitlist Operations", description = "Walk-in parties waiting for a table"),
+        (name = "Item Operations", description = "API operations related to menu items"),
+        (name = "Inventory Operations", description = "Ingredient stock taken by orders"),
+        (name = "Order Operations", description = "API operations related to orders"),
+        (name = "Promotion Operations", description = "Discount rules and coupons applied to bills"),
+        (name = "Tax Operations", description = "Tax rates applied to bills"),
+        (name = "Kitchen Operations", description = "Live updates for the kitchen display"),
+        (name = "Staff Operations", description = "Staff accounts and logins"),
+        (name = "Audit Operations", description = "Log of every write made through the API"),
+    ),
+    modifiers(&BearerAuth)
+)]
+pub(crate) struct Doc {}
+
+/// Creates server application routes.
+pub(crate) fn routes(state: ServerState) -> Router {
+    let router = Router::new()
+        .nest("/api/v1/orders", order_routes(&state))
+        .nest("/api/v1/items", item_routes())
+        .nest("/api/v1/inventory", inventory_routes())
+        .nest("/api/v1/categories", category_routes())
+        .nest("/api/v1/promotions", promotion_routes())
+        .nest("/api/v1/tax_rates", tax_rate_routes())
+        .nest("/api/v1/tables", table_routes(&state))
+        .nest("/api/v1/dining_tables", dining_table_routes())
+        .nest("/api/v1/reservations", reservation_routes())
+        .nest("/api/v1/waitlist", waitlist_routes())
+        .nest("/api/v1/kitchen", kitchen_routes())
+        .nest("/api/v1/staff", staff_routes())
+        .nest("/api/v1/audit", audit_routes())
+        .route_layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .nest("/api/v1/auth", auth_routes())
+        .merge(SwaggerUi::new("/docs").url("/docs/openapi.json", Doc::openapi()));
+    router.fallback(api_fallback).with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::db::get_connection_pool;
+
+    use super::*;
+    use crate::application::config::MAX_FAILED_LOGINS;
+    use axum_test::TestServer;
+    fn get_test_state() -> ServerState {
+        ServerState::new(get_connection_pool()).expect("unable to create server state.")
+    }
+    use serde_json::json;
+    fn build_test_server() -> TestServer {
+        build_test_server_with(get_test_state())
+    }
+    fn build_test_server_with(state: ServerState) -> TestServer {
+        build_test_server_as(state, Role::Admin)
+    }
+    /// Test server logged in as a staff member with `role`, the account is created on first use.
+    fn build_test_server_as(state: ServerState, role: Role) -> TestServer {
+        let name = format!("test-{}", role);
+        let staff = NewStaff {
+            name: &name,
+            role,
+            secret_hash: hash_secret(TEST_SECRET).unwrap(),
+        };
+        let _ = state.staff_repository.create(&staff);
+        let token = state
+            .staff_repository
+            .login(&name, TEST_SECRET)
+            .expect("unable to log in.")
+            .token;
+        let r = routes(state);
+
+        let mut server = TestServer::builder()
+            .save_cookies()
+            .expect_success_by_default()
+            .mock_transport()
+            .build(r)
+            .unwrap();
+        server.add_header(AUTHORIZATION, format!("Bearer {}", token));
+        server
+    }
+    const TEST_SECRET: &str = "0000";
+
+    #[tokio::test]
+    async fn test_create_item() {
+        let server = build_test_server();
+        {
+            let response = server
+                .post("/api/v1/items")
+                .json(&json!({
+                    "description": "Some good tasting item!",
+                    "price": {"amount": 1, "currency": "EUR"},
+                }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+        {
+            let response = server.get("/api/v1/items/1").await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+        {
+            let response = server
+                .get(&format!("/api/v1/items/{}", i32::MAX))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "item_not_found"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_table() {
+        let server = build_test_server();
+        {
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 1}))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+        {
+            let response = server
+                .get("/api/v1/tables")
+                .json(&json!({
+                    "id": 1,
+                }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_order() {
+        let server = build_test_server();
+        {
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({
+                        "table_number": 2,
+                }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+        {
+            let response = server
+                .post("/api/v1/items")
+                .json(&json!({
+                    "description": "Some good tasting item!",
+                    "price": {"amount": 10, "currency": "EUR"},
+                }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+
+        {
+            let response = server
+                .post("/api/v1/orders")
+                .json(&json!([
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 10,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 1,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 5,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 6,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 100,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 50,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 70,},
+                    {"item_id": 1,
+                    "table_id": 2,
+                    "quantity": 1,},
+                ]))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kitchen_events() {
+        let state = get_test_state();
+        let mut events = state.events.subscribe();
+        let server = build_test_server_with(state);
+        {
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 3}))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+            match events.try_recv() {
+                Ok(KitchenEvent::TableCheckedIn { table }) => assert_eq!(table.table_number, 3),
+                other => panic!("Unexpected kitchen event {:?}", other),
+            }
+        }
+        {
+            let response = server.post("/api/v1/tables/3/check_out").await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+            match events.try_recv() {
+                Ok(KitchenEvent::TableCheckedOut { table_number, .. }) => {
+                    assert_eq!(table_number, 3)
+                }
+                other => panic!("Unexpected kitchen event {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kitchen_ws_token() {
+        let state = get_test_state();
+        build_test_server_as(state.clone(), Role::Kitchen);
+        let server = build_test_server_with(state.clone());
+        let token = state
+            .staff_repository
+            .login("test-kitchen", TEST_SECRET)
+            .expect("unable to log in.")
+            .token;
+        // Like a browser, the display can't set the authorization header.
+        let display = TestServer::builder()
+            .http_transport()
+            .build(routes(state))
+            .unwrap();
+        let response = display
+            .get_websocket("/api/v1/kitchen/ws")
+            .expect_failure()
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        let response = display
+            .get_websocket("/api/v1/kitchen/ws")
+            .add_header(
+                SEC_WEBSOCKET_PROTOCOL,
+                format!("{}, {}", WEBSOCKET_TOKEN_PROTOCOL, token),
+            )
+            .await;
+        assert_eq!(
+            response.header(SEC_WEBSOCKET_PROTOCOL),
+            WEBSOCKET_TOKEN_PROTOCOL
+        );
+        let mut socket = response.into_websocket().await;
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 27}))
+            .await;
+        let event = socket.receive_json::<serde_json::Value>().await;
+        assert_eq!(event["type"], "table_checked_in");
+        assert_eq!(event["table"]["table_number"], 27);
+    }
+
+    #[tokio::test]
+    async fn test_order_status() {
+        let server = build_test_server();
+        {
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 4}))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Ramen", "price": {"amount": 12, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        server
+            .post("/api/v1/orders")
+            .json(&json!([{"item_id": item_id, "table_id": 4, "quantity": 1}]))
+            .await;
+        let orders = server
+            .get("/api/v1/tables/4/orders")
+            .await
+            .json::<serde_json::Value>();
+        let order_id = orders["data"][0]["id"]
+            .as_i64()
+            .expect("Unable to read order id");
+        assert_eq!(orders["data"][0]["status"], "pending");
+        {
+            let response = server
+                .post(&format!("/api/v1/orders/{}/status", order_id))
+                .json(&json!({"status": "served"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "illegal_status_transition"
+            );
+        }
+        {
+            let response = server
+                .post(&format!("/api/v1/orders/{}/status", order_id))
+                .json(&json!({"status": "cooking"}))
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["data"][0]["status"],
+                "cooking"
+            );
+        }
+        {
+            let remaining = server
+                .get("/api/v1/tables/4/orders?status=pending,cooking,ready")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(remaining["data"].as_array().map(Vec::len), Some(1));
+            let served = server
+                .get("/api/v1/tables/4/orders?status=served")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(served["data"].as_array().map(Vec::len), Some(0));
+        }
+        {
+            // Once the table is checked out, its orders are sold.
+            server
+                .post("/api/v1/tables/4/payments")
+                .json(&json!({"tender": "cash", "amount": {"amount": 100, "currency": "EUR"}}))
+                .await;
+            server.post("/api/v1/tables/4/check_out").await;
+            let response = server
+                .post(&format!("/api/v1/orders/{}/status", order_id))
+                .json(&json!({"status": "cancelled"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.json::<serde_json::Value>()["code"], "table_closed");
+            let response = server
+                .delete(&format!("/api/v1/orders/{}", order_id))
+                .expect_failure()
+                .await;
+            assert_eq!(response.json::<serde_json::Value>()["code"], "table_closed");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_ticket() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 5}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Gyoza", "price": {"amount": 5, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        {
+            let response = server
+                .post("/api/v1/tables/5/tickets")
+                .json(&json!({"items": [
+                    {"item_id": item_id, "quantity": 2},
+                    {"item_id": item_id, "quantity": 3},
+                ]}))
+                .await;
+            let ticket = response.json::<serde_json::Value>();
+            assert_eq!(ticket["data"]["items"].as_array().map(Vec::len), Some(2));
+        }
+        {
+            // An unknown item rolls back the whole ticket.
+            let response = server
+                .post("/api/v1/tables/5/tickets")
+                .json(&json!({"items": [
+                    {"item_id": item_id, "quantity": 1},
+                    {"item_id": i32::MAX, "quantity": 1},
+                ]}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        {
+            let tickets = server
+                .get("/api/v1/tables/5/tickets")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(tickets["data"].as_array().map(Vec::len), Some(1));
+            let orders = server
+                .get("/api/v1/tables/5/orders")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(orders["data"].as_array().map(Vec::len), Some(2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_order_batch() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 6}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Edamame", "price": {"amount": 4, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let batch = json!([
+            {"item_id": item_id, "table_id": 6, "quantity": 1},
+            {"item_id": i32::MAX, "table_id": 6, "quantity": 1},
+            {"item_id": item_id, "table_id": 6, "quantity": 0},
+        ]);
+        {
+            let response = server
+                .post("/api/v1/orders")
+                .json(&batch)
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            let body = response.json::<serde_json::Value>();
+            assert_eq!(body["committed"], false);
+            assert_eq!(body["data"][0]["status"], "rolled_back");
+            assert_eq!(body["data"][1]["status"], "failed");
+            assert_eq!(body["data"][2]["status"], "failed");
+            let orders = server
+                .get("/api/v1/tables/6/orders")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(orders["data"].as_array().map(Vec::len), Some(0));
+        }
+        {
+            let response = server
+                .post("/api/v1/orders?mode=partial")
+                .json(&batch)
+                .await;
+            assert_eq!(response.status_code(), StatusCode::MULTI_STATUS);
+            let body = response.json::<serde_json::Value>();
+            assert_eq!(body["committed"], true);
+            assert_eq!(body["data"][0]["status"], "created");
+            assert!(body["data"][0]["order_id"].is_i64());
+            assert_eq!(body["data"][1]["status"], "failed");
+            let orders = server
+                .get("/api/v1/tables/6/orders")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(orders["data"].as_array().map(Vec::len), Some(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 7}))
+            .await;
+        {
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 7}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "table_occupied"
+            );
+        }
+        {
+            let response = server
+                .get("/api/v1/tables/7/orders?status=eaten")
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "invalid_request"
+            );
+        }
+        {
+            let response = server
+                .post(&format!("/api/v1/tables/{}/check_out", i32::MAX))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "table_not_found"
+            );
+        }
+        {
+            let response = server
+                .delete(&format!("/api/v1/orders/{}", i32::MAX))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "order_not_found"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_table_session() {
+        use crate::domain::entities::{table::Table, tables, tickets};
+        use diesel::prelude::*;
+        let state = get_test_state();
+        let server = build_test_server_with(state.clone());
+        {
+            let table = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 8}))
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(table["data"]["status"], "open");
+            assert!(table["data"]["total"].is_null());
+            assert!(table["data"]["closed_at"].is_null());
+        }
+        {
+            let response = server.post("/api/v1/tables/8/check_out").await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["data"]["total"]["amount"],
+                0
+            );
+            // Checking out twice finds no open session.
+            let response = server
+                .post("/api/v1/tables/8/check_out")
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+            let response = server
+                .post("/api/v1/tables/8/tickets")
+                .json(&json!({"items": [{"item_id": 1, "quantity": 1}]}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        }
+        {
+            // Even when bypassing the application, a closed session takes no more orders.
+            let conn = &mut state.table_repository.connection_pool.get().unwrap();
+            let closed = tables::table
+                .filter(tables::table_number.eq(8))
+                .select(Table::as_select())
+                .first(conn)
+                .unwrap();
+            assert_eq!(closed.total, Some(Money::zero(Currency::Eur)));
+            assert!(closed.closed_at.is_some());
+            let err = diesel::insert_into(tickets::table)
+                .values((
+                    tickets::published_at.eq("now"),
+                    tickets::table_id.eq(closed.id),
+                ))
+                .execute(conn)
+                .unwrap_err();
+            let err = ApiError::from_db(err, ErrorCode::NotFound, "closed");
+            assert_eq!(err.code, ErrorCode::TableClosed);
+        }
+        {
+            // The table number is free again for the next party.
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 8}))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_table_bill() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 9}))
+            .await;
+        let mut item_ids = vec![];
+        for (description, price) in [("Tonkotsu", 14), ("Edamame", 4)] {
+            let item = server
+                .post("/api/v1/items")
+                .json(&json!({"description": description, "price": {"amount": price, "currency": "EUR"}}))
+                .await
+                .json::<serde_json::Value>();
+            item_ids.push(item["data"]["id"].as_i64().expect("Unable to read item id"));
+        }
+        let ticket = server
+            .post("/api/v1/tables/9/tickets")
+            .json(&json!({"items": [
+                {"item_id": item_ids[0], "quantity": 2},
+                {"item_id": item_ids[1], "quantity": 1},
+                {"item_id": item_ids[1], "quantity": 3},
+            ]}))
+            .await
+            .json::<serde_json::Value>();
+        // Cancelled orders are not billed.
+        let cancelled = &ticket["data"]["items"][2]["id"];
+        server
+            .post(&format!("/api/v1/orders/{}/status", cancelled))
+            .json(&json!({"status": "cancelled"}))
+            .await;
+        let bill = server
+            .get("/api/v1/tables/9/bill")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(
+            bill["data"]["items"],
+            json!([
+                {
+                    "description": "Tonkotsu",
+                    "unit_price": {"amount": 14, "currency": "EUR"},
+                    "quantity": 2,
+                    "line_total": {"amount": 28, "currency": "EUR"},
+                },
+                {
+                    "description": "Edamame",
+                    "unit_price": {"amount": 4, "currency": "EUR"},
+                    "quantity": 1,
+                    "line_total": {"amount": 4, "currency": "EUR"},
+                },
+            ])
+        );
+        assert_eq!(bill["data"]["subtotal"]["amount"], 32);
+        assert_eq!(bill["data"]["total"]["amount"], 32);
+        assert!(bill["data"]["issued_at"].is_null());
+
+        server
+            .post("/api/v1/tables/9/payments")
+            .json(&json!({"tender": "card", "amount": {"amount": 32, "currency": "EUR"}}))
+            .await;
+        let checkout = server
+            .post("/api/v1/tables/9/check_out")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(checkout["data"]["items"], bill["data"]["items"]);
+        assert_eq!(checkout["data"]["total"]["amount"], 32);
+        assert!(checkout["data"]["issued_at"].is_string());
+        server.get("/api/v1/tables/9/bill").expect_failure().await;
+    }
+
+    #[tokio::test]
+    async fn test_split_bill() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 10}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Sake", "price": {"amount": 10, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        server
+            .post("/api/v1/tables/10/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+            .await;
+        {
+            let response = server
+                .post("/api/v1/tables/10/split")
+                .json(&json!({"mode": "amounts", "amounts": [
+                    {"amount": 5, "currency": "EUR"},
+                    {"amount": 4, "currency": "EUR"},
+                ]}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let split = server
+            .post("/api/v1/tables/10/split")
+            .json(&json!({"mode": "even", "parts": 3}))
+            .await
+            .json::<serde_json::Value>();
+        let parts = split["data"]["parts"]
+            .as_array()
+            .expect("Unable to read parts");
+        let amounts: Vec<i64> = parts
+            .iter()
+            .filter_map(|p| p["amount"]["amount"].as_i64())
+            .collect();
+        assert_eq!(amounts, vec![4, 3, 3]);
+        {
+            // A table settling its bill takes no more orders, and can't be split twice.
+            let response = server
+                .post("/api/v1/tables/10/tickets")
+                .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            let response = server
+                .post("/api/v1/tables/10/split")
+                .json(&json!({"mode": "even", "parts": 2}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            let response = server
+                .post("/api/v1/tables/10/check_out")
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+        }
+        for (i, part) in parts.iter().enumerate() {
+            let url = format!("/api/v1/tables/10/split/{}/pay", part["id"]);
+            // A part is only settled once it is paid for.
+            server.post(&url).expect_failure().await;
+            server
+                .post("/api/v1/tables/10/payments")
+                .json(&json!({"tender": "cash", "amount": part["amount"], "part_id": part["id"]}))
+                .await;
+            server.post(&url).await;
+            // The table is only checked out once the last part is paid.
+            if i < parts.len() - 1 {
+                server.get("/api/v1/tables/10").await;
+            } else {
+                server.get("/api/v1/tables/10").expect_failure().await;
+                server.post(&url).expect_failure().await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_payments() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 11}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Omakase", "price": {"amount": 30, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        server
+            .post("/api/v1/tables/11/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+            .await;
+        {
+            let response = server
+                .post("/api/v1/tables/11/payments")
+                .json(&json!({"tender": "cash", "amount": {"amount": 0, "currency": "EUR"}}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            // The table pays in euros, dollars are neither taken nor ordered.
+            let response = server
+                .post("/api/v1/tables/11/payments")
+                .json(&json!({"tender": "cash", "amount": {"amount": 30, "currency": "USD"}}))
+                .expect_failure()
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "currency_mismatch"
+            );
+            let burger = server
+                .post("/api/v1/items")
+                .json(&json!({"description": "Burger", "price": {"amount": 12, "currency": "USD"}}))
+                .await
+                .json::<serde_json::Value>();
+            let response = server
+                .post("/api/v1/tables/11/tickets")
+                .json(&json!({"items": [{"item_id": burger["data"]["id"], "quantity": 1}]}))
+                .expect_failure()
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "currency_mismatch"
+            );
+        }
+        server
+            .post("/api/v1/tables/11/payments")
+            .json(&json!({"tender": "cash", "amount": {"amount": 10, "currency": "EUR"}, "tip": {"amount": 2, "currency": "EUR"}}))
+            .await;
+        let card = server
+            .post("/api/v1/tables/11/payments")
+            .json(&json!({"tender": "card", "amount": {"amount": 20, "currency": "EUR"}, "reference": "AUTH-1"}))
+            .await
+            .json::<serde_json::Value>();
+        let void = format!("/api/v1/tables/11/payments/{}/void", card["data"]["id"]);
+        server.post(&void).await;
+        {
+            let response = server.post(&void).expect_failure().await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            let response = server
+                .post("/api/v1/tables/11/check_out")
+                .expect_failure()
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "insufficient_payment"
+            );
+        }
+        server
+            .post("/api/v1/tables/11/payments")
+            .json(&json!({"tender": "voucher", "amount": {"amount": 20, "currency": "EUR"}, "reference": "GIFT"}))
+            .await;
+        let payments = server
+            .get("/api/v1/tables/11/payments")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(payments["paid"]["amount"], 30);
+        assert_eq!(payments["tips"]["amount"], 2);
+        assert_eq!(payments["due"]["amount"], 0);
+        assert_eq!(payments["data"].as_array().map(Vec::len), Some(3));
+        server.post("/api/v1/tables/11/check_out").await;
+    }
+
+    #[tokio::test]
+    async fn test_promotions() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 12}))
+            .await;
+        let category = server
+            .post("/api/v1/categories")
+            .json(&json!({"name": "Sake"}))
+            .await
+            .json::<serde_json::Value>();
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({
+                "description": "Junmai",
+                "price": {"amount": 20, "currency": "EUR"},
+                "category_id": category["data"]["id"],
+            }))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let sake_off = server
+            .post("/api/v1/promotions")
+            .json(&json!({
+                "name": "Sake 10% off",
+                "kind": "percentage",
+                "percent": 10,
+                "category_id": category["data"]["id"],
+            }))
+            .await
+            .json::<serde_json::Value>();
+        let welcome = server
+            .post("/api/v1/promotions")
+            .json(&json!({
+                "name": "Welcome coupon",
+                "kind": "fixed_amount",
+                "amount": {"amount": 5, "currency": "EUR"},
+                "code": "WELCOME",
+            }))
+            .await
+            .json::<serde_json::Value>();
+        {
+            let response = server
+                .post("/api/v1/promotions")
+                .json(&json!({"name": "Nothing off", "kind": "percentage"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        server
+            .post("/api/v1/tables/12/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 2}]}))
+            .await;
+        {
+            let bill = server
+                .get("/api/v1/tables/12/bill")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(bill["data"]["subtotal"]["amount"], 40);
+            assert_eq!(bill["data"]["discounts"][0]["amount"]["amount"], -4);
+            assert_eq!(
+                bill["data"]["discounts"][0]["promotion_id"],
+                sake_off["data"]["id"]
+            );
+            assert_eq!(bill["data"]["total"]["amount"], 36);
+        }
+        server
+            .post("/api/v1/tables/12/coupons")
+            .json(&json!({"code": "WELCOME"}))
+            .await;
+        {
+            let response = server
+                .post("/api/v1/tables/12/coupons")
+                .json(&json!({"code": "WELCOME"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            let response = server
+                .post("/api/v1/tables/12/coupons")
+                .json(&json!({"code": "UNKNOWN"}))
+                .expect_failure()
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "promotion_not_found"
+            );
+        }
+        {
+            let bill = server
+                .get("/api/v1/tables/12/bill")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(bill["data"]["total"]["amount"], 31);
+        }
+        server
+            .delete(&format!("/api/v1/promotions/{}", sake_off["data"]["id"]))
+            .await;
+        server
+            .post("/api/v1/tables/12/payments")
+            .json(&json!({"tender": "card", "amount": {"amount": 35, "currency": "EUR"}}))
+            .await;
+        let bill = server
+            .post("/api/v1/tables/12/check_out")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(bill["data"]["total"]["amount"], 35);
+        assert_eq!(bill["data"]["discounts"].as_array().map(Vec::len), Some(1));
+        assert_eq!(
+            bill["data"]["discounts"][0]["promotion_id"],
+            welcome["data"]["id"]
+        );
     }
-    use serde_json::json;
-    fn build_test_server() -> TestServer {
-        let r = get_test_routes();
 
-        TestServer::builder()
-            .save_cookies()
-            .expect_success_by_default()
-            .mock_transport()
-            .build(r)
-            .unwrap()
+    #[tokio::test]
+    async fn test_tax_rates() {
+        let server = build_test_server();
+        let category = server
+            .post("/api/v1/categories")
+            .json(&json!({"name": "Bento"}))
+            .await
+            .json::<serde_json::Value>();
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({
+                "description": "Chicken bento",
+                "price": {"amount": 1000, "currency": "EUR"},
+                "category_id": category["data"]["id"],
+            }))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let sales_tax = server
+            .post("/api/v1/tax_rates")
+            .json(&json!({
+                "name": "Sales tax 12.5%",
+                "basis_points": 1250,
+                "service": "dine_in",
+                "category_id": category["data"]["id"],
+            }))
+            .await
+            .json::<serde_json::Value>();
+        let vat = server
+            .post("/api/v1/tax_rates")
+            .json(&json!({
+                "name": "VAT 6%",
+                "basis_points": 600,
+                "inclusive": true,
+                "service": "takeout",
+                "category_id": category["data"]["id"],
+            }))
+            .await
+            .json::<serde_json::Value>();
+        {
+            let response = server
+                .post("/api/v1/tax_rates")
+                .json(&json!({"name": "Everything", "basis_points": 500}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 13}))
+            .await;
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 14, "service": "takeout"}))
+            .await;
+        for table in [13, 14] {
+            server
+                .post(&format!("/api/v1/tables/{}/tickets", table))
+                .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+                .await;
+        }
+        {
+            let bill = server
+                .get("/api/v1/tables/13/bill")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(bill["data"]["taxes"].as_array().map(Vec::len), Some(1));
+            assert_eq!(
+                bill["data"]["taxes"][0]["tax_rate_id"],
+                sales_tax["data"]["id"]
+            );
+            assert_eq!(bill["data"]["taxes"][0]["amount"]["amount"], 125);
+            assert_eq!(bill["data"]["total"]["amount"], 1125);
+        }
+        server
+            .post("/api/v1/tables/14/payments")
+            .json(&json!({"tender": "cash", "amount": {"amount": 1000, "currency": "EUR"}}))
+            .await;
+        let bill = server
+            .post("/api/v1/tables/14/check_out")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(bill["data"]["taxes"][0]["tax_rate_id"], vat["data"]["id"]);
+        assert_eq!(bill["data"]["taxes"][0]["inclusive"], true);
+        assert_eq!(bill["data"]["taxes"][0]["amount"]["amount"], 57);
+        assert_eq!(bill["data"]["total"]["amount"], 1000);
+        server
+            .delete(&format!("/api/v1/tax_rates/{}", sales_tax["data"]["id"]))
+            .await;
+        let bill = server
+            .get("/api/v1/tables/13/bill")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(bill["data"]["total"]["amount"], 1000);
     }
 
     #[tokio::test]
-    async fn test_create_item() {
+    async fn test_modifiers() {
         let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 15}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Shoyu", "price": {"amount": 1200, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let groups = format!("/api/v1/items/{}/modifier_groups", item_id);
         {
             let response = server
-                .post("/api/v1/items")
+                .post(&groups)
                 .json(&json!({
-                    "description": "Some good tasting item!",
-                    "price": 1,
+                    "name": "Sauce",
+                    "modifiers": [{"name": "Hot", "price_delta": {"amount": 50, "currency": "USD"}}],
                 }))
+                .expect_failure()
                 .await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "currency_mismatch"
+            );
+            let response = server
+                .post(&groups)
+                .json(&json!({
+                    "name": "Sauce",
+                    "required": true,
+                    "min_selections": 0,
+                    "modifiers": [{"name": "Hot"}],
+                }))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
         }
+        let egg = server
+            .post(&groups)
+            .json(&json!({
+                "name": "Egg",
+                "required": true,
+                "modifiers": [
+                    {"name": "extra egg", "price_delta": {"amount": 100, "currency": "EUR"}},
+                    {"name": "no egg"},
+                ],
+            }))
+            .await
+            .json::<serde_json::Value>();
+        let toppings = server
+            .post(&groups)
+            .json(&json!({
+                "name": "Toppings",
+                "max_selections": 2,
+                "modifiers": [
+                    {"name": "no scallions"},
+                    {"name": "extra chashu", "price_delta": {"amount": 250, "currency": "EUR"}},
+                ],
+            }))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(egg["data"]["required"], true);
+        assert_eq!(egg["data"]["max_selections"], 1);
+        assert_eq!(toppings["data"]["required"], false);
+        let found = server.get(&groups).await.json::<serde_json::Value>();
+        assert_eq!(found["data"].as_array().map(Vec::len), Some(2));
+        let modifier =
+            |group: &serde_json::Value, i: usize| group["data"]["modifiers"][i]["id"].clone();
         {
-            let response = server.get("/api/v1/items/1").await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            // The egg is required, and only one of them.
+            for modifiers in [json!([]), json!([modifier(&egg, 0), modifier(&egg, 1)])] {
+                let response = server
+                    .post("/api/v1/tables/15/tickets")
+                    .json(&json!({"items": [
+                        {"item_id": item_id, "quantity": 1, "modifiers": modifiers},
+                    ]}))
+                    .expect_failure()
+                    .await;
+                assert_eq!(
+                    response.json::<serde_json::Value>()["code"],
+                    "invalid_modifiers"
+                );
+            }
         }
+        server
+            .post("/api/v1/tables/15/tickets")
+            .json(&json!({"items": [{
+                "item_id": item_id,
+                "quantity": 2,
+                "modifiers": [modifier(&egg, 0), modifier(&toppings, 0)],
+            }]}))
+            .await;
+        server
+            .post("/api/v1/orders")
+            .json(&json!([{
+                "item_id": item_id,
+                "table_id": 15,
+                "quantity": 1,
+                "modifiers": [modifier(&egg, 1), modifier(&toppings, 1)],
+            }]))
+            .await;
+        let bill = server
+            .get("/api/v1/tables/15/bill")
+            .await
+            .json::<serde_json::Value>();
+        let items = &bill["data"]["items"];
+        assert_eq!(items[0]["description"], "Shoyu (extra egg, no scallions)");
+        assert_eq!(items[0]["line_total"]["amount"], 2600);
+        assert_eq!(items[1]["description"], "Shoyu (no egg, extra chashu)");
+        assert_eq!(items[1]["unit_price"]["amount"], 1450);
+        assert_eq!(bill["data"]["total"]["amount"], 4050);
+    }
+
+    #[tokio::test]
+    async fn test_item_availability() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 16}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Toro", "price": {"amount": 900, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let availability = format!("/api/v1/items/{}/availability", item_id);
+        server
+            .post(&availability)
+            .json(&json!({"available": true, "remaining": 2}))
+            .await;
+        let ticket = server
+            .post("/api/v1/tables/16/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 2}]}))
+            .await
+            .json::<serde_json::Value>();
+        let menu = format!("/api/v1/items?id={}", item_id);
+        let sold_out = |items: serde_json::Value| {
+            items["data"]
+                .as_array()
+                .and_then(|items| items.iter().find(|item| item["id"] == item_id).cloned())
+                .expect("Unable to find item")
+        };
+        let item = sold_out(server.get(&menu).await.json());
+        assert_eq!(item["available"], false);
+        assert_eq!(item["remaining"], 0);
         {
-            let response = server.get("/api/v1/items/10").expect_failure().await;
-            assert_eq!(response.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+            let response = server
+                .post("/api/v1/tables/16/tickets")
+                .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            let response = server
+                .post("/api/v1/orders")
+                .json(&json!([{"item_id": item_id, "table_id": 16, "quantity": 1}]))
+                .expect_failure()
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(response["data"][0]["error"]["code"], "item_unavailable");
         }
+        // Cancelling gives the portions back.
+        server
+            .post(&format!(
+                "/api/v1/orders/{}/status",
+                ticket["data"]["items"][0]["id"]
+            ))
+            .json(&json!({"status": "cancelled"}))
+            .await;
+        let item = sold_out(server.get(&menu).await.json());
+        assert_eq!(item["available"], true);
+        assert_eq!(item["remaining"], 2);
+        let order = server
+            .post("/api/v1/orders")
+            .json(&json!([{"item_id": item_id, "table_id": 16, "quantity": 1}]))
+            .await
+            .json::<serde_json::Value>();
+        // An item sold out by hand stays sold out when portions are given back.
+        server
+            .post(&availability)
+            .json(&json!({"available": false, "remaining": 0}))
+            .await;
+        server
+            .post(&format!(
+                "/api/v1/orders/{}/status",
+                order["data"][0]["order_id"]
+            ))
+            .json(&json!({"status": "cancelled"}))
+            .await;
+        let item = sold_out(server.get(&menu).await.json());
+        assert_eq!(item["available"], false);
+        assert_eq!(item["remaining"], 1);
+        let item = server
+            .post(&availability)
+            .json(&json!({"available": false}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(item["data"]["available"], false);
+        assert!(item["data"]["remaining"].is_null());
+        let response = server
+            .post("/api/v1/tables/16/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+            .expect_failure()
+            .await;
+        assert_eq!(
+            response.json::<serde_json::Value>()["code"],
+            "item_unavailable"
+        );
     }
 
     #[tokio::test]
-    async fn test_create_table() {
+    async fn test_inventory() {
         let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 17}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Gyoza", "price": {"amount": 700, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let ingredient_id = server
+            .post("/api/v1/inventory")
+            .json(&json!({"name": "Gyoza wrapper", "unit": "pcs", "stock": 10, "low_stock": 4}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read ingredient id");
+        let recipe = format!("/api/v1/items/{}/recipe", item_id);
         {
             let response = server
-                .post("/api/v1/tables/check_in")
-                .json(&json!({"table_number": 1}))
+                .post(&recipe)
+                .json(&json!({"ingredients": [{"ingredient_id": -1, "quantity": 4}]}))
+                .expect_failure()
                 .await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
         }
+        server
+            .post(&recipe)
+            .json(&json!({"ingredients": [{"ingredient_id": ingredient_id, "quantity": 4}]}))
+            .await;
+        let response = server.get(&recipe).await.json::<serde_json::Value>();
+        assert_eq!(response["data"][0]["quantity"], 4);
+        let ticket = server
+            .post("/api/v1/tables/17/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 2}]}))
+            .await
+            .json::<serde_json::Value>();
+        let stock = |inventory: serde_json::Value| {
+            inventory["data"]
+                .as_array()
+                .and_then(|lines| {
+                    lines
+                        .iter()
+                        .find(|line| line["id"] == ingredient_id)
+                        .cloned()
+                })
+                .expect("Unable to find ingredient")
+        };
+        let line = stock(server.get("/api/v1/inventory").await.json());
+        assert_eq!(line["stock"], 2);
+        assert_eq!(line["low"], true);
+        // Not enough left for another portion.
+        let item = server
+            .get(&format!("/api/v1/items/{}", item_id))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(item["data"]["available"], false);
         {
             let response = server
-                .get("/api/v1/tables")
-                .json(&json!({
-                    "id": 1,
-                }))
+                .post("/api/v1/orders")
+                .json(&json!([{"item_id": item_id, "table_id": 17, "quantity": 1}]))
+                .expect_failure()
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(response["data"][0]["error"]["code"], "item_unavailable");
+        }
+        // Cancelling puts the ingredients back in stock.
+        server
+            .post(&format!(
+                "/api/v1/orders/{}/status",
+                ticket["data"]["items"][0]["id"]
+            ))
+            .json(&json!({"status": "cancelled"}))
+            .await;
+        let line = stock(server.get("/api/v1/inventory").await.json());
+        assert_eq!(line["stock"], 10);
+        assert_eq!(line["low"], false);
+        let order = server
+            .post("/api/v1/orders")
+            .json(&json!([{"item_id": item_id, "table_id": 17, "quantity": 2}]))
+            .await
+            .json::<serde_json::Value>();
+        server
+            .delete(&format!("/api/v1/orders/{}", order["data"][0]["order_id"]))
+            .await;
+        let restocked = server
+            .post(&format!("/api/v1/inventory/{}/restock", ingredient_id))
+            .json(&json!({"quantity": 5}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(restocked["data"]["stock"], 15);
+        let item = server
+            .get(&format!("/api/v1/items/{}", item_id))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(item["data"]["available"], true);
+        {
+            let response = server
+                .post("/api/v1/inventory/-1/restock")
+                .json(&json!({"quantity": 5}))
+                .expect_failure()
                 .await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
         }
     }
 
     #[tokio::test]
-    async fn test_create_order() {
+    async fn test_update_and_archive_item() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 18}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Edamam", "price": {"amount": 400, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let path = format!("/api/v1/items/{}", item_id);
+        let item = server
+            .patch(&path)
+            .json(&json!({"description": "Edamame", "price": {"amount": 450, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(item["data"]["description"], "Edamame");
+        assert_eq!(item["data"]["price"]["amount"], 450);
+        {
+            let response = server
+                .patch(&path)
+                .json(&json!({"price": {"amount": 450, "currency": "USD"}}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            let response = server.patch(&path).json(&json!({})).expect_failure().await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        server
+            .post("/api/v1/orders")
+            .json(&json!([{"item_id": item_id, "table_id": 18, "quantity": 2}]))
+            .await;
+        let item = server.delete(&path).await.json::<serde_json::Value>();
+        assert!(!item["data"]["archived_at"].is_null());
+        // Archived items are off the menu, but still resolve.
+        let items = server
+            .get(&format!("/api/v1/items?id={}", item_id))
+            .await
+            .json::<serde_json::Value>();
+        assert!(items["data"]
+            .as_array()
+            .expect("Unable to read items")
+            .iter()
+            .all(|item| item["id"] != item_id));
+        server.get(&path).await;
+        let bill = server
+            .get("/api/v1/tables/18/bill")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(bill["data"]["items"][0]["description"], "Edamame");
+        {
+            let response = server
+                .post("/api/v1/tables/18/tickets")
+                .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "item_archived"
+            );
+            let response = server.delete(&path).expect_failure().await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+            let response = server
+                .patch(&path)
+                .json(&json!({"description": "Edamame"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_snapshot() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 19}))
+            .await;
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Katsu curry", "price": {"amount": 1000, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        let order = server
+            .post("/api/v1/orders")
+            .json(&json!([{"item_id": item_id, "table_id": 19, "quantity": 1}]))
+            .await
+            .json::<serde_json::Value>();
+        let order = server
+            .get(&format!("/api/v1/orders/{}", order["data"][0]["order_id"]))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(order["data"][0]["unit_price"]["amount"], 1000);
+        assert_eq!(order["data"][0]["description"], "Katsu curry");
+        server
+            .patch(&format!("/api/v1/items/{}", item_id))
+            .json(&json!({"description": "Chicken katsu curry", "price": {"amount": 1200, "currency": "EUR"}}))
+            .await;
+        server
+            .post("/api/v1/tables/19/tickets")
+            .json(&json!({"items": [{"item_id": item_id, "quantity": 1}]}))
+            .await;
+        // The first order keeps the price it was ordered at.
+        let bill = server
+            .get("/api/v1/tables/19/bill")
+            .await
+            .json::<serde_json::Value>();
+        let items = bill["data"]["items"]
+            .as_array()
+            .expect("Unable to read bill items");
+        assert_eq!(items[0]["description"], "Katsu curry");
+        assert_eq!(items[0]["unit_price"]["amount"], 1000);
+        assert_eq!(items[1]["description"], "Chicken katsu curry");
+        assert_eq!(items[1]["unit_price"]["amount"], 1200);
+        assert_eq!(bill["data"]["subtotal"]["amount"], 2200);
+        let prices = server
+            .get(&format!("/api/v1/items/{}/prices", item_id))
+            .await
+            .json::<serde_json::Value>();
+        let prices = prices["data"].as_array().expect("Unable to read prices");
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0]["price"]["amount"], 1000);
+        assert_eq!(prices[1]["price"]["amount"], 1200);
+        // Only a changed price is kept.
+        server
+            .patch(&format!("/api/v1/items/{}", item_id))
+            .json(&json!({"description": "Katsu curry"}))
+            .await;
+        let prices = server
+            .get(&format!("/api/v1/items/{}/prices", item_id))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(prices["data"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_dining_tables() {
         let server = build_test_server();
+        let table = server
+            .post("/api/v1/dining_tables")
+            .json(&json!({"table_number": 120, "seats": 2, "section": "terrace"}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(table["data"]["section"], "terrace");
+        assert_eq!(table["data"]["active"], true);
         {
+            let response = server
+                .post("/api/v1/dining_tables")
+                .json(&json!({"table_number": 120, "seats": 4}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
             let response = server
                 .post("/api/v1/tables/check_in")
-                .json(&json!({
-                        "table_number": 2,
-                }))
+                .json(&json!({"table_number": 121}))
+                .expect_failure()
                 .await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "unknown_table"
+            );
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 120, "party_size": 3}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "party_too_large"
+            );
         }
+        server
+            .patch("/api/v1/dining_tables/120")
+            .json(&json!({"active": false}))
+            .await;
         {
             let response = server
-                .post("/api/v1/items")
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 120, "party_size": 2}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "table_inactive"
+            );
+            let response = server
+                .patch("/api/v1/dining_tables/121")
+                .json(&json!({"active": true}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        }
+        server
+            .patch("/api/v1/dining_tables/120")
+            .json(&json!({"active": true, "seats": 3}))
+            .await;
+        let table = server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 120, "party_size": 3}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(table["data"]["party_size"], 3);
+        let tables = server
+            .get("/api/v1/dining_tables")
+            .await
+            .json::<serde_json::Value>();
+        assert!(tables["data"]
+            .as_array()
+            .expect("Unable to read tables")
+            .iter()
+            .any(|table| table["table_number"] == 120 && table["seats"] == 3));
+    }
+
+    #[tokio::test]
+    async fn test_reservations() {
+        use chrono::{Duration, SecondsFormat, Utc};
+        let server = build_test_server();
+        for (number, section) in [(130, "main"), (131, "patio")] {
+            server
+                .post("/api/v1/dining_tables")
+                .json(&json!({"table_number": number, "seats": 6, "section": section}))
+                .await;
+        }
+        let at = |minutes: i64| {
+            (Utc::now() + Duration::minutes(minutes)).to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+        let booked = server
+            .post("/api/v1/reservations")
+            .json(&json!({
+                "name": "Tanaka",
+                "party_size": 5,
+                "starts_at": at(-10),
+                "ends_at": at(80),
+                "table_number": 130
+            }))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(booked["data"]["status"], "booked");
+        {
+            let response = server
+                .post("/api/v1/reservations")
                 .json(&json!({
-                    "description": "Some good tasting item!",
-                    "price": 10,
+                    "name": "Sato",
+                    "party_size": 2,
+                    "starts_at": at(60),
+                    "ends_at": at(120),
+                    "table_number": 130
                 }))
+                .expect_failure()
                 .await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(response.json::<serde_json::Value>()["code"], "table_booked");
+            let response = server
+                .post("/api/v1/reservations")
+                .json(&json!({
+                    "name": "Sato",
+                    "party_size": 7,
+                    "starts_at": at(60),
+                    "ends_at": at(120),
+                    "table_number": 131
+                }))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            let response = server
+                .post("/api/v1/reservations")
+                .json(&json!({
+                    "name": "Sato",
+                    "party_size": 2,
+                    "starts_at": at(120),
+                    "ends_at": at(60)
+                }))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let free = server
+            .get(&format!(
+                "/api/v1/reservations/availability?starts_at={}&ends_at={}&party_size=5",
+                at(0),
+                at(60)
+            ))
+            .await
+            .json::<serde_json::Value>();
+        let free: Vec<&serde_json::Value> = free["data"]
+            .as_array()
+            .expect("Unable to read tables")
+            .iter()
+            .map(|table| &table["table_number"])
+            .collect();
+        assert!(free.contains(&&json!(131)));
+        assert!(!free.contains(&&json!(130)));
+        let later = server
+            .post("/api/v1/reservations")
+            .json(&json!({
+                "name": "Sato",
+                "party_size": 2,
+                "starts_at": at(180),
+                "ends_at": at(240),
+                "table_number": 131
+            }))
+            .await
+            .json::<serde_json::Value>();
+        let later = format!("/api/v1/reservations/{}", later["data"]["id"]);
+        {
+            let response = server
+                .patch(&later)
+                .json(&json!({"starts_at": at(60), "table_number": 130}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+        }
+        let cancelled = server.delete(&later).await.json::<serde_json::Value>();
+        assert_eq!(cancelled["data"]["status"], "cancelled");
+        {
+            let response = server
+                .patch(&later)
+                .json(&json!({"party_size": 3}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "reservation_closed"
+            );
+        }
+        // The party is seated for its booking.
+        let table = server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 130}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(table["data"]["reservation_id"], booked["data"]["id"]);
+        assert_eq!(table["data"]["party_size"], 5);
+        let seated = server
+            .get(&format!("/api/v1/reservations/{}", booked["data"]["id"]))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(seated["data"]["status"], "seated");
+        // A seated table is no longer offered, though its booking is not pending any more.
+        let free = server
+            .get(&format!(
+                "/api/v1/reservations/availability?starts_at={}&ends_at={}&party_size=5",
+                at(0),
+                at(60)
+            ))
+            .await
+            .json::<serde_json::Value>();
+        let free: Vec<&serde_json::Value> = free["data"]
+            .as_array()
+            .expect("Unable to read tables")
+            .iter()
+            .map(|table| &table["table_number"])
+            .collect();
+        assert!(!free.contains(&&json!(130)));
+    }
+
+    #[tokio::test]
+    async fn test_waitlist() {
+        let server = build_test_server();
+        server
+            .post("/api/v1/dining_tables")
+            .json(&json!({"table_number": 140, "seats": 2}))
+            .await;
+        let party = server
+            .post("/api/v1/waitlist")
+            .json(&json!({"name": "Suzuki", "party_size": 2, "contact": "Pager 7"}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(party["data"]["status"], "waiting");
+        assert!(party["data"]["quoted_minutes"].as_i64() >= Some(0));
+        {
+            let response = server
+                .post("/api/v1/waitlist")
+                .json(&json!({"name": "Club", "party_size": 50}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "party_too_large"
+            );
+        }
+        let queue = server
+            .get("/api/v1/waitlist")
+            .await
+            .json::<serde_json::Value>();
+        let line = queue["data"]
+            .as_array()
+            .and_then(|lines| lines.iter().find(|line| line["id"] == party["data"]["id"]))
+            .cloned()
+            .expect("Unable to find party in the waitlist");
+        assert!(line["wait_minutes"].is_i64());
+        let seat = format!("/api/v1/waitlist/{}/seat", party["data"]["id"]);
+        let seated = server
+            .post(&seat)
+            .json(&json!({"table_number": 140}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(seated["data"]["status"], "seated");
+        assert_eq!(seated["data"]["table"]["table_number"], 140);
+        assert_eq!(seated["data"]["table"]["party_size"], 2);
+        server.get("/api/v1/tables/140").await;
+        {
+            let response = server
+                .post(&seat)
+                .json(&json!({"table_number": 140}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "party_not_waiting"
+            );
+        }
+        let party = server
+            .post("/api/v1/waitlist")
+            .json(&json!({"name": "Kato", "party_size": 2}))
+            .await
+            .json::<serde_json::Value>();
+        {
+            let response = server
+                .post(&format!("/api/v1/waitlist/{}/seat", party["data"]["id"]))
+                .json(&json!({"table_number": 140}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "table_occupied"
+            );
         }
+        let remove = format!("/api/v1/waitlist/{}", party["data"]["id"]);
+        let removed = server.delete(&remove).await.json::<serde_json::Value>();
+        assert_eq!(removed["data"]["status"], "removed");
+        let response = server.delete(&remove).expect_failure().await;
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+    }
 
+    #[tokio::test]
+    async fn test_transfer_and_merge_tables() {
+        let server = build_test_server();
+        for (number, party_size) in [(21, 2), (22, 3)] {
+            server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": number, "party_size": party_size}))
+                .await;
+        }
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Karaage", "price": {"amount": 650, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        server
+            .post("/api/v1/orders")
+            .json(&json!([
+                {"item_id": item_id, "table_id": 21, "quantity": 1},
+                {"item_id": item_id, "table_id": 22, "quantity": 2}
+            ]))
+            .await;
+        {
+            let response = server
+                .post("/api/v1/tables/21/transfer")
+                .json(&json!({"table_number": 22}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "table_occupied"
+            );
+            let response = server
+                .post("/api/v1/tables/21/transfer")
+                .json(&json!({"table_number": 999}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let table = server
+            .post("/api/v1/tables/21/transfer")
+            .json(&json!({"table_number": 23}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(table["data"]["table_number"], 23);
+        server.get("/api/v1/tables/21").expect_failure().await;
+        let orders = server
+            .get("/api/v1/tables/23/orders")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(orders["data"].as_array().map(Vec::len), Some(1));
+        {
+            let response = server
+                .post("/api/v1/tables/23/merge")
+                .json(&json!({"table_number": 22}))
+                .expect_failure()
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "party_too_large"
+            );
+        }
+        server
+            .patch("/api/v1/dining_tables/23")
+            .json(&json!({"seats": 6}))
+            .await;
+        let table = server
+            .post("/api/v1/tables/23/merge")
+            .json(&json!({"table_number": 22}))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(table["data"]["party_size"], 5);
+        server.get("/api/v1/tables/22").expect_failure().await;
+        let bill = server
+            .get("/api/v1/tables/23/bill")
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(bill["data"]["subtotal"]["amount"], 1950);
         {
             let response = server
+                .post("/api/v1/tables/23/merge")
+                .json(&json!({"table_number": 23}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let history = server
+            .get("/api/v1/tables/23/history")
+            .await
+            .json::<serde_json::Value>();
+        let history = history["data"].as_array().expect("Unable to read history");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["kind"], "transfer");
+        assert_eq!(history[0]["from_number"], 21);
+        assert_eq!(history[1]["kind"], "merge");
+        assert_eq!(history[1]["from_number"], 22);
+        assert_eq!(history[1]["to_number"], 23);
+    }
+
+    #[tokio::test]
+    async fn test_staff_auth() {
+        let state = get_test_state();
+        let admin = build_test_server_with(state.clone());
+        let waiter = build_test_server_as(state.clone(), Role::Waiter);
+        let kitchen = build_test_server_as(state.clone(), Role::Kitchen);
+        let manager = build_test_server_as(state.clone(), Role::Manager);
+        let mut anonymous = build_test_server_with(state);
+        anonymous.clear_headers();
+        {
+            let response = anonymous.get("/api/v1/items").expect_failure().await;
+            assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "unauthenticated"
+            );
+            let response = anonymous
+                .post("/api/v1/auth/login")
+                .json(&json!({"name": "test-waiter", "secret": "1111"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "invalid_credentials"
+            );
+        }
+        {
+            waiter.get("/api/v1/items").await;
+            for response in [
+                waiter
+                    .post("/api/v1/items")
+                    .json(&json!({"description": "Tea", "price": {"amount": 2, "currency": "EUR"}}))
+                    .expect_failure()
+                    .await,
+                waiter.get("/api/v1/staff").expect_failure().await,
+                kitchen
+                    .post("/api/v1/tables/check_in")
+                    .json(&json!({"table_number": 24}))
+                    .expect_failure()
+                    .await,
+            ] {
+                assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+                assert_eq!(response.json::<serde_json::Value>()["code"], "forbidden");
+            }
+        }
+        {
+            let item_id = manager
+                .post("/api/v1/items")
+                .json(&json!({"description": "Soup", "price": {"amount": 5, "currency": "EUR"}}))
+                .await
+                .json::<serde_json::Value>()["data"]["id"]
+                .as_i64()
+                .expect("Unable to read item id");
+            waiter
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 24}))
+                .await;
+            waiter
                 .post("/api/v1/orders")
                 .json(&json!([
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 10,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 1,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 5,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 6,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 100,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 50,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 70,},
-                    {"item_id": 1,
-                    "table_id": 2,
-                    "quantity": 1,},
+                    {"item_id": item_id, "table_id": 24, "quantity": 1},
+                    {"item_id": item_id, "table_id": 24, "quantity": 2},
                 ]))
                 .await;
-            assert_eq!(response.status_code(), StatusCode::OK);
+            let orders = waiter
+                .get("/api/v1/tables/24/orders")
+                .await
+                .json::<serde_json::Value>();
+            let order = |i: usize| {
+                orders["data"][i]["id"]
+                    .as_i64()
+                    .expect("Unable to read order id")
+            };
+            // The waiter takes back an order the kitchen didn't start.
+            waiter
+                .delete(&format!("/api/v1/tables/24/orders/{}", order(0)))
+                .await;
+            kitchen
+                .post(&format!("/api/v1/orders/{}/status", order(1)))
+                .json(&json!({"status": "cooking"}))
+                .await;
+            let response = waiter
+                .delete(&format!("/api/v1/orders/{}", order(1)))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+            // Nor can the waiter cancel it instead.
+            let response = waiter
+                .post(&format!("/api/v1/orders/{}/status", order(1)))
+                .json(&json!({"status": "cancelled"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+            assert_eq!(response.json::<serde_json::Value>()["code"], "forbidden");
+            manager
+                .delete(&format!("/api/v1/orders/{}", order(1)))
+                .await;
+            waiter.post("/api/v1/tables/24/check_out").await;
+        }
+        {
+            let name = format!("test-leaver-{}", rand::thread_rng().gen::<u32>());
+            let id = admin
+                .post("/api/v1/staff")
+                .json(&json!({"name": name, "role": "waiter", "secret": "2468"}))
+                .await
+                .json::<serde_json::Value>()["data"]["id"]
+                .as_i64()
+                .expect("Unable to read staff id");
+            let session = anonymous
+                .post("/api/v1/auth/login")
+                .json(&json!({"name": name, "secret": "2468"}))
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(session["data"]["staff"]["role"], "waiter");
+            let token = session["data"]["token"]
+                .as_str()
+                .expect("Unable to read token");
+            anonymous
+                .get("/api/v1/tables")
+                .authorization_bearer(token)
+                .await;
+            admin
+                .patch(&format!("/api/v1/staff/{}", id))
+                .json(&json!({"active": false}))
+                .await;
+            let response = anonymous
+                .get("/api/v1/tables")
+                .authorization_bearer(token)
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        }
+        {
+            // Guessing a PIN locks the account, even the right PIN is turned away then.
+            let name = format!("test-guessed-{}", rand::thread_rng().gen::<u32>());
+            admin
+                .post("/api/v1/staff")
+                .json(&json!({"name": name, "role": "waiter", "secret": "1357"}))
+                .await;
+            for guess in 0..MAX_FAILED_LOGINS {
+                let response = anonymous
+                    .post("/api/v1/auth/login")
+                    .json(&json!({"name": name, "secret": format!("{:04}", guess)}))
+                    .expect_failure()
+                    .await;
+                assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+            }
+            let response = anonymous
+                .post("/api/v1/auth/login")
+                .json(&json!({"name": name, "secret": "1357"}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "account_locked"
+            );
+            let response = anonymous
+                .post("/api/v1/auth/login")
+                .json(&json!({"name": "test-nobody", "secret": "1357"}))
+                .expect_failure()
+                .await;
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "invalid_credentials"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let state = get_test_state();
+        let mut manager = build_test_server_as(state.clone(), Role::Manager);
+        let waiter = build_test_server_as(state.clone(), Role::Waiter);
+        manager.add_header(DEVICE_HEADER, "till-1");
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let item_id = manager
+            .post("/api/v1/items")
+            .add_header("traceparent", traceparent)
+            .json(&json!({"description": "Stew", "price": {"amount": 7, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        manager
+            .patch(&format!("/api/v1/items/{}", item_id))
+            .json(&json!({"price": {"amount": 8, "currency": "EUR"}}))
+            .await;
+        let entries = manager
+            .get(&format!("/api/v1/audit?entity=item&entity_id={}", item_id))
+            .await
+            .json::<serde_json::Value>();
+        let entries = entries["data"]
+            .as_array()
+            .expect("Unable to read audit entries");
+        assert_eq!(entries.len(), 2);
+        let (updated, created) = (&entries[0], &entries[1]);
+        assert_eq!(updated["action"], "update");
+        assert_eq!(updated["before"]["price"]["amount"], 7);
+        assert_eq!(updated["after"]["price"]["amount"], 8);
+        assert_eq!(created["action"], "create");
+        assert!(created["before"].is_null());
+        assert_eq!(created["device"], "till-1");
+        assert_eq!(
+            created["trace_id"],
+            u128::from_str_radix("0af7651916cd43dd8448eb211c80319c", 16)
+                .unwrap()
+                .to_string()
+        );
+        assert_ne!(updated["trace_id"], created["trace_id"]);
+        {
+            // The log is read a page at a time, the latest first.
+            let first = manager
+                .get(&format!(
+                    "/api/v1/audit?entity=item&entity_id={}&limit=1",
+                    item_id
+                ))
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(first["data"][0]["id"], updated["id"]);
+            assert_eq!(first["page"]["sort"], "-created_at");
+            let next = first["links"]["next"]
+                .as_str()
+                .expect("Unable to find next page");
+            let second = manager.get(next).await.json::<serde_json::Value>();
+            assert_eq!(second["data"][0]["id"], created["id"]);
+            assert!(second["links"]["next"].is_null());
+        }
+        {
+            let actor_id = created["actor_id"].as_i64().expect("Unable to read actor");
+            let by_actor = manager
+                .get(&format!(
+                    "/api/v1/audit?entity=item&actor_id={}&from={}",
+                    actor_id,
+                    created["created_at"].as_str().unwrap().replace('+', "%2B")
+                ))
+                .await
+                .json::<serde_json::Value>();
+            assert!(by_actor["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|entry| entry["actor_id"] == actor_id));
+            let response = waiter.get("/api/v1/audit").expect_failure().await;
+            assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        }
+        {
+            use crate::domain::entities::audit_entries;
+            use diesel::prelude::*;
+            let conn = &mut state.audit_repository.connection_pool.get().unwrap();
+            let deleted = diesel::delete(
+                audit_entries::table.filter(audit_entries::entity_id.eq(item_id as i32)),
+            )
+            .execute(conn);
+            assert!(deleted.is_err(), "The audit log must be append-only");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_keys() {
+        use chrono::Duration;
+        let mut state = get_test_state();
+        let server = build_test_server_with(state.clone());
+        let key = |name: &str| format!("{}-{}", name, rand::thread_rng().gen::<u32>());
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Udon", "price": {"amount": 900, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        {
+            let check_in = key("check-in");
+            let first = server
+                .post("/api/v1/tables/check_in")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &check_in)
+                .json(&json!({"table_number": 25}))
+                .await;
+            let retry = server
+                .post("/api/v1/tables/check_in")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &check_in)
+                .json(&json!({"table_number": 25}))
+                .await;
+            assert_eq!(retry.header(IDEMPOTENT_REPLAYED_HEADER), "true");
+            assert_eq!(
+                retry.json::<serde_json::Value>(),
+                first.json::<serde_json::Value>()
+            );
+            let response = server
+                .post("/api/v1/tables/check_in")
+                .json(&json!({"table_number": 25}))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+        }
+        {
+            let order = key("order");
+            for _ in 0..2 {
+                server
+                    .post("/api/v1/orders")
+                    .add_header(IDEMPOTENCY_KEY_HEADER, &order)
+                    .json(&json!([{"item_id": item_id, "table_id": 25, "quantity": 1}]))
+                    .await;
+            }
+            let response = server
+                .post("/api/v1/orders")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &order)
+                .json(&json!([{"item_id": item_id, "table_id": 25, "quantity": 2}]))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(
+                response.json::<serde_json::Value>()["code"],
+                "idempotency_key_reused"
+            );
+            // Keys of other staff members and other routes are unrelated.
+            let waiter = build_test_server_as(state.clone(), Role::Waiter);
+            let response = waiter
+                .post("/api/v1/orders")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &order)
+                .json(&json!([{"item_id": item_id, "table_id": 25, "quantity": 2}]))
+                .await;
+            assert!(response.maybe_header(IDEMPOTENT_REPLAYED_HEADER).is_none());
+            let response = server
+                .post("/api/v1/tables/25/check_out")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &order)
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CONFLICT);
+            // An expired key is handled as a new one.
+            state.idempotency_repository.ttl = Duration::zero();
+            let expiring = build_test_server_with(state);
+            let order = key("order");
+            for quantity in [1, 2] {
+                expiring
+                    .post("/api/v1/orders")
+                    .add_header(IDEMPOTENCY_KEY_HEADER, &order)
+                    .json(&json!([{"item_id": item_id, "table_id": 25, "quantity": quantity}]))
+                    .await;
+            }
+            let orders = server
+                .get("/api/v1/tables/25/orders")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(orders["data"].as_array().map(Vec::len), Some(4));
+        }
+        {
+            let check_out = key("check-out");
+            server
+                .post("/api/v1/tables/25/payments")
+                .json(&json!({"tender": "cash", "amount": {"amount": 10000, "currency": "EUR"}}))
+                .await;
+            let first = server
+                .post("/api/v1/tables/25/check_out")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &check_out)
+                .await;
+            let retry = server
+                .post("/api/v1/tables/25/check_out")
+                .add_header(IDEMPOTENCY_KEY_HEADER, &check_out)
+                .await;
+            assert_eq!(
+                retry.json::<serde_json::Value>()["data"]["total"],
+                first.json::<serde_json::Value>()["data"]["total"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        let server = build_test_server();
+        let item_id = server
+            .post("/api/v1/items")
+            .json(&json!({"description": "Gyoza", "price": {"amount": 650, "currency": "EUR"}}))
+            .await
+            .json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .expect("Unable to read item id");
+        server
+            .post("/api/v1/tables/check_in")
+            .json(&json!({"table_number": 26}))
+            .await;
+        server
+            .post("/api/v1/orders")
+            .json(&json!([
+                {"item_id": item_id, "table_id": 26, "quantity": 1},
+                {"item_id": item_id, "table_id": 26, "quantity": 2},
+                {"item_id": item_id, "table_id": 26, "quantity": 3},
+            ]))
+            .await;
+        {
+            let first = server
+                .get(&format!("/api/v1/orders?item_id={}&limit=2", item_id))
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(first["data"].as_array().map(Vec::len), Some(2));
+            assert_eq!(first["page"]["limit"], 2);
+            assert_eq!(first["page"]["sort"], "id");
+            let next = first["links"]["next"]
+                .as_str()
+                .expect("Unable to find next page");
+            assert!(next.contains(&format!("item_id={}", item_id)));
+            let second = server.get(next).await.json::<serde_json::Value>();
+            assert_eq!(second["data"].as_array().map(Vec::len), Some(1));
+            assert_eq!(second["data"][0]["quantity"], 3);
+            assert!(second["page"]["next_cursor"].is_null());
+            assert!(second["links"]["next"].is_null());
+            // A cursor is only good for the sort it was made for.
+            let cursor = first["page"]["next_cursor"]
+                .as_str()
+                .expect("Unable to read cursor");
+            let response = server
+                .get(&format!("/api/v1/orders?sort=-id&cursor={}", cursor))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        {
+            let orders = server
+                .get("/api/v1/orders?table_number=26&status=pending&sort=-quantity")
+                .await
+                .json::<serde_json::Value>();
+            let quantities: Vec<_> = orders["data"]
+                .as_array()
+                .expect("Unable to read orders")
+                .iter()
+                .map(|order| order["quantity"].clone())
+                .collect();
+            assert_eq!(quantities, vec![json!(3), json!(2), json!(1)]);
+            let orders = server
+                .get("/api/v1/orders?table_number=26&status=served")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(orders["data"].as_array().map(Vec::len), Some(0));
+        }
+        {
+            let tables = server
+                .get("/api/v1/tables?table_number=26&status=open,billing")
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(tables["data"].as_array().map(Vec::len), Some(1));
+            assert_eq!(tables["page"]["sort"], "-opened_at");
+            let items = server
+                .get(&format!("/api/v1/items?id={}&available=true", item_id))
+                .await
+                .json::<serde_json::Value>();
+            assert_eq!(items["data"][0]["id"], item_id);
+        }
+        for query in ["limit=0", "sort=colour", "cursor=nope", "status=eaten"] {
+            let response = server
+                .get(&format!("/api/v1/orders?{}", query))
+                .expect_failure()
+                .await;
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
         }
     }
 }
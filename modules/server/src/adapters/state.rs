@@ -2,32 +2,124 @@
 
 use anyhow::Result;
 
-use super::factories::{ItemFactory, OrderFactory, TableFactory};
+use super::factories::{
+    AuditFactory, BillFactory, CategoryFactory, DiningTableFactory, IdempotencyFactory,
+    InventoryFactory, ItemFactory, ModifierFactory, OrderFactory, PaymentFactory, PromotionFactory,
+    ReservationFactory, StaffFactory, TableFactory, TaxRateFactory, TicketFactory, TokenKeys,
+    WaitlistFactory,
+};
+use crate::application::config::{IDEMPOTENCY_TTL_MINUTES, KITCHEN_EVENT_CAPACITY};
+use crate::domain::events::KitchenEvent;
+use chrono::Duration;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
+use log::warn;
+use rand::Rng;
+use std::env::var;
+use tokio::sync::broadcast::{self, Sender};
 
 /// Server state.
 #[derive(Clone, Debug)]
 pub(crate) struct ServerState {
     pub(crate) order_repository: OrderFactory,
     pub(crate) item_repository: ItemFactory,
+    pub(crate) modifier_repository: ModifierFactory,
+    pub(crate) inventory_repository: InventoryFactory,
+    pub(crate) dining_table_repository: DiningTableFactory,
+    pub(crate) reservation_repository: ReservationFactory,
+    pub(crate) waitlist_repository: WaitlistFactory,
     pub(crate) table_repository: TableFactory,
+    pub(crate) ticket_repository: TicketFactory,
+    pub(crate) bill_repository: BillFactory,
+    pub(crate) payment_repository: PaymentFactory,
+    pub(crate) category_repository: CategoryFactory,
+    pub(crate) promotion_repository: PromotionFactory,
+    pub(crate) tax_rate_repository: TaxRateFactory,
+    pub(crate) staff_repository: StaffFactory,
+    pub(crate) audit_repository: AuditFactory,
+    pub(crate) idempotency_repository: IdempotencyFactory,
+    pub(crate) events: Sender<KitchenEvent>,
 }
 
 impl ServerState {
     pub(crate) fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Result<Self> {
+        let (events, _) = broadcast::channel(KITCHEN_EVENT_CAPACITY);
+        let keys = match var("AUTH_SECRET").ok().filter(|secret| !secret.is_empty()) {
+            Some(secret) => TokenKeys::new(secret.as_bytes()),
+            None => {
+                warn!("AUTH_SECRET is not set, logins won't survive a restart!");
+                TokenKeys::new(&rand::thread_rng().gen::<[u8; 32]>())
+            }
+        };
+        let idempotency_ttl = match var("IDEMPOTENCY_TTL_MINUTES") {
+            Ok(minutes) => minutes.parse().unwrap_or_else(|_| {
+                warn!("IDEMPOTENCY_TTL_MINUTES is not a number of minutes, it is ignored!");
+                IDEMPOTENCY_TTL_MINUTES
+            }),
+            Err(_) => IDEMPOTENCY_TTL_MINUTES,
+        };
         // TODO: Introduce lifetimes instead of cloning the connection!
         Ok(ServerState {
             order_repository: OrderFactory {
                 connection_pool: pool.clone(),
+                events: events.clone(),
             },
             item_repository: ItemFactory {
                 connection_pool: pool.clone(),
             },
+            modifier_repository: ModifierFactory {
+                connection_pool: pool.clone(),
+            },
+            inventory_repository: InventoryFactory {
+                connection_pool: pool.clone(),
+            },
+            dining_table_repository: DiningTableFactory {
+                connection_pool: pool.clone(),
+            },
+            reservation_repository: ReservationFactory {
+                connection_pool: pool.clone(),
+            },
+            waitlist_repository: WaitlistFactory {
+                connection_pool: pool.clone(),
+                events: events.clone(),
+            },
             table_repository: TableFactory {
                 connection_pool: pool.clone(),
+                events: events.clone(),
+            },
+            ticket_repository: TicketFactory {
+                connection_pool: pool.clone(),
+                events: events.clone(),
+            },
+            bill_repository: BillFactory {
+                connection_pool: pool.clone(),
+                events: events.clone(),
+            },
+            payment_repository: PaymentFactory {
+                connection_pool: pool.clone(),
+            },
+            category_repository: CategoryFactory {
+                connection_pool: pool.clone(),
+            },
+            promotion_repository: PromotionFactory {
+                connection_pool: pool.clone(),
+            },
+            tax_rate_repository: TaxRateFactory {
+                connection_pool: pool.clone(),
+            },
+            staff_repository: StaffFactory {
+                connection_pool: pool.clone(),
+                keys,
+            },
+            audit_repository: AuditFactory {
+                connection_pool: pool.clone(),
+            },
+            idempotency_repository: IdempotencyFactory {
+                connection_pool: pool.clone(),
+                ttl: Duration::minutes(idempotency_ttl),
             },
+            events,
         })
     }
 }
@@ -1,5 +1,7 @@
 //! application/config.rs
 
+use crate::domain::money::Currency;
+
 /// Default path to the app configuration file.
 #[allow(unused)] // TODO read from config file
 const DEFAULT_CONFIG_PATH: &str = if cfg!(debug_assertions) {
@@ -10,3 +12,61 @@ const DEFAULT_CONFIG_PATH: &str = if cfg!(debug_assertions) {
 
 pub(crate) const HOST_URL: &str = "127.0.0.1";
 pub(crate) const HOST_PORT: &str = "8080";
+
+/// Name of the admin account created from `ADMIN_SECRET` when there is no admin.
+pub(crate) const ADMIN_NAME: &str = "admin";
+
+/// Currency of a table session, unless another one is given at check-in.
+pub(crate) const DEFAULT_CURRENCY: Currency = Currency::Eur;
+
+/// How early a party may check in for its reservation.
+pub(crate) const RESERVATION_EARLY_MINUTES: i64 = 15;
+
+/// Time a party is expected to stay at a table, until there are sessions to go by.
+pub(crate) const DEFAULT_TURN_MINUTES: i64 = 60;
+
+/// Number of the latest closed sessions the turn time is worked out from.
+pub(crate) const TURN_TIME_SESSIONS: i64 = 50;
+
+/// Number of kitchen events buffered per subscriber before it starts lagging.
+pub(crate) const KITCHEN_EVENT_CAPACITY: usize = 256;
+
+/// Shortest PIN or password a staff member may log in with.
+pub(crate) const MIN_SECRET_LENGTH: usize = 4;
+
+/// Failed logins in a row after which an account is locked.
+pub(crate) const MAX_FAILED_LOGINS: i32 = 5;
+
+/// How long an account stays locked after too many failed logins.
+pub(crate) const LOGIN_LOCKOUT_MINUTES: i64 = 15;
+
+/// How long a login token is valid, about a shift.
+pub(crate) const TOKEN_TTL_MINUTES: i64 = 12 * 60;
+
+/// Websocket subprotocol a browser offers its bearer token after, as `bearer, <token>`,
+/// since it can't set the authorization header on a websocket.
+pub(crate) const WEBSOCKET_TOKEN_PROTOCOL: &str = "bearer";
+
+/// Header a till or handheld identifies itself with, kept in the audit log.
+pub(crate) const DEVICE_HEADER: &str = "x-device-id";
+
+/// Entries on a page of a collection, unless another limit is asked for.
+pub(crate) const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Most entries a page of a collection holds.
+pub(crate) const MAX_PAGE_SIZE: i64 = 200;
+
+/// Header a request that may be retried is made with, a retry gets the original response.
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header set on a response that is the replay of an earlier one.
+pub(crate) const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long an idempotency key is kept, unless `IDEMPOTENCY_TTL_MINUTES` says otherwise.
+pub(crate) const IDEMPOTENCY_TTL_MINUTES: i64 = 24 * 60;
+
+/// Longest idempotency key accepted.
+pub(crate) const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// Largest request or response body kept for an idempotency key.
+pub(crate) const MAX_IDEMPOTENT_BODY_BYTES: usize = 2 * 1024 * 1024;
@@ -2,34 +2,32 @@
 //! Application features (usecases)
 //! TODO use these inside
 
-use anyhow::Result;
 use diesel::prelude::*;
 use diesel::PgConnection;
 use diesel::{QueryDsl, SelectableHelper};
 
-use crate::domain::entities::table::Table;
-use crate::domain::entities::tables::total;
+use crate::domain::entities::table::{SessionStatus, Table};
+use crate::domain::entities::tables::status;
 
-/// Get all active tables, i.e. tables which has not paid yet.
-/// (Could obviously be handled with another type of flag.)
-pub(crate) fn get_all_active_tables(conn: &mut PgConnection) -> Result<Vec<Table>> {
+/// Get all active tables, i.e. tables where a party is still seated.
+pub(crate) fn get_all_active_tables(conn: &mut PgConnection) -> QueryResult<Vec<Table>> {
     use crate::domain::entities::tables;
     let all = tables::table
         .select(Table::as_select())
-        .filter(total.eq(-1))
+        .filter(status.ne(SessionStatus::Closed))
         .load(conn)?;
     Ok(all)
 }
 
-/// Get table from id
-pub(crate) fn get_table(conn: &mut PgConnection, cid: &i32) -> Result<Vec<Table>> {
+/// Get the session of a table number where a party is still seated
+pub(crate) fn get_table(conn: &mut PgConnection, cid: &i32) -> QueryResult<Vec<Table>> {
     use crate::domain::entities::tables;
     let table = tables::table
         .select(Table::as_select())
         .filter(
             crate::domain::entities::tables::table_number
                 .eq(cid)
-                .and(crate::domain::entities::tables::total.eq(-1_i32)),
+                .and(status.ne(SessionStatus::Closed)),
         )
         .load(conn)?;
     Ok(table)
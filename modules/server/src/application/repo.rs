@@ -1,35 +1,200 @@
-use crate::{
-    adapters::ServerResult, // Todo, move me out of adapter.
-    domain::entities::{
-        item::{Item, NewItem},
-        order::{NewOrder, Order},
-        table::{NewTable, Table},
+use crate::domain::{
+    entities::{
+        audit::{AuditEntry, AuditFilter, AuditSort},
+        bill::Bill,
+        bill_part::{BillPart, BillSplit},
+        category::{Category, NewCategory},
+        dining_table::{DiningTable, DiningTableChanges, NewDiningTable},
+        idempotency::Attempt,
+        ingredient::{Ingredient, NewIngredient, RecipeIngredient},
+        item::{Item, ItemAvailability, ItemChanges, ItemFilter, ItemPrice, ItemSort, NewItem},
+        modifier::{Modifier, ModifierGroup, NewModifierEntry, NewModifierGroup},
+        order::{NewOrderLine, Order, OrderFilter, OrderSort, OrderStatus},
+        payment::{NewPaymentEntry, Payment},
+        promotion::{NewPromotion, Promotion},
+        reservation::{NewReservation, Reservation, ReservationChanges},
+        staff::{NewStaff, Session, Staff, StaffChanges},
+        table::{NewTable, Table, TableFilter, TableSort},
+        table_move::TableMove,
+        tax::{NewTaxRate, TaxRate},
+        ticket::{NewTicketLine, Ticket},
+        waitlist::{NewWaitlistEntry, WaitlistEntry},
     },
+    error::ServerResult,
+    page::{Page, PageRequest},
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 #[async_trait(?Send)]
 pub(crate) trait OrderRepository {
     fn find(&self, id: &i32) -> ServerResult<Vec<Order>>;
-    fn find_table(&self, id: &i32) -> ServerResult<Vec<Order>>;
-    fn delete_table_order(&self, cid: &i32, oid: &i32) -> ServerResult<String>;
-    fn create(&self, order: &NewOrder) -> ServerResult<Order>;
-    fn delete(&self, item_id: &i32) -> ServerResult<()>;
-    fn all(&self) -> ServerResult<Vec<Order>>;
-    fn total(&self, oid: &i32) -> ServerResult<i32>;
+    fn find_table(&self, id: &i32, status: &[OrderStatus]) -> ServerResult<Vec<Order>>;
+    fn delete_table_order(&self, cid: &i32, oid: &i32, manages: bool) -> ServerResult<String>;
+    fn create_batch(
+        &self,
+        lines: &[NewOrderLine],
+        partial: bool,
+    ) -> ServerResult<(bool, Vec<ServerResult<Order>>)>;
+    fn delete(&self, item_id: &i32, manages: bool) -> ServerResult<()>;
+    fn all(&self, filter: &OrderFilter, page: &PageRequest<OrderSort>)
+        -> ServerResult<Page<Order>>;
+    fn set_status(&self, id: &i32, status: &OrderStatus, manages: bool) -> ServerResult<Order>;
 }
 
 #[async_trait(?Send)]
 pub(crate) trait ItemRepository {
     fn create(&self, item: &NewItem) -> ServerResult<Item>;
     fn get(&self, id: &i32) -> ServerResult<Item>;
-    fn all(&self) -> ServerResult<Vec<Item>>;
+    fn all(&self, filter: &ItemFilter, page: &PageRequest<ItemSort>) -> ServerResult<Page<Item>>;
+    fn set_availability(&self, id: &i32, availability: &ItemAvailability) -> ServerResult<Item>;
+    fn update(&self, id: &i32, changes: &ItemChanges) -> ServerResult<Item>;
+    fn archive(&self, id: &i32) -> ServerResult<Item>;
+    fn prices(&self, id: &i32) -> ServerResult<Vec<ItemPrice>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait ModifierRepository {
+    fn create(
+        &self,
+        group: &NewModifierGroup,
+        modifiers: &[NewModifierEntry],
+    ) -> ServerResult<(ModifierGroup, Vec<Modifier>)>;
+    fn find_item(&self, item_id: &i32) -> ServerResult<Vec<(ModifierGroup, Vec<Modifier>)>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait InventoryRepository {
+    fn create(&self, ingredient: &NewIngredient) -> ServerResult<Ingredient>;
+    fn all(&self) -> ServerResult<Vec<Ingredient>>;
+    fn restock(&self, id: &i32, quantity: &i32) -> ServerResult<Ingredient>;
+    fn set_recipe(
+        &self,
+        item_id: &i32,
+        recipe: &[RecipeIngredient],
+    ) -> ServerResult<Vec<RecipeIngredient>>;
+    fn recipe(&self, item_id: &i32) -> ServerResult<Vec<RecipeIngredient>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait DiningTableRepository {
+    fn create(&self, table: &NewDiningTable) -> ServerResult<DiningTable>;
+    fn all(&self) -> ServerResult<Vec<DiningTable>>;
+    fn update(&self, number: &i32, changes: &DiningTableChanges) -> ServerResult<DiningTable>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait ReservationRepository {
+    fn create(&self, reservation: &NewReservation) -> ServerResult<Reservation>;
+    fn get(&self, id: &i32) -> ServerResult<Reservation>;
+    fn all(&self) -> ServerResult<Vec<Reservation>>;
+    fn update(&self, id: &i32, changes: &ReservationChanges) -> ServerResult<Reservation>;
+    fn cancel(&self, id: &i32) -> ServerResult<Reservation>;
+    fn available(
+        &self,
+        starts_at: &DateTime<Utc>,
+        ends_at: &DateTime<Utc>,
+        party_size: &i32,
+    ) -> ServerResult<Vec<DiningTable>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait WaitlistRepository {
+    fn create(&self, entry: &NewWaitlistEntry) -> ServerResult<WaitlistEntry>;
+    fn waiting(&self) -> ServerResult<Vec<(WaitlistEntry, Option<i64>)>>;
+    fn seat(&self, id: &i32, table: &NewTable) -> ServerResult<(WaitlistEntry, Table)>;
+    fn remove(&self, id: &i32) -> ServerResult<WaitlistEntry>;
 }
 
 #[async_trait(?Send)]
 pub(crate) trait TableRepository {
     fn create(&self, item: &NewTable) -> ServerResult<Table>;
     fn get(&self, id: &i32) -> ServerResult<Table>;
-    fn checkout(&self, id: &i32, total: &i32) -> ServerResult<()>;
-    fn all(&self) -> ServerResult<Vec<Table>>;
+    fn all(&self, filter: &TableFilter, page: &PageRequest<TableSort>)
+        -> ServerResult<Page<Table>>;
+    fn transfer(&self, id: &i32, to: &i32) -> ServerResult<Table>;
+    fn merge(&self, id: &i32, other: &i32) -> ServerResult<Table>;
+    fn history(&self, id: &i32) -> ServerResult<Vec<TableMove>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait TicketRepository {
+    fn create(&self, tid: &i32, lines: &[NewTicketLine]) -> ServerResult<(Ticket, Vec<Order>)>;
+    fn find_table(&self, tid: &i32) -> ServerResult<Vec<(Ticket, Vec<Order>)>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait BillRepository {
+    fn draft(&self, tid: &i32) -> ServerResult<Bill>;
+    fn checkout(&self, tid: &i32) -> ServerResult<Bill>;
+    fn split(&self, tid: &i32, split: &BillSplit) -> ServerResult<(Bill, Vec<BillPart>)>;
+    fn parts(&self, tid: &i32) -> ServerResult<(Bill, Vec<BillPart>)>;
+    fn settle(&self, tid: &i32, pid: &i32) -> ServerResult<(Bill, Vec<BillPart>)>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait PaymentRepository {
+    fn create(&self, tid: &i32, entry: &NewPaymentEntry) -> ServerResult<Payment>;
+    fn find_table(&self, tid: &i32) -> ServerResult<Vec<Payment>>;
+    fn void(&self, tid: &i32, pid: &i32) -> ServerResult<Payment>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait CategoryRepository {
+    fn create(&self, category: &NewCategory) -> ServerResult<Category>;
+    fn all(&self) -> ServerResult<Vec<Category>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait PromotionRepository {
+    fn create(&self, promotion: &NewPromotion) -> ServerResult<Promotion>;
+    fn all(&self) -> ServerResult<Vec<Promotion>>;
+    fn deactivate(&self, id: &i32) -> ServerResult<Promotion>;
+    fn redeem(&self, tid: &i32, code: &str) -> ServerResult<Promotion>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait TaxRateRepository {
+    fn create(&self, rate: &NewTaxRate) -> ServerResult<TaxRate>;
+    fn all(&self) -> ServerResult<Vec<TaxRate>>;
+    fn deactivate(&self, id: &i32) -> ServerResult<TaxRate>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait StaffRepository {
+    fn create(&self, staff: &NewStaff) -> ServerResult<Staff>;
+    fn all(&self) -> ServerResult<Vec<Staff>>;
+    fn update(&self, id: &i32, changes: &StaffChanges) -> ServerResult<Staff>;
+    fn bootstrap(&self, name: &str, secret: &str) -> ServerResult<Option<Staff>>;
+    fn login(&self, name: &str, secret: &str) -> ServerResult<Session>;
+    fn authenticate(&self, token: &str) -> ServerResult<Staff>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait AuditRepository {
+    fn find(
+        &self,
+        filter: &AuditFilter,
+        page: &PageRequest<AuditSort>,
+    ) -> ServerResult<Page<AuditEntry>>;
+}
+
+#[async_trait(?Send)]
+pub(crate) trait IdempotencyRepository {
+    fn begin(
+        &self,
+        staff_id: &i32,
+        route: &str,
+        key: &str,
+        fingerprint: &str,
+    ) -> ServerResult<Attempt>;
+    fn complete(
+        &self,
+        staff_id: &i32,
+        route: &str,
+        key: &str,
+        status_code: u16,
+        body: &[u8],
+    ) -> ServerResult;
+    fn release(&self, staff_id: &i32, route: &str, key: &str) -> ServerResult;
 }
@@ -0,0 +1,97 @@
+//! Audit
+use std::fmt;
+use std::str::FromStr;
+
+use super::audit_entries;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Who a request is made by, the writes it makes are audited as theirs.
+#[derive(Clone, Debug)]
+pub(crate) struct Actor {
+    pub(crate) staff_id: i32,
+    /// Device the request came from, as it identified itself.
+    pub(crate) device: Option<String>,
+    /// Trace the request is part of, as its log lines show it.
+    pub(crate) trace_id: String,
+}
+
+/// A write made through the API.
+#[derive(Identifiable, Selectable, Queryable, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = audit_entries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct AuditEntry {
+    pub(crate) id: i32,
+    pub(crate) created_at: DateTime<Utc>,
+    /// Staff member who made the write, none for writes the server made on its own.
+    pub(crate) actor_id: Option<i32>,
+    pub(crate) device: Option<String>,
+    /// What was done, e.g. `delete`.
+    pub(crate) action: String,
+    /// Kind of what it was done to, e.g. `order`.
+    pub(crate) entity: String,
+    pub(crate) entity_id: i32,
+    /// What the entity looked like before, none if it was created.
+    pub(crate) before: Option<Value>,
+    /// What the entity looked like after, none if it was deleted.
+    pub(crate) after: Option<Value>,
+    /// Trace of the request, to find its log lines by.
+    pub(crate) trace_id: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_entries)]
+pub(crate) struct NewAuditEntry<'a> {
+    pub(crate) actor_id: Option<i32>,
+    pub(crate) device: Option<String>,
+    pub(crate) action: &'a str,
+    pub(crate) entity: &'a str,
+    pub(crate) entity_id: i32,
+    pub(crate) before: Option<Value>,
+    pub(crate) after: Option<Value>,
+    pub(crate) trace_id: Option<String>,
+}
+
+/// Fields audit entries can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AuditSort {
+    CreatedAt,
+}
+
+impl AuditSort {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AuditSort::CreatedAt => "created_at",
+        }
+    }
+}
+
+impl fmt::Display for AuditSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created_at" => Ok(AuditSort::CreatedAt),
+            other => Err(format!("Unable to sort by {:?}", other)),
+        }
+    }
+}
+
+/// Which entries to find, everything that is not set matches.
+#[derive(Debug, Default)]
+pub(crate) struct AuditFilter<'a> {
+    pub(crate) entity: Option<&'a String>,
+    pub(crate) entity_id: Option<&'a i32>,
+    pub(crate) actor_id: Option<&'a i32>,
+    pub(crate) from: Option<DateTime<Utc>>,
+    pub(crate) until: Option<DateTime<Utc>>,
+}
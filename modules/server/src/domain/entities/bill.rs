@@ -0,0 +1,342 @@
+//! Bill
+use std::fmt;
+use std::str::FromStr;
+
+use super::{bill_lines, bills};
+use crate::domain::error::ServerResult;
+use crate::domain::money::{overflow, Currency, Money};
+use chrono::{DateTime, NaiveTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a stored bill line is for.
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq)]
+#[diesel(sql_type = Text)]
+pub(crate) enum BillLineKind {
+    Item,
+    Discount,
+    Tax,
+    IncludedTax,
+    Charge,
+}
+
+impl BillLineKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            BillLineKind::Item => "item",
+            BillLineKind::Discount => "discount",
+            BillLineKind::Tax => "tax",
+            BillLineKind::IncludedTax => "included_tax",
+            BillLineKind::Charge => "charge",
+        }
+    }
+}
+
+impl fmt::Display for BillLineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BillLineKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "item" => Ok(BillLineKind::Item),
+            "discount" => Ok(BillLineKind::Discount),
+            "tax" => Ok(BillLineKind::Tax),
+            "included_tax" => Ok(BillLineKind::IncludedTax),
+            "charge" => Ok(BillLineKind::Charge),
+            other => Err(format!("Unknown bill line kind {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for BillLineKind {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for BillLineKind {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let kind = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(kind.parse()?)
+    }
+}
+
+/// Something ordered by the table.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub(crate) struct BillItem {
+    pub(crate) description: String,
+    pub(crate) unit_price: Money,
+    pub(crate) quantity: i32,
+    pub(crate) line_total: Money,
+}
+
+impl BillItem {
+    pub(crate) fn new(description: String, unit_price: Money, quantity: i32) -> ServerResult<Self> {
+        Ok(BillItem {
+            description,
+            unit_price,
+            quantity,
+            line_total: unit_price.checked_mul(i64::from(quantity))?,
+        })
+    }
+}
+
+/// An order of a table, with what promotions need to know about it.
+#[derive(Clone, Debug)]
+pub(crate) struct BilledOrder {
+    pub(crate) order_id: i32,
+    pub(crate) item_id: i32,
+    pub(crate) category_id: Option<i32>,
+    /// Local time of day the order was placed.
+    pub(crate) ordered_at: NaiveTime,
+    pub(crate) item: BillItem,
+}
+
+/// A promotion applied to the bill, the amount is negative.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub(crate) struct BillDiscount {
+    pub(crate) promotion_id: i32,
+    pub(crate) description: String,
+    pub(crate) amount: Money,
+}
+
+/// A tax on the bill. Taxes included in the prices are reported, but not added to the total.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub(crate) struct BillTax {
+    pub(crate) tax_rate_id: i32,
+    pub(crate) description: String,
+    pub(crate) inclusive: bool,
+    pub(crate) amount: Money,
+}
+
+/// A charge added on top of the subtotal.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub(crate) struct BillCharge {
+    pub(crate) description: String,
+    pub(crate) amount: Money,
+}
+
+/// Itemized bill of a table session, every amount is in the currency of the session.
+/// `issued_at` is only set once the table has checked out and the bill is final.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub(crate) struct Bill {
+    pub(crate) table_number: i32,
+    pub(crate) items: Vec<BillItem>,
+    pub(crate) subtotal: Money,
+    pub(crate) discounts: Vec<BillDiscount>,
+    pub(crate) taxes: Vec<BillTax>,
+    pub(crate) charges: Vec<BillCharge>,
+    pub(crate) total: Money,
+    pub(crate) issued_at: Option<DateTime<Utc>>,
+}
+
+impl Bill {
+    /// Build a bill, merging the items that were ordered more than once at the same price.
+    /// Fails when a line is not in `currency`, or the total does not fit.
+    pub(crate) fn new(
+        table_number: i32,
+        currency: Currency,
+        ordered: Vec<BillItem>,
+        discounts: Vec<BillDiscount>,
+        taxes: Vec<BillTax>,
+        charges: Vec<BillCharge>,
+    ) -> ServerResult<Self> {
+        let mut items: Vec<BillItem> = Vec::with_capacity(ordered.len());
+        for item in ordered {
+            match items.iter_mut().find(|line| {
+                line.description == item.description && line.unit_price == item.unit_price
+            }) {
+                Some(line) => {
+                    line.quantity = line
+                        .quantity
+                        .checked_add(item.quantity)
+                        .ok_or_else(overflow)?;
+                    line.line_total = line.line_total.checked_add(&item.line_total)?;
+                }
+                None => items.push(item),
+            }
+        }
+        let subtotal = Money::sum(currency, items.iter().map(|item| &item.line_total))?;
+        let total = Money::sum(
+            currency,
+            std::iter::once(&subtotal)
+                .chain(discounts.iter().map(|discount| &discount.amount))
+                .chain(
+                    taxes
+                        .iter()
+                        .filter(|tax| !tax.inclusive)
+                        .map(|tax| &tax.amount),
+                )
+                .chain(charges.iter().map(|charge| &charge.amount)),
+        )?;
+        Ok(Bill {
+            table_number,
+            items,
+            subtotal,
+            discounts,
+            taxes,
+            charges,
+            total,
+            issued_at: None,
+        })
+    }
+
+    /// Every line of the bill, as it is stored.
+    pub(crate) fn lines(&self, bill_id: i32) -> Vec<NewBillLine<'_>> {
+        let items = self.items.iter().map(|item| NewBillLine {
+            bill_id,
+            kind: BillLineKind::Item,
+            description: &item.description,
+            unit_price: item.unit_price.amount,
+            quantity: item.quantity,
+            amount: item.line_total.amount,
+            promotion_id: None,
+            tax_rate_id: None,
+        });
+        let discounts = self.discounts.iter().map(|discount| NewBillLine {
+            bill_id,
+            kind: BillLineKind::Discount,
+            description: &discount.description,
+            unit_price: discount.amount.amount,
+            quantity: 1,
+            amount: discount.amount.amount,
+            promotion_id: Some(discount.promotion_id),
+            tax_rate_id: None,
+        });
+        let taxes = self.taxes.iter().map(|tax| NewBillLine {
+            bill_id,
+            kind: if tax.inclusive {
+                BillLineKind::IncludedTax
+            } else {
+                BillLineKind::Tax
+            },
+            description: &tax.description,
+            unit_price: tax.amount.amount,
+            quantity: 1,
+            amount: tax.amount.amount,
+            promotion_id: None,
+            tax_rate_id: Some(tax.tax_rate_id),
+        });
+        let charges = self.charges.iter().map(|charge| NewBillLine {
+            bill_id,
+            kind: BillLineKind::Charge,
+            description: &charge.description,
+            unit_price: charge.amount.amount,
+            quantity: 1,
+            amount: charge.amount.amount,
+            promotion_id: None,
+            tax_rate_id: None,
+        });
+        items.chain(discounts).chain(taxes).chain(charges).collect()
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = bills)]
+pub(crate) struct NewBill<'a> {
+    pub(crate) table_id: &'a i32,
+    pub(crate) subtotal: &'a i64,
+    pub(crate) total: &'a i64,
+    pub(crate) currency: Currency,
+}
+
+/// A line of a stored bill, in the currency of the bill.
+#[derive(Insertable)]
+#[diesel(table_name = bill_lines)]
+pub(crate) struct NewBillLine<'a> {
+    pub(crate) bill_id: i32,
+    pub(crate) kind: BillLineKind,
+    pub(crate) description: &'a String,
+    pub(crate) unit_price: i64,
+    pub(crate) quantity: i32,
+    pub(crate) amount: i64,
+    pub(crate) promotion_id: Option<i32>,
+    pub(crate) tax_rate_id: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::error::ErrorCode;
+
+    fn eur(amount: i64) -> Money {
+        Money::new(amount, Currency::Eur)
+    }
+
+    #[test]
+    fn test_bill_totals() {
+        let bill = Bill::new(
+            1,
+            Currency::Eur,
+            vec![
+                BillItem::new("Ramen".to_string(), eur(12), 2).unwrap(),
+                BillItem::new("Gyoza".to_string(), eur(5), 1).unwrap(),
+                BillItem::new("Ramen".to_string(), eur(12), 1).unwrap(),
+            ],
+            vec![BillDiscount {
+                promotion_id: 1,
+                description: "Happy hour".to_string(),
+                amount: eur(-6),
+            }],
+            vec![
+                BillTax {
+                    tax_rate_id: 1,
+                    description: "Sales tax".to_string(),
+                    inclusive: false,
+                    amount: eur(3),
+                },
+                BillTax {
+                    tax_rate_id: 2,
+                    description: "VAT".to_string(),
+                    inclusive: true,
+                    amount: eur(5),
+                },
+            ],
+            vec![BillCharge {
+                description: "Service".to_string(),
+                amount: eur(4),
+            }],
+        )
+        .unwrap();
+        assert_eq!(bill.items.len(), 2);
+        assert_eq!(
+            bill.items[0],
+            BillItem::new("Ramen".to_string(), eur(12), 3).unwrap()
+        );
+        assert_eq!(bill.subtotal, eur(41));
+        assert_eq!(bill.total, eur(42));
+        assert_eq!(bill.lines(1).len(), 6);
+        let dollars = BillItem::new("Ramen".to_string(), Money::new(12, Currency::Usd), 1);
+        assert!(Bill::new(
+            1,
+            Currency::Eur,
+            vec![dollars.unwrap()],
+            vec![],
+            vec![],
+            vec![]
+        )
+        .is_err());
+        let free = || BillItem::new("Water".to_string(), eur(0), i32::MAX).unwrap();
+        let err = Bill::new(
+            1,
+            Currency::Eur,
+            vec![free(), free()],
+            vec![],
+            vec![],
+            vec![],
+        )
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::AmountOverflow);
+    }
+}
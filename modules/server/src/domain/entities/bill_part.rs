@@ -0,0 +1,199 @@
+//! Bill part
+use std::collections::HashMap;
+
+use super::{bill::Bill, bill_parts};
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use crate::domain::money::Money;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Most guests a single bill is split between.
+const MAX_PARTS: usize = 100;
+
+/// How a bill is split between the guests of a table.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub(crate) enum BillSplit {
+    /// Split the total evenly between this many guests.
+    Even { parts: i32 },
+    /// Every guest pays for their own orders, charges are shared in proportion.
+    Seats { seats: Vec<Vec<i32>> },
+    /// Every guest pays the given amount, the amounts must add up to the total.
+    Amounts { amounts: Vec<Money> },
+}
+
+impl BillSplit {
+    /// Work out what every guest pays, `orders` are the billed orders with their line totals.
+    pub(crate) fn parts(
+        &self,
+        bill: &Bill,
+        orders: &[(i32, Money)],
+    ) -> ServerResult<Vec<NewBillPart>> {
+        let invalid = |error: &str| Err(ApiError::new(ErrorCode::InvalidRequest, error));
+        let shares: Vec<(Money, Vec<i32>)> = match self {
+            BillSplit::Even { parts } => {
+                if *parts <= 0 || *parts as usize > MAX_PARTS {
+                    return invalid("A bill is split in 1 to 100 parts!");
+                }
+                bill.total
+                    .allocate(&vec![1; *parts as usize])
+                    .into_iter()
+                    .map(|amount| (amount, vec![]))
+                    .collect()
+            }
+            BillSplit::Seats { seats } => {
+                let line_totals: HashMap<i32, Money> = orders.iter().copied().collect();
+                let mut assigned: Vec<i32> = seats.iter().flatten().copied().collect();
+                assigned.sort_unstable();
+                let mut billed: Vec<i32> = line_totals.keys().copied().collect();
+                billed.sort_unstable();
+                if seats.len() > MAX_PARTS || seats.iter().any(Vec::is_empty) || assigned != billed
+                {
+                    return invalid("Every billed order must be assigned to exactly one seat!");
+                }
+                let subtotals = seats
+                    .iter()
+                    .map(|seat| {
+                        Money::sum(
+                            bill.total.currency,
+                            seat.iter().map(|order| &line_totals[order]),
+                        )
+                    })
+                    .collect::<ServerResult<Vec<Money>>>()?;
+                let weights: Vec<i64> = subtotals.iter().map(|subtotal| subtotal.amount).collect();
+                let charges = bill.total.checked_sub(&bill.subtotal)?.allocate(&weights);
+                seats
+                    .iter()
+                    .zip(subtotals.iter().zip(charges))
+                    .map(|(seat, (subtotal, charge))| {
+                        Ok((subtotal.checked_add(&charge)?, seat.clone()))
+                    })
+                    .collect::<ServerResult<_>>()?
+            }
+            BillSplit::Amounts { amounts } => {
+                if amounts.is_empty()
+                    || amounts.len() > MAX_PARTS
+                    || amounts.iter().any(Money::is_negative)
+                {
+                    return invalid("Every part needs an amount!");
+                }
+                if Money::sum(bill.total.currency, amounts)? != bill.total {
+                    return invalid("The amounts must add up to the total!");
+                }
+                amounts.iter().map(|amount| (*amount, vec![])).collect()
+            }
+        };
+        Ok(shares
+            .into_iter()
+            .zip(1..)
+            .map(|((amount, order_ids), position)| NewBillPart {
+                position,
+                amount,
+                order_ids,
+            })
+            .collect())
+    }
+}
+
+/// Part of a split bill, settled on its own.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = bill_parts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct BillPart {
+    pub(crate) id: i32,
+    pub(crate) position: i32,
+    #[diesel(select_expression = (bill_parts::amount, bill_parts::currency))]
+    #[diesel(select_expression_type = (bill_parts::amount, bill_parts::currency))]
+    pub(crate) amount: Money,
+    pub(crate) order_ids: Vec<i32>,
+    pub(crate) paid_at: Option<DateTime<Utc>>,
+}
+
+/// A part of a split bill that is yet to be stored.
+#[derive(Debug, PartialEq)]
+pub(crate) struct NewBillPart {
+    pub(crate) position: i32,
+    pub(crate) amount: Money,
+    pub(crate) order_ids: Vec<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::bill::{BillCharge, BillItem};
+    use crate::domain::money::Currency;
+
+    fn eur(amount: i64) -> Money {
+        Money::new(amount, Currency::Eur)
+    }
+
+    #[test]
+    fn test_split_even() {
+        let bill = Bill::new(
+            1,
+            Currency::Eur,
+            vec![BillItem::new("Ramen".to_string(), eur(1999), 1).unwrap()],
+            vec![],
+            vec![],
+            vec![],
+        )
+        .unwrap();
+        for parts in 1..20 {
+            let split = BillSplit::Even { parts };
+            let amounts: Vec<Money> = split
+                .parts(&bill, &[])
+                .unwrap()
+                .into_iter()
+                .map(|part| part.amount)
+                .collect();
+            assert_eq!(Money::sum(Currency::Eur, &amounts).unwrap(), eur(1999));
+        }
+        let split = BillSplit::Even { parts: 3 };
+        let amounts: Vec<i64> = split
+            .parts(&bill, &[])
+            .unwrap()
+            .iter()
+            .map(|part| part.amount.amount)
+            .collect();
+        assert_eq!(amounts, vec![667, 666, 666]);
+    }
+
+    #[test]
+    fn test_split_seats() {
+        let bill = Bill::new(
+            1,
+            Currency::Eur,
+            vec![
+                BillItem::new("Ramen".to_string(), eur(12), 1).unwrap(),
+                BillItem::new("Gyoza".to_string(), eur(5), 2).unwrap(),
+            ],
+            vec![],
+            vec![],
+            vec![BillCharge {
+                description: "Service".to_string(),
+                amount: eur(5),
+            }],
+        )
+        .unwrap();
+        let orders = [(10, eur(12)), (11, eur(10))];
+        let split = BillSplit::Seats {
+            seats: vec![vec![10], vec![11]],
+        };
+        let parts = split.parts(&bill, &orders).unwrap();
+        assert_eq!((parts[0].amount, parts[1].amount), (eur(15), eur(12)));
+        let split = BillSplit::Seats {
+            seats: vec![vec![10]],
+        };
+        assert!(split.parts(&bill, &orders).is_err());
+        let split = BillSplit::Amounts {
+            amounts: vec![eur(20), eur(6)],
+        };
+        assert!(split.parts(&bill, &orders).is_err());
+        let split = BillSplit::Amounts {
+            amounts: vec![eur(20), Money::new(7, Currency::Usd)],
+        };
+        assert!(split.parts(&bill, &orders).is_err());
+    }
+}
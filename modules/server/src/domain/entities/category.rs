@@ -0,0 +1,22 @@
+//! Category
+use super::categories;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Section of the menu an item is listed under, e.g. drinks.
+#[derive(
+    Identifiable, Selectable, Queryable, Debug, Deserialize, Serialize, PartialEq, ToSchema,
+)]
+#[diesel(table_name = categories)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct Category {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = categories)]
+pub struct NewCategory<'a> {
+    pub(crate) name: &'a String,
+}
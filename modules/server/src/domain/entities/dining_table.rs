@@ -0,0 +1,71 @@
+//! Dining table
+use super::dining_tables;
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A physical table of the floor, parties are seated at it by its number.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = dining_tables)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct DiningTable {
+    #[serde(skip_serializing)]
+    pub(crate) id: i32,
+    pub(crate) table_number: i32,
+    pub(crate) seats: i32,
+    /// Area of the floor the table is in, e.g. `terrace`.
+    pub(crate) section: String,
+    /// Whether parties can be seated at the table.
+    pub(crate) active: bool,
+}
+
+impl DiningTable {
+    /// Whether a party can be seated at the table, a party of unknown size always fits.
+    pub(crate) fn seat(&self, party_size: Option<&i32>) -> ServerResult {
+        if !self.active {
+            return Err(ApiError::new(
+                ErrorCode::TableInactive,
+                format!("Table {} is not in use!", self.table_number),
+            ));
+        }
+        match party_size {
+            Some(size) if *size <= 0 => Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                "Party size must be positive!",
+            )),
+            Some(size) if *size > self.seats => Err(ApiError::new(
+                ErrorCode::PartyTooLarge,
+                format!(
+                    "Table {} seats {}, unable to seat a party of {}!",
+                    self.table_number, self.seats, size
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Registers a table, it is placed in the main section unless told otherwise.
+#[derive(Insertable)]
+#[diesel(table_name = dining_tables)]
+pub(crate) struct NewDiningTable<'a> {
+    pub(crate) table_number: &'a i32,
+    pub(crate) seats: &'a i32,
+    pub(crate) section: Option<&'a String>,
+}
+
+/// Changes to a table, only what is set is changed.
+#[derive(AsChangeset)]
+#[diesel(table_name = dining_tables)]
+pub(crate) struct DiningTableChanges<'a> {
+    pub(crate) seats: Option<&'a i32>,
+    pub(crate) section: Option<&'a String>,
+    pub(crate) active: Option<bool>,
+}
+
+impl DiningTableChanges<'_> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.seats.is_none() && self.section.is_none() && self.active.is_none()
+    }
+}
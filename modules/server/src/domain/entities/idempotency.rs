@@ -0,0 +1,39 @@
+//! Idempotency
+use super::idempotency_keys;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+/// An idempotency key a request was made with, and the response it got once there is one.
+#[derive(Identifiable, Selectable, Queryable, Debug)]
+#[diesel(table_name = idempotency_keys, primary_key(staff_id, route, key))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct IdempotencyKey {
+    /// Staff member the key was used by, keys of others are unrelated.
+    pub(crate) staff_id: i32,
+    /// Method and path of the route the key was used on, e.g. `POST /api/v1/orders`.
+    pub(crate) route: String,
+    pub(crate) key: String,
+    /// Hash of the request, a key is only replayed for the very same request.
+    pub(crate) fingerprint: String,
+    pub(crate) status_code: Option<i16>,
+    pub(crate) body: Option<Vec<u8>>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = idempotency_keys)]
+pub(crate) struct NewIdempotencyKey<'a> {
+    pub(crate) staff_id: i32,
+    pub(crate) route: &'a str,
+    pub(crate) key: &'a str,
+    pub(crate) fingerprint: &'a str,
+    pub(crate) expires_at: DateTime<Utc>,
+}
+
+/// How to handle a request made with an idempotency key.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Attempt {
+    /// The key is new, the request is handled and its response kept.
+    First,
+    /// The request was handled before, it is answered with the response it got then.
+    Replay { status_code: u16, body: Vec<u8> },
+}
@@ -0,0 +1,59 @@
+//! Ingredient
+use super::{ingredients, recipe_ingredients};
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Stock of an ingredient, counted in its unit, e.g. grams.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = ingredients)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct Ingredient {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) unit: String,
+    pub(crate) stock: i32,
+    /// Stock at or below which the ingredient is reported low.
+    pub(crate) low_stock: i32,
+}
+
+impl Ingredient {
+    pub(crate) fn low(&self) -> bool {
+        self.stock <= self.low_stock
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ingredients)]
+pub(crate) struct NewIngredient<'a> {
+    pub(crate) name: &'a String,
+    pub(crate) unit: &'a String,
+    pub(crate) stock: &'a i32,
+    pub(crate) low_stock: &'a i32,
+}
+
+/// How much of an ingredient goes into a single portion of an item.
+#[derive(Selectable, Queryable, Insertable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = recipe_ingredients)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct RecipeIngredient {
+    pub(crate) item_id: i32,
+    pub(crate) ingredient_id: i32,
+    pub(crate) quantity: i32,
+}
+
+impl RecipeIngredient {
+    /// How much of the ingredient goes into `portions` of the item.
+    pub(crate) fn needed(&self, portions: &i32) -> ServerResult<i32> {
+        self.quantity.checked_mul(*portions).ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::InvalidQuantity,
+                format!(
+                    "Unable to make {} portions of item {}!",
+                    portions, self.item_id
+                ),
+            )
+        })
+    }
+}
@@ -1,5 +1,10 @@
 //! Item
-use super::items;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{item_prices, items};
+use crate::domain::money::{Currency, Money};
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -12,14 +17,125 @@ use utoipa::ToSchema;
 pub(crate) struct Item {
     pub(crate) id: i32,
     pub(crate) estimated_minutes: i32,
-    pub(crate) price: i32,
+    #[diesel(select_expression = (items::price, items::currency))]
+    #[diesel(select_expression_type = (items::price, items::currency))]
+    pub(crate) price: Money,
     pub(crate) description: String,
+    pub(crate) category_id: Option<i32>,
+    /// Whether the item can be ordered, it is sold out otherwise.
+    /// An item is also sold out while an ingredient of its recipe ran out.
+    #[diesel(select_expression = items::available.and(items::stocked))]
+    #[diesel(select_expression_type = diesel::dsl::And<items::available, items::stocked>)]
+    pub(crate) available: bool,
+    /// Portions left, when they are counted.
+    pub(crate) remaining: Option<i32>,
+    /// When the item was taken off the menu, orders and bills still refer to it.
+    pub(crate) archived_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = items)]
-pub struct NewItem<'a> {
+pub(crate) struct NewItem<'a> {
     pub(crate) description: &'a String,
     pub(crate) estimated_minutes: &'a i32,
-    pub(crate) price: &'a i32,
+    pub(crate) price: &'a i64,
+    pub(crate) currency: Currency,
+    pub(crate) category_id: Option<&'a i32>,
+}
+
+/// A price an item had, from the moment it took effect until the next one did.
+#[derive(Identifiable, Selectable, Queryable, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = item_prices)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct ItemPrice {
+    pub(crate) id: i32,
+    pub(crate) item_id: i32,
+    #[diesel(select_expression = (item_prices::price, item_prices::currency))]
+    #[diesel(select_expression_type = (item_prices::price, item_prices::currency))]
+    pub(crate) price: Money,
+    pub(crate) effective_from: DateTime<Utc>,
+}
+
+/// Changes to an item, only what is set is changed.
+/// A price stays in the currency of the item.
+#[derive(AsChangeset)]
+#[diesel(table_name = items)]
+pub(crate) struct ItemChanges<'a> {
+    pub(crate) description: Option<&'a String>,
+    pub(crate) price: Option<&'a i64>,
+    pub(crate) currency: Option<Currency>,
+    pub(crate) category_id: Option<&'a i32>,
+}
+
+impl ItemChanges<'_> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.description.is_none() && self.price.is_none() && self.category_id.is_none()
+    }
+}
+
+/// Whether an item can be ordered, and how many portions are left.
+/// An item without portions left is sold out.
+#[derive(AsChangeset)]
+#[diesel(table_name = items, treat_none_as_null = true)]
+pub(crate) struct ItemAvailability<'a> {
+    pub(crate) available: bool,
+    pub(crate) remaining: Option<&'a i32>,
+    /// Availability set by hand is kept when portions are given back.
+    pub(crate) sold_out_by_stock: bool,
+}
+
+impl<'a> ItemAvailability<'a> {
+    pub(crate) fn new(available: bool, remaining: Option<&'a i32>) -> Self {
+        ItemAvailability {
+            available: available && remaining != Some(&0),
+            remaining,
+            sold_out_by_stock: false,
+        }
+    }
+}
+
+/// Fields menu items can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ItemSort {
+    Id,
+    Description,
+    Price,
+}
+
+impl ItemSort {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ItemSort::Id => "id",
+            ItemSort::Description => "description",
+            ItemSort::Price => "price",
+        }
+    }
+}
+
+impl fmt::Display for ItemSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ItemSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(ItemSort::Id),
+            "description" => Ok(ItemSort::Description),
+            "price" => Ok(ItemSort::Price),
+            other => Err(format!("Unable to sort by {:?}", other)),
+        }
+    }
+}
+
+/// Which items of the menu to find, everything that is not set matches.
+#[derive(Debug, Default)]
+pub(crate) struct ItemFilter<'a> {
+    pub(crate) ids: &'a [i32],
+    pub(crate) category_id: Option<&'a i32>,
+    /// Only items that can be ordered, or only sold out ones.
+    pub(crate) available: Option<bool>,
 }
@@ -1,15 +1,199 @@
 //! mod
+pub(crate) mod audit;
+pub(crate) mod bill;
+pub(crate) mod bill_part;
+pub(crate) mod category;
+pub(crate) mod dining_table;
+pub(crate) mod idempotency;
+pub(crate) mod ingredient;
 pub(crate) mod item;
+pub(crate) mod modifier;
 pub(crate) mod order;
+pub(crate) mod payment;
+pub(crate) mod promotion;
+pub(crate) mod reservation;
+pub(crate) mod staff;
 pub(crate) mod table;
+pub(crate) mod table_move;
+pub(crate) mod tax;
+pub(crate) mod ticket;
+pub(crate) mod waitlist;
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    payments (id) {
+        id -> Int4,
+        table_id -> Int4,
+        bill_part_id -> Nullable<Int4>,
+        tender -> Text,
+        amount -> Int8,
+        tip -> Int8,
+        reference -> Nullable<Text>,
+        created_at -> Timestamptz,
+        voided_at -> Nullable<Timestamptz>,
+        currency -> Text,
+    }
+}
+
 diesel::table! {
     tables (id) {
         id -> Int4,
-        checked_in_time -> Text,
         table_number -> Int4,
-        total -> Int4,
+        total -> Nullable<Int8>,
+        opened_at -> Timestamptz,
+        closed_at -> Nullable<Timestamptz>,
+        status -> Text,
+        service -> Text,
+        currency -> Text,
+        party_size -> Nullable<Int4>,
+        reservation_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    reservations (id) {
+        id -> Int4,
+        name -> Text,
+        phone -> Nullable<Text>,
+        party_size -> Int4,
+        starts_at -> Timestamptz,
+        ends_at -> Timestamptz,
+        table_number -> Nullable<Int4>,
+        status -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    table_moves (id) {
+        id -> Int4,
+        kind -> Text,
+        table_id -> Int4,
+        from_number -> Int4,
+        to_number -> Int4,
+        merged_table_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    dining_tables (id) {
+        id -> Int4,
+        table_number -> Int4,
+        seats -> Int4,
+        section -> Text,
+        active -> Bool,
+    }
+}
+
+diesel::table! {
+    bill_parts (id) {
+        id -> Int4,
+        bill_id -> Int4,
+        position -> Int4,
+        amount -> Int8,
+        order_ids -> Array<Int4>,
+        paid_at -> Nullable<Timestamptz>,
+        currency -> Text,
+    }
+}
+
+diesel::table! {
+    bills (id) {
+        id -> Int4,
+        table_id -> Int4,
+        subtotal -> Int8,
+        total -> Int8,
+        issued_at -> Timestamptz,
+        currency -> Text,
+    }
+}
+
+diesel::table! {
+    audit_entries (id) {
+        id -> Int4,
+        created_at -> Timestamptz,
+        actor_id -> Nullable<Int4>,
+        device -> Nullable<Text>,
+        action -> Text,
+        entity -> Text,
+        entity_id -> Int4,
+        before -> Nullable<Jsonb>,
+        after -> Nullable<Jsonb>,
+        trace_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    bill_lines (id) {
+        id -> Int4,
+        bill_id -> Int4,
+        kind -> Text,
+        description -> Text,
+        unit_price -> Int8,
+        quantity -> Int4,
+        amount -> Int8,
+        promotion_id -> Nullable<Int4>,
+        tax_rate_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    tax_rates (id) {
+        id -> Int4,
+        name -> Text,
+        basis_points -> Int4,
+        inclusive -> Bool,
+        service -> Nullable<Text>,
+        item_id -> Nullable<Int4>,
+        category_id -> Nullable<Int4>,
+        active -> Bool,
+    }
+}
+
+diesel::table! {
+    categories (id) {
+        id -> Int4,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    promotions (id) {
+        id -> Int4,
+        name -> Text,
+        kind -> Text,
+        percent -> Nullable<Int4>,
+        amount -> Nullable<Int8>,
+        buy_quantity -> Nullable<Int4>,
+        free_quantity -> Nullable<Int4>,
+        item_id -> Nullable<Int4>,
+        category_id -> Nullable<Int4>,
+        starts_at -> Nullable<Time>,
+        ends_at -> Nullable<Time>,
+        code -> Nullable<Text>,
+        active -> Bool,
+        currency -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    staff_members (id) {
+        id -> Int4,
+        name -> Text,
+        role -> Text,
+        secret_hash -> Text,
+        active -> Bool,
+        created_at -> Timestamptz,
+        failed_logins -> Int4,
+        locked_until -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    table_coupons (table_id, promotion_id) {
+        table_id -> Int4,
+        promotion_id -> Int4,
     }
 }
 
@@ -18,7 +202,82 @@ diesel::table! {
         id -> Int4,
         description -> Text,
         estimated_minutes -> Int4,
-        price -> Int4,
+        price -> Int8,
+        category_id -> Nullable<Int4>,
+        currency -> Text,
+        available -> Bool,
+        remaining -> Nullable<Int4>,
+        stocked -> Bool,
+        archived_at -> Nullable<Timestamptz>,
+        sold_out_by_stock -> Bool,
+    }
+}
+
+diesel::table! {
+    item_prices (id) {
+        id -> Int4,
+        item_id -> Int4,
+        price -> Int8,
+        currency -> Text,
+        effective_from -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    idempotency_keys (staff_id, route, key) {
+        key -> Text,
+        fingerprint -> Text,
+        status_code -> Nullable<Int2>,
+        body -> Nullable<Bytea>,
+        created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        staff_id -> Int4,
+        route -> Text,
+    }
+}
+
+diesel::table! {
+    ingredients (id) {
+        id -> Int4,
+        name -> Text,
+        unit -> Text,
+        stock -> Int4,
+        low_stock -> Int4,
+    }
+}
+
+diesel::table! {
+    recipe_ingredients (item_id, ingredient_id) {
+        item_id -> Int4,
+        ingredient_id -> Int4,
+        quantity -> Int4,
+    }
+}
+
+diesel::table! {
+    modifier_groups (id) {
+        id -> Int4,
+        item_id -> Int4,
+        name -> Text,
+        min_selections -> Int4,
+        max_selections -> Int4,
+    }
+}
+
+diesel::table! {
+    modifiers (id) {
+        id -> Int4,
+        group_id -> Int4,
+        name -> Text,
+        price_delta -> Int8,
+        currency -> Text,
+    }
+}
+
+diesel::table! {
+    order_modifiers (order_id, modifier_id) {
+        order_id -> Int4,
+        modifier_id -> Int4,
     }
 }
 
@@ -29,10 +288,90 @@ diesel::table! {
         quantity -> Int4,
         item_id -> Int4,
         table_id -> Int4,
+        status -> Text,
+        ticket_id -> Nullable<Int4>,
+        description -> Text,
+        unit_price -> Int8,
+        currency -> Text,
+    }
+}
+
+diesel::table! {
+    waitlist_entries (id) {
+        id -> Int4,
+        name -> Text,
+        party_size -> Int4,
+        contact -> Nullable<Text>,
+        status -> Text,
+        quoted_minutes -> Int4,
+        created_at -> Timestamptz,
+        left_at -> Nullable<Timestamptz>,
+        table_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    tickets (id) {
+        id -> Int4,
+        published_at -> Text,
+        table_id -> Int4,
     }
 }
 
+diesel::joinable!(bill_parts -> bills (bill_id));
+diesel::joinable!(bills -> tables (table_id));
+diesel::joinable!(bill_lines -> bills (bill_id));
+diesel::joinable!(bill_lines -> promotions (promotion_id));
+diesel::joinable!(bill_lines -> tax_rates (tax_rate_id));
+diesel::joinable!(items -> categories (category_id));
+diesel::joinable!(item_prices -> items (item_id));
 diesel::joinable!(orders -> tables (table_id));
+diesel::joinable!(modifier_groups -> items (item_id));
+diesel::joinable!(modifiers -> modifier_groups (group_id));
+diesel::joinable!(order_modifiers -> modifiers (modifier_id));
+diesel::joinable!(order_modifiers -> orders (order_id));
+diesel::joinable!(promotions -> categories (category_id));
+diesel::joinable!(promotions -> items (item_id));
+diesel::joinable!(recipe_ingredients -> ingredients (ingredient_id));
+diesel::joinable!(recipe_ingredients -> items (item_id));
+diesel::joinable!(table_coupons -> promotions (promotion_id));
+diesel::joinable!(table_coupons -> tables (table_id));
+diesel::joinable!(tax_rates -> categories (category_id));
+diesel::joinable!(tax_rates -> items (item_id));
+diesel::joinable!(payments -> bill_parts (bill_part_id));
+diesel::joinable!(payments -> tables (table_id));
 diesel::joinable!(orders -> items (item_id));
+diesel::joinable!(orders -> tickets (ticket_id));
+diesel::joinable!(tables -> reservations (reservation_id));
+diesel::joinable!(tickets -> tables (table_id));
+diesel::joinable!(waitlist_entries -> tables (table_id));
 
-diesel::allow_tables_to_appear_in_same_query!(tables, items, orders,);
+diesel::joinable!(audit_entries -> staff_members (actor_id));
+diesel::joinable!(idempotency_keys -> staff_members (staff_id));
+diesel::allow_tables_to_appear_in_same_query!(
+    audit_entries,
+    bill_parts,
+    bills,
+    bill_lines,
+    categories,
+    dining_tables,
+    idempotency_keys,
+    ingredients,
+    item_prices,
+    payments,
+    promotions,
+    recipe_ingredients,
+    reservations,
+    staff_members,
+    table_coupons,
+    table_moves,
+    tables,
+    tax_rates,
+    items,
+    modifier_groups,
+    modifiers,
+    order_modifiers,
+    orders,
+    tickets,
+    waitlist_entries,
+);
@@ -0,0 +1,204 @@
+//! Modifier
+use super::{item::Item, modifier_groups, modifiers};
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use crate::domain::money::{Currency, Money};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A choice offered with an item, e.g. its toppings.
+/// A group asking for at least one selection is required.
+#[derive(
+    Identifiable,
+    Selectable,
+    Queryable,
+    Associations,
+    Clone,
+    Debug,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(table_name = modifier_groups)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Item))]
+pub(crate) struct ModifierGroup {
+    pub(crate) id: i32,
+    pub(crate) item_id: i32,
+    pub(crate) name: String,
+    pub(crate) min_selections: i32,
+    pub(crate) max_selections: i32,
+}
+
+impl ModifierGroup {
+    pub(crate) fn required(&self) -> bool {
+        self.min_selections > 0
+    }
+}
+
+/// An option of a group, changing the price of the item by its delta.
+#[derive(
+    Identifiable,
+    Selectable,
+    Queryable,
+    Associations,
+    Clone,
+    Debug,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(table_name = modifiers)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(ModifierGroup, foreign_key = group_id))]
+pub(crate) struct Modifier {
+    pub(crate) id: i32,
+    pub(crate) group_id: i32,
+    pub(crate) name: String,
+    #[diesel(select_expression = (modifiers::price_delta, modifiers::currency))]
+    #[diesel(select_expression_type = (modifiers::price_delta, modifiers::currency))]
+    pub(crate) price_delta: Money,
+}
+
+fn invalid(error: String) -> ApiError {
+    ApiError::new(ErrorCode::InvalidModifiers, error)
+}
+
+/// Check the modifiers chosen for an item against its groups.
+/// Every modifier is offered by one of the groups and chosen once at most,
+/// and each group gets between its minimum and maximum selections.
+pub(crate) fn validate(groups: &[(ModifierGroup, Vec<Modifier>)], chosen: &[i32]) -> ServerResult {
+    for (i, id) in chosen.iter().enumerate() {
+        if chosen[..i].contains(id) {
+            return Err(invalid(format!("Modifier {} is chosen twice!", id)));
+        }
+        if !groups
+            .iter()
+            .any(|(_, modifiers)| modifiers.iter().any(|modifier| modifier.id == *id))
+        {
+            return Err(invalid(format!(
+                "Modifier {} is not offered with this item!",
+                id
+            )));
+        }
+    }
+    for (group, modifiers) in groups {
+        let selected = modifiers
+            .iter()
+            .filter(|modifier| chosen.contains(&modifier.id))
+            .count() as i64;
+        if selected < i64::from(group.min_selections) || selected > i64::from(group.max_selections)
+        {
+            return Err(invalid(format!(
+                "Choose between {} and {} of {}!",
+                group.min_selections, group.max_selections, group.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// An item with the modifiers chosen for it, e.g. "Ramen (extra egg, no scallions)".
+/// The price deltas of the modifiers are added to its price.
+pub(crate) fn modified(
+    description: String,
+    price: Money,
+    chosen: &[(String, Money)],
+) -> ServerResult<(String, Money)> {
+    if chosen.is_empty() {
+        return Ok((description, price));
+    }
+    let names: Vec<&str> = chosen.iter().map(|(name, _)| name.as_str()).collect();
+    let price = chosen
+        .iter()
+        .try_fold(price, |price, (_, delta)| price.checked_add(delta))?;
+    Ok((format!("{} ({})", description, names.join(", ")), price))
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = modifier_groups)]
+pub struct NewModifierGroup<'a> {
+    pub(crate) item_id: &'a i32,
+    pub(crate) name: &'a String,
+    pub(crate) min_selections: &'a i32,
+    pub(crate) max_selections: &'a i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = modifiers)]
+pub(crate) struct NewModifier<'a> {
+    pub(crate) group_id: &'a i32,
+    pub(crate) name: &'a String,
+    pub(crate) price_delta: &'a i64,
+    pub(crate) currency: Currency,
+}
+
+/// A modifier to be offered by a new group, free unless it has a price delta.
+#[derive(Debug)]
+pub(crate) struct NewModifierEntry {
+    pub(crate) name: String,
+    pub(crate) price_delta: Option<Money>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur(amount: i64) -> Money {
+        Money::new(amount, Currency::Eur)
+    }
+
+    fn group(id: i32, min_selections: i32, max_selections: i32) -> (ModifierGroup, Vec<Modifier>) {
+        let modifiers = (1..=3)
+            .map(|i| Modifier {
+                id: id * 10 + i,
+                group_id: id,
+                name: format!("Modifier {}", id * 10 + i),
+                price_delta: eur(i64::from(i)),
+            })
+            .collect();
+        let group = ModifierGroup {
+            id,
+            item_id: 1,
+            name: format!("Group {}", id),
+            min_selections,
+            max_selections,
+        };
+        (group, modifiers)
+    }
+
+    #[test]
+    fn test_validate() {
+        let groups = [group(1, 1, 1), group(2, 0, 2)];
+        assert!(groups[0].0.required() && !groups[1].0.required());
+        assert!(validate(&groups, &[11]).is_ok());
+        assert!(validate(&groups, &[12, 21, 23]).is_ok());
+        // The required group is left out, or gets too much.
+        assert!(validate(&groups, &[]).is_err());
+        assert!(validate(&groups, &[11, 12]).is_err());
+        // The optional group gets too much, or a modifier twice.
+        assert!(validate(&groups, &[11, 21, 22, 23]).is_err());
+        assert!(validate(&groups, &[11, 21, 21]).is_err());
+        // A modifier of another item.
+        assert!(validate(&groups, &[11, 31]).is_err());
+        assert!(validate(&[], &[]).is_ok());
+    }
+
+    #[test]
+    fn test_modified() {
+        let chosen = [
+            ("extra egg".to_string(), eur(100)),
+            ("no scallions".to_string(), eur(0)),
+        ];
+        assert_eq!(
+            modified("Ramen".to_string(), eur(1200), &chosen).unwrap(),
+            ("Ramen (extra egg, no scallions)".to_string(), eur(1300))
+        );
+        assert_eq!(
+            modified("Ramen".to_string(), eur(1200), &[]).unwrap(),
+            ("Ramen".to_string(), eur(1200))
+        );
+        let dollars = [("extra egg".to_string(), Money::new(100, Currency::Usd))];
+        assert!(modified("Ramen".to_string(), eur(1200), &dollars).is_err());
+    }
+}
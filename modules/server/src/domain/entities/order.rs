@@ -1,41 +1,240 @@
 //! Order
 
-use super::{item::Item, orders, table::Table};
+use std::fmt;
+use std::str::FromStr;
+
+use super::{item::Item, orders, table::Table, ticket::Ticket};
+use crate::domain::money::{Currency, Money};
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
 use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+/// Lifecycle of an order, from the moment it is taken until it is served (or cancelled).
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OrderStatus {
+    Pending,
+    Cooking,
+    Ready,
+    Served,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub(crate) const ALL: [OrderStatus; 5] = [
+        OrderStatus::Pending,
+        OrderStatus::Cooking,
+        OrderStatus::Ready,
+        OrderStatus::Served,
+        OrderStatus::Cancelled,
+    ];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Cooking => "cooking",
+            OrderStatus::Ready => "ready",
+            OrderStatus::Served => "served",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether an order may move from this status to `next`.
+    /// Orders only move forward, and can be cancelled until they are served.
+    pub(crate) fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        matches!(
+            (self, next),
+            (OrderStatus::Pending, OrderStatus::Cooking)
+                | (OrderStatus::Cooking, OrderStatus::Ready)
+                | (OrderStatus::Ready, OrderStatus::Served)
+                | (
+                    OrderStatus::Pending | OrderStatus::Cooking | OrderStatus::Ready,
+                    OrderStatus::Cancelled
+                )
+        )
+    }
+
+    /// Whether the kitchen started on the order.
+    pub(crate) fn is_started(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Cooking | OrderStatus::Ready | OrderStatus::Served
+        )
+    }
+
+    /// Every status an order may be in to move to `next`.
+    pub(crate) fn predecessors(next: &OrderStatus) -> Vec<OrderStatus> {
+        OrderStatus::ALL
+            .into_iter()
+            .filter(|status| status.can_transition_to(next))
+            .collect()
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(OrderStatus::Pending),
+            "cooking" => Ok(OrderStatus::Cooking),
+            "ready" => Ok(OrderStatus::Ready),
+            "served" => Ok(OrderStatus::Served),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            other => Err(format!("Unknown order status {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for OrderStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for OrderStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let status = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(status.parse()?)
+    }
+}
+
 #[derive(
-    Identifiable, Selectable, Queryable, Associations, Debug, Deserialize, Serialize, ToSchema,
+    Identifiable,
+    Selectable,
+    Queryable,
+    Associations,
+    Clone,
+    Debug,
+    Deserialize,
+    Serialize,
+    ToSchema,
 )]
 #[diesel(table_name = orders)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[diesel(belongs_to(Item))]
 #[diesel(belongs_to(Table))]
+#[diesel(belongs_to(Ticket))]
 #[diesel(primary_key(table_id, item_id))]
 pub(crate) struct Order {
     pub(crate) id: i32,
     pub(crate) published_at: String,
-    // This should probably be a list instead.
-    // then I could add orders as:
-    // {
-    //  "table_id": 1,
-    //  "items": [
-    //    { "item_id": 101, "quantity": 2 },
-    //    { "item_id": 102, "quantity": 5 }
-    //  ]
-    //}
     pub(crate) quantity: i32,
     pub(crate) item_id: i32,
     #[serde(skip_serializing)]
     pub(crate) table_id: i32,
+    pub(crate) status: OrderStatus,
+    pub(crate) ticket_id: Option<i32>,
+    /// Description of the item when it was ordered.
+    pub(crate) description: String,
+    /// Price of the item when it was ordered, later price changes don't apply.
+    #[diesel(select_expression = (orders::unit_price, orders::currency))]
+    #[diesel(select_expression_type = (orders::unit_price, orders::currency))]
+    pub(crate) unit_price: Money,
+}
+
+/// A single order to be placed for a table, identified by its table number.
+#[derive(Debug)]
+pub(crate) struct NewOrderLine {
+    pub(crate) table_number: i32,
+    pub(crate) item_id: i32,
+    pub(crate) quantity: i32,
+    pub(crate) modifiers: Vec<i32>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = orders)]
-pub struct NewOrder<'a> {
+pub(crate) struct NewOrder<'a> {
     pub(crate) item_id: &'a i32,
     pub(crate) table_id: &'a i32,
     pub(crate) published_at: &'a String,
     pub(crate) quantity: &'a i32,
+    pub(crate) ticket_id: Option<&'a i32>,
+    pub(crate) description: &'a String,
+    pub(crate) unit_price: &'a i64,
+    pub(crate) currency: Currency,
+}
+
+/// Fields orders can be sorted by, by id is the order they were taken in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OrderSort {
+    Id,
+    ItemId,
+    Quantity,
+}
+
+impl OrderSort {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            OrderSort::Id => "id",
+            OrderSort::ItemId => "item_id",
+            OrderSort::Quantity => "quantity",
+        }
+    }
+}
+
+impl fmt::Display for OrderSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(OrderSort::Id),
+            "item_id" => Ok(OrderSort::ItemId),
+            "quantity" => Ok(OrderSort::Quantity),
+            other => Err(format!("Unable to sort by {:?}", other)),
+        }
+    }
+}
+
+/// Which orders to find, everything that is not set matches.
+#[derive(Debug, Default)]
+pub(crate) struct OrderFilter<'a> {
+    /// Statuses to include, every status when empty.
+    pub(crate) statuses: &'a [OrderStatus],
+    /// Orders of any session seated at the table.
+    pub(crate) table_number: Option<&'a i32>,
+    pub(crate) item_id: Option<&'a i32>,
+    /// Orders taken from this time on.
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Orders taken before this time.
+    pub(crate) until: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderStatus;
+
+    #[test]
+    fn test_order_status_transitions() {
+        assert!(OrderStatus::Pending.can_transition_to(&OrderStatus::Cooking));
+        assert!(OrderStatus::Cooking.can_transition_to(&OrderStatus::Ready));
+        assert!(OrderStatus::Ready.can_transition_to(&OrderStatus::Served));
+        assert!(OrderStatus::Cooking.can_transition_to(&OrderStatus::Cancelled));
+
+        assert!(!OrderStatus::Pending.can_transition_to(&OrderStatus::Served));
+        assert!(!OrderStatus::Ready.can_transition_to(&OrderStatus::Cooking));
+        assert!(!OrderStatus::Served.can_transition_to(&OrderStatus::Cancelled));
+        assert!(!OrderStatus::Cancelled.can_transition_to(&OrderStatus::Pending));
+    }
 }
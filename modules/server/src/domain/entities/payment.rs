@@ -0,0 +1,150 @@
+//! Payment
+use std::fmt;
+use std::str::FromStr;
+
+use super::{payments, table::Table};
+use crate::domain::error::ServerResult;
+use crate::domain::money::{Currency, Money};
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How a payment was made.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Tender {
+    Cash,
+    Card,
+    Voucher,
+}
+
+impl Tender {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Tender::Cash => "cash",
+            Tender::Card => "card",
+            Tender::Voucher => "voucher",
+        }
+    }
+}
+
+impl fmt::Display for Tender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Tender {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cash" => Ok(Tender::Cash),
+            "card" => Ok(Tender::Card),
+            "voucher" => Ok(Tender::Voucher),
+            other => Err(format!("Unknown tender {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for Tender {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for Tender {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let tender = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(tender.parse()?)
+    }
+}
+
+/// Money received for a table session.
+/// `amount` goes towards the bill, the `tip` does not.
+#[derive(
+    Identifiable,
+    Selectable,
+    Queryable,
+    Associations,
+    Clone,
+    Debug,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(table_name = payments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Table))]
+pub(crate) struct Payment {
+    pub(crate) id: i32,
+    #[serde(skip_serializing)]
+    pub(crate) table_id: i32,
+    pub(crate) bill_part_id: Option<i32>,
+    pub(crate) tender: Tender,
+    #[diesel(select_expression = (payments::amount, payments::currency))]
+    #[diesel(select_expression_type = (payments::amount, payments::currency))]
+    pub(crate) amount: Money,
+    #[diesel(select_expression = (payments::tip, payments::currency))]
+    #[diesel(select_expression_type = (payments::tip, payments::currency))]
+    pub(crate) tip: Money,
+    pub(crate) reference: Option<String>,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) voided_at: Option<DateTime<Utc>>,
+}
+
+impl Payment {
+    /// Paid towards the bill, a voided payment counts for nothing.
+    pub(crate) fn paid(payments: &[Payment], currency: Currency) -> ServerResult<Money> {
+        Money::sum(
+            currency,
+            payments
+                .iter()
+                .filter(|payment| payment.voided_at.is_none())
+                .map(|payment| &payment.amount),
+        )
+    }
+
+    /// Tips on top of the bill.
+    pub(crate) fn tips(payments: &[Payment], currency: Currency) -> ServerResult<Money> {
+        Money::sum(
+            currency,
+            payments
+                .iter()
+                .filter(|payment| payment.voided_at.is_none())
+                .map(|payment| &payment.tip),
+        )
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = payments)]
+pub(crate) struct NewPayment<'a> {
+    pub(crate) table_id: &'a i32,
+    pub(crate) bill_part_id: Option<&'a i32>,
+    pub(crate) tender: Tender,
+    pub(crate) amount: &'a i64,
+    pub(crate) tip: &'a i64,
+    pub(crate) currency: Currency,
+    pub(crate) reference: Option<&'a String>,
+}
+
+/// A payment to be recorded for a table.
+#[derive(Debug)]
+pub(crate) struct NewPaymentEntry {
+    pub(crate) tender: Tender,
+    pub(crate) amount: Money,
+    /// Nothing when not given.
+    pub(crate) tip: Option<Money>,
+    pub(crate) reference: Option<String>,
+    pub(crate) bill_part_id: Option<i32>,
+}
@@ -0,0 +1,280 @@
+//! Promotion
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{
+    bill::{BillDiscount, BilledOrder},
+    promotions,
+};
+use crate::domain::error::ServerResult;
+use crate::domain::money::{Currency, MaybeMoney, Money, Rounding};
+use chrono::NaiveTime;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How a promotion discounts the lines it applies to.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PromotionKind {
+    /// `percent` off the price.
+    Percentage,
+    /// `amount` off the bill, at most what the lines cost.
+    FixedAmount,
+    /// For every `buy_quantity` of an item, `free_quantity` more are free.
+    BuyGetFree,
+}
+
+impl PromotionKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            PromotionKind::Percentage => "percentage",
+            PromotionKind::FixedAmount => "fixed_amount",
+            PromotionKind::BuyGetFree => "buy_get_free",
+        }
+    }
+}
+
+impl fmt::Display for PromotionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PromotionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "percentage" => Ok(PromotionKind::Percentage),
+            "fixed_amount" => Ok(PromotionKind::FixedAmount),
+            "buy_get_free" => Ok(PromotionKind::BuyGetFree),
+            other => Err(format!("Unknown promotion kind {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for PromotionKind {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for PromotionKind {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let kind = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(kind.parse()?)
+    }
+}
+
+/// A discount rule.
+/// It applies to the lines of the given item or category, ordered within the time window.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = promotions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct Promotion {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) kind: PromotionKind,
+    pub(crate) percent: Option<i32>,
+    #[diesel(select_expression = (promotions::amount, promotions::currency))]
+    #[diesel(select_expression_type = (promotions::amount, promotions::currency))]
+    #[diesel(deserialize_as = MaybeMoney)]
+    pub(crate) amount: Option<Money>,
+    pub(crate) buy_quantity: Option<i32>,
+    pub(crate) free_quantity: Option<i32>,
+    pub(crate) item_id: Option<i32>,
+    pub(crate) category_id: Option<i32>,
+    pub(crate) starts_at: Option<NaiveTime>,
+    pub(crate) ends_at: Option<NaiveTime>,
+    pub(crate) code: Option<String>,
+    pub(crate) active: bool,
+}
+
+impl Promotion {
+    /// Whether the time window contains `at`, a window may wrap around midnight.
+    fn in_window(&self, at: NaiveTime) -> bool {
+        match (self.starts_at, self.ends_at) {
+            (Some(starts), Some(ends)) if starts <= ends => starts <= at && at < ends,
+            (Some(starts), Some(ends)) => at >= starts || at < ends,
+            _ => true,
+        }
+    }
+
+    fn applies_to(&self, order: &BilledOrder) -> bool {
+        self.item_id.is_none_or(|item| item == order.item_id)
+            && self
+                .category_id
+                .is_none_or(|category| Some(category) == order.category_id)
+            && self.in_window(order.ordered_at)
+    }
+
+    /// What the promotion takes off the given orders, billed in `currency`.
+    /// Percentages are rounded down, a discount never takes off more than was promised.
+    pub(crate) fn discount(
+        &self,
+        orders: &[BilledOrder],
+        currency: Currency,
+    ) -> ServerResult<Money> {
+        let eligible: Vec<&BilledOrder> = orders.iter().filter(|o| self.applies_to(o)).collect();
+        let cost = Money::sum(currency, eligible.iter().map(|o| &o.item.line_total))?;
+        let discount = match self.kind {
+            PromotionKind::Percentage => {
+                cost.ratio(i64::from(self.percent.unwrap_or(0)), 100, Rounding::Down)?
+            }
+            PromotionKind::FixedAmount => match self.amount {
+                Some(amount) if amount.currency == currency && amount.amount < cost.amount => {
+                    amount
+                }
+                Some(amount) if amount.currency == currency => cost,
+                // A coupon in another currency takes nothing off.
+                _ => Money::zero(currency),
+            },
+            PromotionKind::BuyGetFree => {
+                let buy = i64::from(self.buy_quantity.unwrap_or(0));
+                let free = i64::from(self.free_quantity.unwrap_or(0));
+                let mut units: BTreeMap<(i32, i64), (Money, i64)> = BTreeMap::new();
+                for order in eligible {
+                    let price = order.item.unit_price;
+                    units
+                        .entry((order.item_id, price.amount))
+                        .or_insert((price, 0))
+                        .1 += i64::from(order.item.quantity);
+                }
+                let free_items = units
+                    .into_values()
+                    .map(|(price, units)| price.checked_mul(units / (buy + free).max(1) * free))
+                    .collect::<ServerResult<Vec<Money>>>()?;
+                Money::sum(currency, &free_items)?
+            }
+        };
+        Ok(if discount.is_negative() {
+            Money::zero(currency)
+        } else {
+            discount
+        })
+    }
+}
+
+/// Apply every promotion in turn, together they never take more than the orders cost.
+pub(crate) fn discounts(
+    promotions: &[Promotion],
+    orders: &[BilledOrder],
+    currency: Currency,
+) -> ServerResult<Vec<BillDiscount>> {
+    let mut left = Money::sum(currency, orders.iter().map(|order| &order.item.line_total))?;
+    let mut discounts = vec![];
+    for promotion in promotions {
+        let mut amount = promotion.discount(orders, currency)?;
+        if amount.amount > left.amount {
+            amount = left;
+        }
+        if !amount.is_positive() {
+            continue;
+        }
+        left = left.checked_sub(&amount)?;
+        discounts.push(BillDiscount {
+            promotion_id: promotion.id,
+            description: promotion.name.clone(),
+            amount: amount.checked_neg()?,
+        });
+    }
+    Ok(discounts)
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = promotions)]
+pub(crate) struct NewPromotion<'a> {
+    pub(crate) name: &'a String,
+    pub(crate) kind: PromotionKind,
+    pub(crate) percent: Option<&'a i32>,
+    pub(crate) amount: Option<&'a i64>,
+    pub(crate) currency: Option<Currency>,
+    pub(crate) buy_quantity: Option<&'a i32>,
+    pub(crate) free_quantity: Option<&'a i32>,
+    pub(crate) item_id: Option<&'a i32>,
+    pub(crate) category_id: Option<&'a i32>,
+    pub(crate) starts_at: Option<&'a NaiveTime>,
+    pub(crate) ends_at: Option<&'a NaiveTime>,
+    pub(crate) code: Option<&'a String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::bill::BillItem;
+
+    fn order(item_id: i32, price: i64, quantity: i32, at: (u32, u32)) -> BilledOrder {
+        BilledOrder {
+            order_id: item_id,
+            item_id,
+            category_id: Some(item_id % 2),
+            ordered_at: NaiveTime::from_hms_opt(at.0, at.1, 0).unwrap(),
+            item: BillItem::new(
+                format!("Item {}", item_id),
+                Money::new(price, Currency::Eur),
+                quantity,
+            )
+            .unwrap(),
+        }
+    }
+
+    fn promotion(kind: PromotionKind) -> Promotion {
+        Promotion {
+            id: 1,
+            name: "Promotion".to_string(),
+            kind,
+            percent: Some(20),
+            amount: Some(Money::new(15, Currency::Eur)),
+            buy_quantity: Some(2),
+            free_quantity: Some(1),
+            item_id: None,
+            category_id: None,
+            starts_at: None,
+            ends_at: None,
+            code: None,
+            active: true,
+        }
+    }
+
+    #[test]
+    fn test_promotion_discounts() {
+        let orders = [order(1, 10, 5, (17, 30)), order(2, 4, 1, (20, 0))];
+        let discount = |promotion: &Promotion| promotion.discount(&orders, Currency::Eur).unwrap();
+        let mut happy_hour = promotion(PromotionKind::Percentage);
+        happy_hour.starts_at = NaiveTime::from_hms_opt(17, 0, 0);
+        happy_hour.ends_at = NaiveTime::from_hms_opt(19, 0, 0);
+        assert_eq!(discount(&happy_hour).amount, 10);
+        happy_hour.starts_at = NaiveTime::from_hms_opt(19, 30, 0);
+        happy_hour.ends_at = NaiveTime::from_hms_opt(2, 0, 0);
+        assert_eq!(discount(&happy_hour).amount, 0);
+        let mut by_category = promotion(PromotionKind::Percentage);
+        by_category.category_id = Some(1);
+        assert_eq!(discount(&by_category).amount, 10);
+        let three_for_two = promotion(PromotionKind::BuyGetFree);
+        assert_eq!(discount(&three_for_two).amount, 10);
+        let coupon = promotion(PromotionKind::FixedAmount);
+        assert_eq!(discount(&coupon).amount, 15);
+        let mut dollars = promotion(PromotionKind::FixedAmount);
+        dollars.amount = Some(Money::new(15, Currency::Usd));
+        assert_eq!(discount(&dollars).amount, 0);
+        // Together, the discounts stop at what was ordered.
+        let applied = discounts(
+            &[coupon.clone(), coupon.clone(), coupon.clone(), coupon],
+            &orders,
+            Currency::Eur,
+        )
+        .unwrap();
+        let amounts: Vec<i64> = applied.iter().map(|d| d.amount.amount).collect();
+        assert_eq!(amounts, vec![-15, -15, -15, -9]);
+    }
+}
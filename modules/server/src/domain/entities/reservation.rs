@@ -0,0 +1,119 @@
+//! Reservation
+use std::fmt;
+use std::str::FromStr;
+
+use super::reservations;
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Whether a booking still holds its table.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReservationStatus {
+    Booked,
+    Seated,
+    Cancelled,
+}
+
+impl ReservationStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Booked => "booked",
+            ReservationStatus::Seated => "seated",
+            ReservationStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReservationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "booked" => Ok(ReservationStatus::Booked),
+            "seated" => Ok(ReservationStatus::Seated),
+            "cancelled" => Ok(ReservationStatus::Cancelled),
+            other => Err(format!("Unknown reservation status {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for ReservationStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for ReservationStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let status = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(status.parse()?)
+    }
+}
+
+/// A booking of a party, from `starts_at` until `ends_at`.
+/// The table may be assigned later, at the latest when the party is seated.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = reservations)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct Reservation {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) phone: Option<String>,
+    pub(crate) party_size: i32,
+    pub(crate) starts_at: DateTime<Utc>,
+    pub(crate) ends_at: DateTime<Utc>,
+    pub(crate) table_number: Option<i32>,
+    pub(crate) status: ReservationStatus,
+    pub(crate) created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reservations)]
+pub(crate) struct NewReservation<'a> {
+    pub(crate) name: &'a String,
+    pub(crate) phone: Option<&'a String>,
+    pub(crate) party_size: &'a i32,
+    pub(crate) starts_at: &'a DateTime<Utc>,
+    pub(crate) ends_at: &'a DateTime<Utc>,
+    pub(crate) table_number: Option<&'a i32>,
+}
+
+/// Changes to a booking, only what is set is changed.
+#[derive(AsChangeset)]
+#[diesel(table_name = reservations)]
+pub(crate) struct ReservationChanges<'a> {
+    pub(crate) name: Option<&'a String>,
+    pub(crate) phone: Option<&'a String>,
+    pub(crate) party_size: Option<&'a i32>,
+    pub(crate) starts_at: Option<&'a DateTime<Utc>>,
+    pub(crate) ends_at: Option<&'a DateTime<Utc>>,
+    pub(crate) table_number: Option<&'a i32>,
+}
+
+impl ReservationChanges<'_> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.phone.is_none()
+            && self.party_size.is_none()
+            && self.starts_at.is_none()
+            && self.ends_at.is_none()
+            && self.table_number.is_none()
+    }
+}
@@ -0,0 +1,243 @@
+//! Staff
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use super::staff_members;
+use crate::application::config::MIN_SECRET_LENGTH;
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a staff member does, decides which parts of the API the member may use.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Role {
+    Waiter,
+    Kitchen,
+    Manager,
+    Admin,
+}
+
+impl Role {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Role::Waiter => "waiter",
+            Role::Kitchen => "kitchen",
+            Role::Manager => "manager",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Whether the role may overrule the floor, e.g. delete orders the kitchen started.
+    pub(crate) fn manages(&self) -> bool {
+        matches!(self, Role::Manager | Role::Admin)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "waiter" => Ok(Role::Waiter),
+            "kitchen" => Ok(Role::Kitchen),
+            "manager" => Ok(Role::Manager),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for Role {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for Role {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let role = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(role.parse()?)
+    }
+}
+
+/// Roles allowed to use a group of routes, reading is usually allowed to more roles than writing.
+/// Admins may use every group.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Access {
+    read: &'static [Role],
+    write: &'static [Role],
+}
+
+impl Access {
+    /// Orders, everyone takes part in getting them out.
+    pub(crate) const SERVICE: Access = Access {
+        read: &[Role::Waiter, Role::Kitchen, Role::Manager],
+        write: &[Role::Waiter, Role::Kitchen, Role::Manager],
+    };
+    /// Table sessions, reservations and the waitlist, run by the waiters.
+    pub(crate) const FLOOR: Access = Access {
+        read: &[Role::Waiter, Role::Kitchen, Role::Manager],
+        write: &[Role::Waiter, Role::Manager],
+    };
+    /// Ingredient stock, kept by the kitchen.
+    pub(crate) const STOCK: Access = Access {
+        read: &[Role::Waiter, Role::Kitchen, Role::Manager],
+        write: &[Role::Kitchen, Role::Manager],
+    };
+    /// Menu, prices, taxes and the floor plan, set up by managers.
+    pub(crate) const SETUP: Access = Access {
+        read: &[Role::Waiter, Role::Kitchen, Role::Manager],
+        write: &[Role::Manager],
+    };
+    /// The audit log, read by managers and written by the server only.
+    pub(crate) const AUDIT: Access = Access {
+        read: &[Role::Manager],
+        write: &[],
+    };
+    /// Staff accounts.
+    pub(crate) const STAFF: Access = Access {
+        read: &[],
+        write: &[],
+    };
+
+    /// Whether `role` may read, or change something when `write` is set.
+    pub(crate) fn allows(&self, role: &Role, write: bool) -> bool {
+        let roles = if write { self.write } else { self.read };
+        *role == Role::Admin || roles.contains(role)
+    }
+}
+
+/// A staff account, the PIN or password is never handed out.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = staff_members)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct Staff {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) role: Role,
+    /// Whether the member can log in.
+    pub(crate) active: bool,
+    pub(crate) created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = staff_members)]
+pub(crate) struct NewStaff<'a> {
+    pub(crate) name: &'a String,
+    pub(crate) role: Role,
+    pub(crate) secret_hash: String,
+}
+
+/// Changes to a staff account, only what is set is changed.
+#[derive(AsChangeset)]
+#[diesel(table_name = staff_members)]
+pub(crate) struct StaffChanges {
+    pub(crate) role: Option<Role>,
+    pub(crate) active: Option<bool>,
+    pub(crate) secret_hash: Option<String>,
+}
+
+impl StaffChanges {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.role.is_none() && self.active.is_none() && self.secret_hash.is_none()
+    }
+}
+
+/// What a signed token says about the staff member it was issued to.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Claims {
+    /// Id of the staff member.
+    pub(crate) sub: i32,
+    pub(crate) role: Role,
+    /// Expiry as a unix timestamp.
+    pub(crate) exp: i64,
+}
+
+/// A login, the token is sent as bearer token with every request until it expires.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct Session {
+    pub(crate) token: String,
+    pub(crate) expires_at: DateTime<Utc>,
+    pub(crate) staff: Staff,
+}
+
+/// Hash a PIN or password to be kept, it is salted so equal secrets hash differently.
+pub(crate) fn hash_secret(secret: &str) -> ServerResult<String> {
+    if secret.chars().count() < MIN_SECRET_LENGTH {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!(
+                "A PIN or password needs at least {} characters!",
+                MIN_SECRET_LENGTH
+            ),
+        ));
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| ApiError::new(ErrorCode::Internal, "Unable to hash secret!"))
+}
+
+/// Whether `secret` is the PIN or password `hash` was made from.
+pub(crate) fn verify_secret(secret: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .and_then(|hash| Argon2::default().verify_password(secret.as_bytes(), &hash))
+        .is_ok()
+}
+
+/// Verify `secret` when there is no account to verify it against, so logging in with an
+/// unknown name takes as long as with a known one.
+pub(crate) fn reject_secret(secret: &str) {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = DUMMY_HASH.get_or_init(|| hash_secret("no such account").unwrap_or_default());
+    verify_secret(secret, hash);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_by_role() {
+        assert!(Access::SERVICE.allows(&Role::Kitchen, true));
+        assert!(Access::FLOOR.allows(&Role::Kitchen, false));
+        assert!(!Access::FLOOR.allows(&Role::Kitchen, true));
+        assert!(!Access::SETUP.allows(&Role::Waiter, true));
+        assert!(!Access::STAFF.allows(&Role::Manager, false));
+        assert!(Access::STAFF.allows(&Role::Admin, true));
+    }
+
+    #[test]
+    fn secrets_are_hashed() {
+        let hash = hash_secret("4711").unwrap();
+        assert_ne!(hash, "4711");
+        assert!(verify_secret("4711", &hash));
+        assert!(!verify_secret("4712", &hash));
+        assert!(!verify_secret("4711", "not a hash"));
+        assert_eq!(
+            hash_secret("47").unwrap_err().code,
+            ErrorCode::InvalidRequest
+        );
+    }
+}
@@ -1,25 +1,215 @@
 //! Table
+use std::fmt;
+use std::str::FromStr;
+
 use super::tables;
+use crate::domain::money::{Currency, MaybeMoney, Money};
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
 use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+
+/// Whether a party is still seated at the table.
+/// A table that is settling a split bill is still seated, but takes no more orders.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SessionStatus {
+    Open,
+    Billing,
+    Closed,
+}
+
+impl SessionStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            SessionStatus::Open => "open",
+            SessionStatus::Billing => "billing",
+            SessionStatus::Closed => "closed",
+        }
+    }
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SessionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(SessionStatus::Open),
+            "billing" => Ok(SessionStatus::Billing),
+            "closed" => Ok(SessionStatus::Closed),
+            other => Err(format!("Unknown session status {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for SessionStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for SessionStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let status = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(status.parse()?)
+    }
+}
+
+/// How the party of a session is served, taxes may differ between the two.
 #[derive(
-    Identifiable, Selectable, Queryable, Debug, Deserialize, Serialize, PartialEq, ToSchema,
+    AsExpression,
+    FromSqlRow,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ServiceType {
+    #[default]
+    DineIn,
+    Takeout,
+}
+
+impl ServiceType {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ServiceType::DineIn => "dine_in",
+            ServiceType::Takeout => "takeout",
+        }
+    }
+}
+
+impl fmt::Display for ServiceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ServiceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dine_in" => Ok(ServiceType::DineIn),
+            "takeout" => Ok(ServiceType::Takeout),
+            other => Err(format!("Unknown service type {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for ServiceType {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for ServiceType {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let service = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(service.parse()?)
+    }
+}
+
+/// A table session, from check-in until the party checks out.
+/// The total is only known once the session is closed.
+#[derive(
+    Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema,
 )]
 #[diesel(table_name = tables)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub(crate) struct Table {
     #[serde(skip_serializing)]
     pub(crate) id: i32,
-    pub(crate) checked_in_time: String,
     pub(crate) table_number: i32,
-    pub(crate) total: i32,
+    #[diesel(select_expression = (tables::total, tables::currency))]
+    #[diesel(select_expression_type = (tables::total, tables::currency))]
+    #[diesel(deserialize_as = MaybeMoney)]
+    pub(crate) total: Option<Money>,
+    pub(crate) opened_at: DateTime<Utc>,
+    pub(crate) closed_at: Option<DateTime<Utc>>,
+    pub(crate) status: SessionStatus,
+    pub(crate) service: ServiceType,
+    pub(crate) currency: Currency,
+    /// Number of guests seated, when it was given at check-in.
+    pub(crate) party_size: Option<i32>,
+    /// Reservation the party was seated for.
+    pub(crate) reservation_id: Option<i32>,
 }
 
+/// Opens a new session, the database decides when it was opened.
 #[derive(Insertable)]
 #[diesel(table_name = tables)]
-pub struct NewTable<'a> {
-    pub(crate) checked_in_time: &'a String,
-    pub(crate) total: &'a i32,
+pub(crate) struct NewTable<'a> {
     pub(crate) table_number: &'a i32,
+    pub(crate) service: ServiceType,
+    pub(crate) currency: Currency,
+    pub(crate) party_size: Option<&'a i32>,
+    pub(crate) reservation_id: Option<&'a i32>,
+}
+
+/// Fields table sessions can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TableSort {
+    OpenedAt,
+    TableNumber,
+}
+
+impl TableSort {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TableSort::OpenedAt => "opened_at",
+            TableSort::TableNumber => "table_number",
+        }
+    }
+}
+
+impl fmt::Display for TableSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TableSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opened_at" => Ok(TableSort::OpenedAt),
+            "table_number" => Ok(TableSort::TableNumber),
+            other => Err(format!("Unable to sort by {:?}", other)),
+        }
+    }
+}
+
+/// Which table sessions to find, everything that is not set matches.
+#[derive(Debug, Default)]
+pub(crate) struct TableFilter<'a> {
+    pub(crate) table_number: Option<&'a i32>,
+    /// Statuses to include, every status when empty.
+    pub(crate) statuses: &'a [SessionStatus],
+    /// Sessions opened from this time on.
+    pub(crate) from: Option<DateTime<Utc>>,
+    /// Sessions opened before this time.
+    pub(crate) until: Option<DateTime<Utc>>,
 }
@@ -0,0 +1,91 @@
+//! Table move
+use std::fmt;
+use std::str::FromStr;
+
+use super::table_moves;
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How a party moved tables.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MoveKind {
+    /// The party moved to another table.
+    Transfer,
+    /// Another party joined the table, their orders are billed together.
+    Merge,
+}
+
+impl MoveKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            MoveKind::Transfer => "transfer",
+            MoveKind::Merge => "merge",
+        }
+    }
+}
+
+impl fmt::Display for MoveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MoveKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "transfer" => Ok(MoveKind::Transfer),
+            "merge" => Ok(MoveKind::Merge),
+            other => Err(format!("Unknown table move {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for MoveKind {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for MoveKind {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let kind = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(kind.parse()?)
+    }
+}
+
+/// A move of a table session, from one table number to another.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = table_moves)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct TableMove {
+    pub(crate) id: i32,
+    pub(crate) kind: MoveKind,
+    pub(crate) from_number: i32,
+    pub(crate) to_number: i32,
+    pub(crate) created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = table_moves)]
+pub(crate) struct NewTableMove {
+    pub(crate) kind: MoveKind,
+    /// Session that moved, or that the other one was merged into.
+    pub(crate) table_id: i32,
+    pub(crate) from_number: i32,
+    pub(crate) to_number: i32,
+    /// Session that was closed by a merge.
+    pub(crate) merged_table_id: Option<i32>,
+}
@@ -0,0 +1,179 @@
+//! Tax
+use std::collections::BTreeMap;
+
+use super::{
+    bill::{BillTax, BilledOrder},
+    table::ServiceType,
+    tax_rates,
+};
+use crate::domain::error::ServerResult;
+use crate::domain::money::{Currency, Money, Rounding};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A tax on the items of a category, or on a single item.
+/// The taxes of an item replace the taxes of its category.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = tax_rates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct TaxRate {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    /// Hundredths of a percent, 1250 is 12.5%.
+    pub(crate) basis_points: i32,
+    /// Whether prices already include the tax.
+    pub(crate) inclusive: bool,
+    /// Only tax this kind of service, both when not set.
+    pub(crate) service: Option<ServiceType>,
+    pub(crate) item_id: Option<i32>,
+    pub(crate) category_id: Option<i32>,
+    pub(crate) active: bool,
+}
+
+impl TaxRate {
+    fn serves(&self, service: ServiceType) -> bool {
+        self.service.is_none_or(|only| only == service)
+    }
+
+    /// The tax on a taxable amount, or the part of it that is tax when prices include it.
+    /// Rounded half up to a minor unit.
+    pub(crate) fn tax(&self, taxable: &Money) -> ServerResult<Money> {
+        let basis_points = i64::from(self.basis_points);
+        if self.inclusive {
+            taxable.checked_sub(&taxable.ratio(10_000, 10_000 + basis_points, Rounding::HalfUp)?)
+        } else {
+            taxable.basis_points(basis_points, Rounding::HalfUp)
+        }
+    }
+}
+
+/// Tax the orders of a session billed in `currency`, every tax on its own.
+/// `discount` is what promotions took off the bill, it lowers every taxable amount in proportion.
+pub(crate) fn taxes(
+    rates: &[TaxRate],
+    orders: &[BilledOrder],
+    service: ServiceType,
+    currency: Currency,
+    discount: &Money,
+) -> ServerResult<Vec<BillTax>> {
+    let mut taxable: BTreeMap<i32, Money> = BTreeMap::new();
+    for order in orders {
+        let by_item: Vec<&TaxRate> = rates
+            .iter()
+            .filter(|rate| rate.serves(service) && rate.item_id == Some(order.item_id))
+            .collect();
+        let applied = if by_item.is_empty() {
+            rates
+                .iter()
+                .filter(|rate| {
+                    rate.serves(service)
+                        && rate.category_id.is_some()
+                        && rate.category_id == order.category_id
+                })
+                .collect()
+        } else {
+            by_item
+        };
+        for rate in applied {
+            let sum = taxable.entry(rate.id).or_insert(Money::zero(currency));
+            *sum = sum.checked_add(&order.item.line_total)?;
+        }
+    }
+    let subtotal = Money::sum(currency, orders.iter().map(|order| &order.item.line_total))?;
+    let paid = subtotal.checked_add(discount)?.amount.max(0);
+    rates
+        .iter()
+        .filter_map(|rate| Some((rate, taxable.get(&rate.id)?)))
+        .map(|(rate, gross)| {
+            // Spreading the discount rounds halves to even, so it does not drift one way.
+            let net = gross.ratio(paid, subtotal.amount.max(1), Rounding::HalfEven)?;
+            let amount = rate.tax(&net)?;
+            Ok(BillTax {
+                tax_rate_id: rate.id,
+                description: rate.name.clone(),
+                inclusive: rate.inclusive,
+                amount,
+            })
+        })
+        .collect()
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = tax_rates)]
+pub(crate) struct NewTaxRate<'a> {
+    pub(crate) name: &'a String,
+    pub(crate) basis_points: &'a i32,
+    pub(crate) inclusive: &'a bool,
+    pub(crate) service: Option<ServiceType>,
+    pub(crate) item_id: Option<&'a i32>,
+    pub(crate) category_id: Option<&'a i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::bill::BillItem;
+    use chrono::NaiveTime;
+
+    fn order(item_id: i32, category_id: i32, price: i64) -> BilledOrder {
+        BilledOrder {
+            order_id: item_id,
+            item_id,
+            category_id: Some(category_id),
+            ordered_at: NaiveTime::MIN,
+            item: BillItem::new(
+                format!("Item {}", item_id),
+                Money::new(price, Currency::Eur),
+                1,
+            )
+            .unwrap(),
+        }
+    }
+
+    fn rate(id: i32, basis_points: i32, inclusive: bool) -> TaxRate {
+        TaxRate {
+            id,
+            name: format!("Tax {}", id),
+            basis_points,
+            inclusive,
+            service: None,
+            item_id: None,
+            category_id: Some(1),
+            active: true,
+        }
+    }
+
+    #[test]
+    fn test_taxes() {
+        let orders = [order(1, 1, 1000), order(2, 1, 500), order(3, 2, 300)];
+        let vat = rate(1, 1250, false);
+        let mut takeout = rate(2, 600, true);
+        takeout.service = Some(ServiceType::Takeout);
+        let mut exempt = rate(3, 0, false);
+        exempt.item_id = Some(2);
+        exempt.category_id = None;
+        let rates = [vat, takeout, exempt];
+
+        let amounts = |service, discount| -> Vec<(i32, i64)> {
+            taxes(
+                &rates,
+                &orders,
+                service,
+                Currency::Eur,
+                &Money::new(discount, Currency::Eur),
+            )
+            .unwrap()
+            .iter()
+            .map(|tax| (tax.tax_rate_id, tax.amount.amount))
+            .collect()
+        };
+        assert_eq!(amounts(ServiceType::DineIn, 0), vec![(1, 125), (3, 0)]);
+        assert_eq!(
+            amounts(ServiceType::Takeout, 0),
+            vec![(1, 125), (2, 57), (3, 0)]
+        );
+        // A fifth off the bill leaves a fifth less to tax.
+        assert_eq!(amounts(ServiceType::DineIn, -360)[0], (1, 100));
+    }
+}
@@ -0,0 +1,42 @@
+//! Ticket
+use super::{table::Table, tickets};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Order header, grouping the order lines taken for a table in one go.
+#[derive(
+    Identifiable,
+    Selectable,
+    Queryable,
+    Associations,
+    Clone,
+    Debug,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(table_name = tickets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(belongs_to(Table))]
+pub(crate) struct Ticket {
+    pub(crate) id: i32,
+    pub(crate) published_at: String,
+    #[serde(skip_serializing)]
+    pub(crate) table_id: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = tickets)]
+pub struct NewTicket<'a> {
+    pub(crate) table_id: &'a i32,
+    pub(crate) published_at: &'a String,
+}
+
+/// A single line of a ticket to be created.
+#[derive(Debug)]
+pub(crate) struct NewTicketLine {
+    pub(crate) item_id: i32,
+    pub(crate) quantity: i32,
+    pub(crate) modifiers: Vec<i32>,
+}
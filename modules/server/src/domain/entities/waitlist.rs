@@ -0,0 +1,175 @@
+//! Waitlist
+use std::fmt;
+use std::str::FromStr;
+
+use super::waitlist_entries;
+use chrono::{DateTime, Duration, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Whether a party is still waiting for a table.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WaitlistStatus {
+    Waiting,
+    Seated,
+    Removed,
+}
+
+impl WaitlistStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            WaitlistStatus::Waiting => "waiting",
+            WaitlistStatus::Seated => "seated",
+            WaitlistStatus::Removed => "removed",
+        }
+    }
+}
+
+impl fmt::Display for WaitlistStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WaitlistStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "waiting" => Ok(WaitlistStatus::Waiting),
+            "seated" => Ok(WaitlistStatus::Seated),
+            "removed" => Ok(WaitlistStatus::Removed),
+            other => Err(format!("Unknown waitlist status {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for WaitlistStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for WaitlistStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let status = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(status.parse()?)
+    }
+}
+
+/// A walk-in party in the queue for a table.
+#[derive(Identifiable, Selectable, Queryable, Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[diesel(table_name = waitlist_entries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub(crate) struct WaitlistEntry {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) party_size: i32,
+    /// Phone number or pager label to call the party by.
+    pub(crate) contact: Option<String>,
+    pub(crate) status: WaitlistStatus,
+    /// Wait the party was told when it joined the queue.
+    pub(crate) quoted_minutes: i32,
+    pub(crate) created_at: DateTime<Utc>,
+    /// When the party was seated or removed.
+    pub(crate) left_at: Option<DateTime<Utc>>,
+    /// Table session the party was seated at.
+    pub(crate) table_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = waitlist_entries)]
+pub(crate) struct NewWaitlistEntry<'a> {
+    pub(crate) name: &'a String,
+    pub(crate) party_size: &'a i32,
+    pub(crate) contact: Option<&'a String>,
+}
+
+/// Average time a party stays at a table, from check-in to checkout.
+/// The `fallback` is used without any sessions to go by.
+pub(crate) fn turn_time(
+    sessions: &[(DateTime<Utc>, DateTime<Utc>)],
+    fallback: Duration,
+) -> Duration {
+    let Some(count) = i32::try_from(sessions.len())
+        .ok()
+        .filter(|count| *count > 0)
+    else {
+        return fallback;
+    };
+    let stayed: Duration = sessions
+        .iter()
+        .map(|(opened_at, closed_at)| *closed_at - *opened_at)
+        .sum();
+    stayed / count
+}
+
+/// Estimate how many minutes each party in the queue waits for a table, in queue order.
+/// A table is given as its seats and when it is free, a party is seated at the table that
+/// seats it and is free the soonest, the smallest one if several are. The table is then free
+/// again once the party stayed for `turn`. `None` for a party no table seats.
+pub(crate) fn estimate_waits(
+    tables: &[(i32, DateTime<Utc>)],
+    parties: &[i32],
+    turn: Duration,
+    now: DateTime<Utc>,
+) -> Vec<Option<i64>> {
+    let mut free: Vec<(i32, DateTime<Utc>)> = tables
+        .iter()
+        .map(|(seats, free_at)| (*seats, *free_at.max(&now)))
+        .collect();
+    parties
+        .iter()
+        .map(|size| {
+            let (_, free_at) = free
+                .iter_mut()
+                .filter(|(seats, _)| seats >= size)
+                .min_by_key(|(seats, free_at)| (*free_at, *seats))?;
+            let wait = (*free_at - now).num_minutes();
+            *free_at += turn;
+            Some(wait)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_time_is_average_stay() {
+        let now = Utc::now();
+        let sessions = [
+            (now - Duration::minutes(90), now - Duration::minutes(30)),
+            (now - Duration::minutes(40), now),
+        ];
+        assert_eq!(
+            turn_time(&sessions, Duration::minutes(45)),
+            Duration::minutes(50)
+        );
+        assert_eq!(turn_time(&[], Duration::minutes(45)), Duration::minutes(45));
+    }
+
+    #[test]
+    fn waits_follow_the_queue() {
+        let now = Utc::now();
+        let tables = [
+            (2, now - Duration::minutes(5)),
+            (4, now + Duration::minutes(20)),
+            (6, now + Duration::minutes(40)),
+        ];
+        let waits = estimate_waits(&tables, &[2, 2, 4, 5, 8], Duration::minutes(60), now);
+        // The free table for two, then the table for four as it is free sooner.
+        assert_eq!(waits, vec![Some(0), Some(20), Some(40), Some(100), None]);
+    }
+}
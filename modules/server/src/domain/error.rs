@@ -0,0 +1,187 @@
+//! Domain errors
+use diesel::r2d2::PoolError;
+use diesel::result::{DatabaseErrorKind, Error};
+use log::error;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Broad category of an error, decides how it is reported to clients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorKind {
+    NotFound,
+    Unauthorized,
+    Forbidden,
+    Conflict,
+    Unprocessable,
+    TooManyRequests,
+    Unavailable,
+    Internal,
+}
+
+/// Machine readable error code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorCode {
+    NotFound,
+    ItemNotFound,
+    TableNotFound,
+    OrderNotFound,
+    BillPartNotFound,
+    PaymentNotFound,
+    PromotionNotFound,
+    TaxRateNotFound,
+    IngredientNotFound,
+    DiningTableNotFound,
+    ReservationNotFound,
+    PartyNotFound,
+    StaffNotFound,
+    Unauthenticated,
+    InvalidCredentials,
+    Forbidden,
+    Conflict,
+    TableOccupied,
+    TableClosed,
+    BillAlreadySplit,
+    BillNotSplit,
+    BillPartPaid,
+    PaymentVoided,
+    PaymentSettled,
+    InsufficientPayment,
+    CouponRedeemed,
+    ItemUnavailable,
+    ItemArchived,
+    TableInactive,
+    TableBooked,
+    ReservationClosed,
+    PartyNotWaiting,
+    IllegalStatusTransition,
+    IdempotencyKeyInFlight,
+    InvalidRequest,
+    InvalidQuantity,
+    InvalidAmount,
+    InvalidReference,
+    UnknownItem,
+    UnknownTable,
+    PartyTooLarge,
+    InvalidModifiers,
+    CurrencyMismatch,
+    AmountOverflow,
+    IdempotencyKeyReused,
+    AccountLocked,
+    DatabaseUnavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    pub(crate) fn kind(&self) -> ErrorKind {
+        match self {
+            ErrorCode::NotFound
+            | ErrorCode::ItemNotFound
+            | ErrorCode::TableNotFound
+            | ErrorCode::OrderNotFound
+            | ErrorCode::BillPartNotFound
+            | ErrorCode::PaymentNotFound
+            | ErrorCode::PromotionNotFound
+            | ErrorCode::TaxRateNotFound
+            | ErrorCode::IngredientNotFound
+            | ErrorCode::DiningTableNotFound
+            | ErrorCode::ReservationNotFound
+            | ErrorCode::PartyNotFound
+            | ErrorCode::StaffNotFound => ErrorKind::NotFound,
+            ErrorCode::Unauthenticated | ErrorCode::InvalidCredentials => ErrorKind::Unauthorized,
+            ErrorCode::Forbidden => ErrorKind::Forbidden,
+            ErrorCode::Conflict
+            | ErrorCode::TableOccupied
+            | ErrorCode::TableClosed
+            | ErrorCode::BillAlreadySplit
+            | ErrorCode::BillNotSplit
+            | ErrorCode::BillPartPaid
+            | ErrorCode::PaymentVoided
+            | ErrorCode::PaymentSettled
+            | ErrorCode::InsufficientPayment
+            | ErrorCode::CouponRedeemed
+            | ErrorCode::ItemUnavailable
+            | ErrorCode::ItemArchived
+            | ErrorCode::TableInactive
+            | ErrorCode::TableBooked
+            | ErrorCode::ReservationClosed
+            | ErrorCode::PartyNotWaiting
+            | ErrorCode::IllegalStatusTransition
+            | ErrorCode::IdempotencyKeyInFlight => ErrorKind::Conflict,
+            ErrorCode::InvalidRequest
+            | ErrorCode::InvalidQuantity
+            | ErrorCode::InvalidAmount
+            | ErrorCode::InvalidReference
+            | ErrorCode::UnknownItem
+            | ErrorCode::UnknownTable
+            | ErrorCode::PartyTooLarge
+            | ErrorCode::InvalidModifiers
+            | ErrorCode::CurrencyMismatch
+            | ErrorCode::AmountOverflow
+            | ErrorCode::IdempotencyKeyReused => ErrorKind::Unprocessable,
+            ErrorCode::AccountLocked => ErrorKind::TooManyRequests,
+            ErrorCode::DatabaseUnavailable => ErrorKind::Unavailable,
+            ErrorCode::Internal => ErrorKind::Internal,
+        }
+    }
+}
+
+/// Error returned by every fallible operation of the application.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ApiError {
+    pub(crate) code: ErrorCode,
+    pub(crate) error: String,
+}
+
+pub(crate) type ServerResult<T = ()> = Result<T, ApiError>;
+
+/// Raised by the database when writing to a closed table session.
+const TABLE_SESSION_CLOSED: &str = "table session is closed";
+
+impl ApiError {
+    pub(crate) fn new(code: ErrorCode, error: impl Into<String>) -> Self {
+        ApiError {
+            code,
+            error: error.into(),
+        }
+    }
+
+    /// Classify a database error, `not_found` is used when no row matched the query.
+    pub(crate) fn from_db(err: Error, not_found: ErrorCode, error: impl Into<String>) -> Self {
+        let code = match &err {
+            Error::NotFound => not_found,
+            Error::DatabaseError(DatabaseErrorKind::CheckViolation, info)
+                if info.message() == TABLE_SESSION_CLOSED =>
+            {
+                ErrorCode::TableClosed
+            }
+            Error::DatabaseError(kind, _) => match kind {
+                DatabaseErrorKind::UniqueViolation => ErrorCode::Conflict,
+                DatabaseErrorKind::ForeignKeyViolation => ErrorCode::InvalidReference,
+                DatabaseErrorKind::NotNullViolation | DatabaseErrorKind::CheckViolation => {
+                    ErrorCode::InvalidRequest
+                }
+                DatabaseErrorKind::ClosedConnection => ErrorCode::DatabaseUnavailable,
+                _ => ErrorCode::Internal,
+            },
+            _ => ErrorCode::Internal,
+        };
+        if code != not_found {
+            error!("{:?}", err);
+        }
+        ApiError::new(code, error)
+    }
+
+    /// Unable to get a database connection.
+    pub(crate) fn unavailable(err: PoolError) -> Self {
+        error!("{:?}", err);
+        ApiError::new(ErrorCode::DatabaseUnavailable, "Database is unavailable!")
+    }
+}
+
+/// Lets `?` be used on queries inside transactions that fail with an `ApiError`.
+impl From<Error> for ApiError {
+    fn from(err: Error) -> Self {
+        ApiError::from_db(err, ErrorCode::NotFound, "Database query failed!")
+    }
+}
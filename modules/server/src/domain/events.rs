@@ -0,0 +1,31 @@
+//! Kitchen events
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::entities::{
+    order::{Order, OrderStatus},
+    table::Table,
+};
+use super::money::Money;
+
+/// Events pushed to the kitchen display whenever orders or tables change.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum KitchenEvent {
+    /// A new order was placed for a table.
+    OrderCreated { table_number: i32, order: Order },
+    /// An order moved along its lifecycle.
+    OrderStatusChanged { order_id: i32, status: OrderStatus },
+    /// An order was removed.
+    OrderDeleted { order_id: i32, item_id: i32 },
+    /// A table was checked in.
+    TableCheckedIn { table: Table },
+    /// A table was checked out.
+    TableCheckedOut { table_number: i32, total: Money },
+    /// A party moved to another table, with everything it ordered.
+    TableTransferred { from: i32, to: i32 },
+    /// The party at `merged` joined the one at `table_number`, with everything it ordered.
+    TablesMerged { table_number: i32, merged: i32 },
+    /// The display fell behind and missed events, it should refetch its state.
+    Resync { missed: u64 },
+}
@@ -1 +1,5 @@
 pub(crate) mod entities;
+pub(crate) mod error;
+pub(crate) mod events;
+pub(crate) mod money;
+pub(crate) mod page;
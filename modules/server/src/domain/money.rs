@@ -0,0 +1,381 @@
+//! Money
+use std::fmt;
+use std::str::FromStr;
+
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use diesel::deserialize::{self, FromSql, FromSqlRow, Queryable};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::{BigInt, Nullable, Text};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// ISO 4217 currency of an amount.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "UPPERCASE")]
+pub(crate) enum Currency {
+    Eur,
+    Usd,
+    Gbp,
+    Sek,
+    Nok,
+    Dkk,
+    Chf,
+    Jpy,
+}
+
+impl Currency {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+            Currency::Gbp => "GBP",
+            Currency::Sek => "SEK",
+            Currency::Nok => "NOK",
+            Currency::Dkk => "DKK",
+            Currency::Chf => "CHF",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    /// Digits after the decimal point, a minor unit is a cent for most currencies.
+    pub(crate) fn minor_units(&self) -> u32 {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EUR" => Ok(Currency::Eur),
+            "USD" => Ok(Currency::Usd),
+            "GBP" => Ok(Currency::Gbp),
+            "SEK" => Ok(Currency::Sek),
+            "NOK" => Ok(Currency::Nok),
+            "DKK" => Ok(Currency::Dkk),
+            "CHF" => Ok(Currency::Chf),
+            "JPY" => Ok(Currency::Jpy),
+            other => Err(format!("Unknown currency {:?}", other)),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for Currency {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for Currency {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let currency = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        Ok(currency.parse()?)
+    }
+}
+
+/// How an amount that falls between two minor units is rounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Rounding {
+    /// Towards zero.
+    Down,
+    /// To the nearest, halves away from zero.
+    HalfUp,
+    /// To the nearest, halves to the even neighbour.
+    HalfEven,
+}
+
+impl Rounding {
+    /// Divide by a positive `d`.
+    fn divide(&self, n: i128, d: i128) -> i128 {
+        let (quotient, remainder) = (n.abs() / d, n.abs() % d);
+        let up = match self {
+            Rounding::Down => false,
+            Rounding::HalfUp => 2 * remainder >= d,
+            Rounding::HalfEven => 2 * remainder > d || (2 * remainder == d && quotient % 2 == 1),
+        };
+        n.signum() * (quotient + i128::from(up))
+    }
+}
+
+/// An amount of money, in minor units of its currency.
+/// Arithmetic is checked, it fails rather than overflow or mix currencies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub(crate) struct Money {
+    /// Minor units, cents for `EUR`.
+    pub(crate) amount: i64,
+    pub(crate) currency: Currency,
+}
+
+/// An amount or count that does not fit, e.g. when adding up a bill.
+pub(crate) fn overflow() -> ApiError {
+    ApiError::new(ErrorCode::AmountOverflow, "Amount is out of range!")
+}
+
+impl Money {
+    pub(crate) fn new(amount: i64, currency: Currency) -> Self {
+        Money { amount, currency }
+    }
+
+    pub(crate) fn zero(currency: Currency) -> Self {
+        Money::new(0, currency)
+    }
+
+    pub(crate) fn is_negative(&self) -> bool {
+        self.amount < 0
+    }
+
+    pub(crate) fn is_positive(&self) -> bool {
+        self.amount > 0
+    }
+
+    /// Fail unless `other` is in the same currency.
+    pub(crate) fn same_currency(&self, other: &Money) -> ServerResult {
+        if self.currency != other.currency {
+            return Err(ApiError::new(
+                ErrorCode::CurrencyMismatch,
+                format!(
+                    "Unable to combine {} with {}!",
+                    self.currency, other.currency
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn checked_add(&self, other: &Money) -> ServerResult<Money> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_add(other.amount).ok_or_else(overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    pub(crate) fn checked_sub(&self, other: &Money) -> ServerResult<Money> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_sub(other.amount).ok_or_else(overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    pub(crate) fn checked_mul(&self, factor: i64) -> ServerResult<Money> {
+        let amount = self.amount.checked_mul(factor).ok_or_else(overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    pub(crate) fn checked_neg(&self) -> ServerResult<Money> {
+        let amount = self.amount.checked_neg().ok_or_else(overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    /// Multiply by `numerator / denominator`, rounding the result to a minor unit.
+    pub(crate) fn ratio(
+        &self,
+        numerator: i64,
+        denominator: i64,
+        rounding: Rounding,
+    ) -> ServerResult<Money> {
+        if denominator <= 0 {
+            return Err(ApiError::new(
+                ErrorCode::InvalidAmount,
+                "Unable to divide an amount by zero or less!",
+            ));
+        }
+        let amount = rounding.divide(
+            i128::from(self.amount) * i128::from(numerator),
+            i128::from(denominator),
+        );
+        Ok(Money::new(
+            i64::try_from(amount).map_err(|_| overflow())?,
+            self.currency,
+        ))
+    }
+
+    /// Hundredths of a percent of the amount, 1250 is 12.5%.
+    pub(crate) fn basis_points(
+        &self,
+        basis_points: i64,
+        rounding: Rounding,
+    ) -> ServerResult<Money> {
+        self.ratio(basis_points, 10_000, rounding)
+    }
+
+    /// Add up amounts of the given currency.
+    pub(crate) fn sum<'a>(
+        currency: Currency,
+        amounts: impl IntoIterator<Item = &'a Money>,
+    ) -> ServerResult<Money> {
+        amounts
+            .into_iter()
+            .try_fold(Money::zero(currency), |sum, amount| sum.checked_add(amount))
+    }
+
+    /// Share the amount in proportion to `weights`, every share falls on a minor unit.
+    /// What is left after rounding down goes one unit at a time to the largest remainders,
+    /// the earliest share first on ties, so the shares always add up to the amount.
+    pub(crate) fn allocate(&self, weights: &[i64]) -> Vec<Money> {
+        let weights: Vec<i128> = if weights.iter().any(|w| *w > 0) {
+            weights.iter().map(|w| i128::from(*w).max(0)).collect()
+        } else {
+            vec![1; weights.len()]
+        };
+        let sum: i128 = weights.iter().sum();
+        let total = i128::from(self.amount);
+        let mut shares: Vec<i128> = weights
+            .iter()
+            .map(|w| (total * w).div_euclid(sum))
+            .collect();
+        let mut order: Vec<usize> = (0..weights.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse((total * weights[*i]).rem_euclid(sum)));
+        let left = total - shares.iter().sum::<i128>();
+        for i in order.into_iter().take(left as usize) {
+            shares[i] += 1;
+        }
+        shares
+            .into_iter()
+            .map(|share| Money::new(share as i64, self.currency))
+            .collect()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.currency.minor_units();
+        let scale = 10_u64.pow(digits);
+        let sign = if self.is_negative() { "-" } else { "" };
+        let (units, minor) = (
+            self.amount.unsigned_abs() / scale,
+            self.amount.unsigned_abs() % scale,
+        );
+        if digits == 0 {
+            write!(f, "{}{} {}", sign, units, self.currency)
+        } else {
+            write!(
+                f,
+                "{}{}.{:0width$} {}",
+                sign,
+                units,
+                minor,
+                self.currency,
+                width = digits as usize
+            )
+        }
+    }
+}
+
+/// An amount column read together with the currency column of its row.
+impl Queryable<(BigInt, Text), Pg> for Money {
+    type Row = (i64, Currency);
+
+    fn build((amount, currency): Self::Row) -> deserialize::Result<Self> {
+        Ok(Money::new(amount, currency))
+    }
+}
+
+/// An amount that may not be set yet, read together with the currency of its row.
+pub(crate) struct MaybeMoney(Option<Money>);
+
+impl Queryable<(Nullable<BigInt>, Text), Pg> for MaybeMoney {
+    type Row = (Option<i64>, Currency);
+
+    fn build((amount, currency): Self::Row) -> deserialize::Result<Self> {
+        Ok(MaybeMoney(
+            amount.map(|amount| Money::new(amount, currency)),
+        ))
+    }
+}
+
+impl Queryable<(Nullable<BigInt>, Nullable<Text>), Pg> for MaybeMoney {
+    type Row = (Option<i64>, Option<Currency>);
+
+    fn build(row: Self::Row) -> deserialize::Result<Self> {
+        Ok(MaybeMoney(match row {
+            (Some(amount), Some(currency)) => Some(Money::new(amount, currency)),
+            _ => None,
+        }))
+    }
+}
+
+impl From<MaybeMoney> for Option<Money> {
+    fn from(money: MaybeMoney) -> Self {
+        money.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur(amount: i64) -> Money {
+        Money::new(amount, Currency::Eur)
+    }
+
+    #[test]
+    fn test_money_arithmetic() {
+        assert_eq!(eur(150).checked_add(&eur(250)).unwrap(), eur(400));
+        assert_eq!(eur(150).checked_sub(&eur(250)).unwrap(), eur(-100));
+        assert_eq!(eur(150).checked_mul(3).unwrap(), eur(450));
+        assert_eq!(
+            eur(1)
+                .checked_add(&Money::new(1, Currency::Usd))
+                .unwrap_err()
+                .code,
+            ErrorCode::CurrencyMismatch
+        );
+        assert_eq!(
+            eur(i64::MAX).checked_add(&eur(1)).unwrap_err().code,
+            ErrorCode::AmountOverflow
+        );
+        assert_eq!(
+            eur(i64::MAX).checked_mul(2).unwrap_err().code,
+            ErrorCode::AmountOverflow
+        );
+        assert_eq!(eur(-1250).to_string(), "-12.50 EUR");
+        assert_eq!(Money::new(1250, Currency::Jpy).to_string(), "1250 JPY");
+    }
+
+    #[test]
+    fn test_money_rounding() {
+        let half = |amount: i64, rounding| eur(amount).ratio(1, 2, rounding).unwrap().amount;
+        assert_eq!(half(5, Rounding::Down), 2);
+        assert_eq!(half(5, Rounding::HalfUp), 3);
+        assert_eq!(half(5, Rounding::HalfEven), 2);
+        assert_eq!(half(7, Rounding::HalfEven), 4);
+        assert_eq!(half(-5, Rounding::HalfUp), -3);
+        assert_eq!(half(-5, Rounding::Down), -2);
+        assert_eq!(
+            eur(1000).basis_points(1250, Rounding::HalfUp).unwrap(),
+            eur(125)
+        );
+    }
+
+    #[test]
+    fn test_money_allocate() {
+        let amounts = |total: i64, weights: &[i64]| -> Vec<i64> {
+            eur(total)
+                .allocate(weights)
+                .iter()
+                .map(|m| m.amount)
+                .collect()
+        };
+        assert_eq!(amounts(100, &[1, 1, 1]), vec![34, 33, 33]);
+        assert_eq!(amounts(101, &[1, 1, 1]), vec![34, 34, 33]);
+        assert_eq!(amounts(10, &[1, 2, 2]), vec![2, 4, 4]);
+        assert_eq!(amounts(7, &[1, 1, 2]), vec![2, 2, 3]);
+        assert_eq!(amounts(5, &[0, 0]), vec![3, 2]);
+        for parts in 1..20 {
+            assert_eq!(amounts(1999, &vec![1; parts]).iter().sum::<i64>(), 1999);
+        }
+    }
+}
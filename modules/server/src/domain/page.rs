@@ -0,0 +1,220 @@
+//! Pages of collections
+use std::fmt;
+use std::str::FromStr;
+
+use crate::application::config::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::domain::error::{ApiError, ErrorCode, ServerResult};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Field a collection is sorted by, descending when it is given with a `-` in front,
+/// e.g. `-opened_at`. Entries sorted the same are sorted by id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Sort<F> {
+    pub(crate) field: F,
+    pub(crate) descending: bool,
+}
+
+impl<F> Sort<F> {
+    pub(crate) fn asc(field: F) -> Self {
+        Sort {
+            field,
+            descending: false,
+        }
+    }
+
+    pub(crate) fn desc(field: F) -> Self {
+        Sort {
+            field,
+            descending: true,
+        }
+    }
+}
+
+impl<F: fmt::Display> fmt::Display for Sort<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.descending {
+            f.write_str("-")?;
+        }
+        self.field.fmt(f)
+    }
+}
+
+impl<F: FromStr<Err = String>> FromStr for Sort<F> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('-') {
+            Some(field) => Ok(Sort::desc(field.parse()?)),
+            None => Ok(Sort::asc(s.parse()?)),
+        }
+    }
+}
+
+/// Where a page ended, the next one starts after the entry with this sort value and id.
+/// It is handed out encoded, clients pass it back as they got it.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub(crate) struct Cursor {
+    /// Sort the cursor was made for, it is no good for another one.
+    pub(crate) sort: String,
+    pub(crate) value: Value,
+    pub(crate) id: i32,
+}
+
+impl Cursor {
+    pub(crate) fn new<F: fmt::Display, T: Serialize>(sort: &Sort<F>, value: &T, id: i32) -> Self {
+        Cursor {
+            sort: sort.to_string(),
+            value: serde_json::to_value(value).unwrap_or_default(),
+            id,
+        }
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub(crate) fn decode(cursor: &str) -> ServerResult<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| invalid_cursor(cursor))
+    }
+
+    /// Sort value of the entry the page ended with.
+    pub(crate) fn value<T: DeserializeOwned>(&self) -> ServerResult<T> {
+        serde_json::from_value(self.value.clone())
+            .map_err(|_| ApiError::new(ErrorCode::InvalidRequest, "Invalid page cursor!"))
+    }
+}
+
+fn invalid_cursor(cursor: &str) -> ApiError {
+    ApiError::new(
+        ErrorCode::InvalidRequest,
+        format!("Invalid page cursor {:?}!", cursor),
+    )
+}
+
+/// Which page of a collection to get.
+#[derive(Debug)]
+pub(crate) struct PageRequest<F> {
+    pub(crate) limit: i64,
+    pub(crate) sort: Sort<F>,
+    /// Where the previous page ended, the first page is got without.
+    pub(crate) after: Option<Cursor>,
+}
+
+impl<F: fmt::Display + FromStr<Err = String>> PageRequest<F> {
+    /// Parse the page requested, `sort` is used when none is given.
+    pub(crate) fn parse(
+        limit: Option<i64>,
+        sort: Option<&String>,
+        cursor: Option<&String>,
+        default: Sort<F>,
+    ) -> ServerResult<Self> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!("A page holds 1 to {} entries!", MAX_PAGE_SIZE),
+            ));
+        }
+        let sort = match sort {
+            Some(sort) => sort
+                .parse()
+                .map_err(|error: String| ApiError::new(ErrorCode::InvalidRequest, error))?,
+            None => default,
+        };
+        let after = cursor.map(|cursor| Cursor::decode(cursor)).transpose()?;
+        if let Some(after) = &after {
+            if after.sort != sort.to_string() {
+                return Err(ApiError::new(
+                    ErrorCode::InvalidRequest,
+                    format!("The page cursor is for sort {:?}!", after.sort),
+                ));
+            }
+        }
+        Ok(PageRequest { limit, sort, after })
+    }
+
+    /// Cut a page from the entries loaded for it, one more than the limit is loaded
+    /// to tell whether there is a next page.
+    pub(crate) fn page<T>(&self, mut entries: Vec<T>, cursor: impl Fn(&T) -> Cursor) -> Page<T> {
+        let more = entries.len() as i64 > self.limit;
+        entries.truncate(self.limit as usize);
+        Page {
+            next: entries.last().filter(|_| more).map(cursor),
+            entries,
+        }
+    }
+}
+
+/// A page of a collection, with where the next page starts if there is one.
+#[derive(Debug)]
+pub(crate) struct Page<T> {
+    pub(crate) entries: Vec<T>,
+    pub(crate) next: Option<Cursor>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Field;
+
+    impl fmt::Display for Field {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("field")
+        }
+    }
+
+    impl FromStr for Field {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "field" => Ok(Field),
+                other => Err(format!("Unknown sort {:?}", other)),
+            }
+        }
+    }
+
+    #[test]
+    fn sort_by_field() {
+        assert_eq!("-field".parse::<Sort<Field>>(), Ok(Sort::desc(Field)));
+        assert_eq!("field".parse::<Sort<Field>>(), Ok(Sort::asc(Field)));
+        assert!("other".parse::<Sort<Field>>().is_err());
+        assert_eq!(Sort::desc(Field).to_string(), "-field");
+    }
+
+    #[test]
+    fn pages_follow_cursors() {
+        let first = PageRequest::parse(Some(2), None, None, Sort::asc(Field)).unwrap();
+        let page = first.page(vec![1, 2, 3], |n| Cursor::new(&first.sort, n, *n));
+        assert_eq!(page.entries, vec![1, 2]);
+        let next = page.next.expect("Unable to find next page").encode();
+        let second = PageRequest::parse(Some(2), None, Some(&next), Sort::asc(Field)).unwrap();
+        let after = second.after.as_ref().expect("Unable to read cursor");
+        assert_eq!((after.value::<i32>().unwrap(), after.id), (2, 2));
+        let page = second.page(vec![3], |n| Cursor::new(&second.sort, n, *n));
+        assert!(page.next.is_none());
+        // A cursor is only good for the sort it was made for.
+        let sorted = PageRequest::parse(
+            None,
+            Some(&"-field".to_string()),
+            Some(&next),
+            Sort::asc(Field),
+        );
+        assert_eq!(sorted.unwrap_err().code, ErrorCode::InvalidRequest);
+        assert!(PageRequest::parse(Some(0), None, None, Sort::asc(Field)).is_err());
+        assert!(
+            PageRequest::<Field>::parse(None, None, Some(&"x".to_string()), Sort::asc(Field))
+                .is_err()
+        );
+    }
+}
@@ -1,12 +1,14 @@
 //! infrastructure/server.rs
 //! Server module
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use log::info;
 use std::env::var;
 use tokio::net::TcpListener;
 
 use crate::adapters::routes::routes;
 use crate::adapters::state::ServerState;
-use crate::application::config::{HOST_PORT, HOST_URL};
+use crate::application::config::{ADMIN_NAME, HOST_PORT, HOST_URL};
+use crate::application::repo::StaffRepository;
 
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
@@ -25,9 +27,21 @@ impl Server {
         let host = var("HOST_URL").unwrap_or_else(|_| HOST_URL.to_string());
         let port = var("HOST_PORT").unwrap_or_else(|_| HOST_PORT.to_string());
         let listener = TcpListener::bind(format!("{}:{}", host, port)).await?; // TODO read me from config.
+        let state = ServerState::new(pool)?;
+        // Without an admin nobody could create staff accounts.
+        if let Some(secret) = var("ADMIN_SECRET").ok().filter(|secret| !secret.is_empty()) {
+            let name = var("ADMIN_NAME").unwrap_or_else(|_| ADMIN_NAME.to_string());
+            if let Some(admin) = state
+                .staff_repository
+                .bootstrap(&name, &secret)
+                .map_err(|err| anyhow!(err.error))?
+            {
+                info!("Created admin account {}", admin.name);
+            }
+        }
         Ok(Server {
             // TODO do something useful here ;)
-            state: ServerState::new(pool)?,
+            state,
             socket: listener,
         })
     }